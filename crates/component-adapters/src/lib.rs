@@ -0,0 +1,138 @@
+//! Adaptation of WASI preview 1 (`wasip1`) core modules to WASI preview 2 (`wasip2`) components
+
+use anyhow::Context as _;
+use wasmparser::{Parser, Payload};
+use wit_component::ComponentEncoder;
+
+pub use wasi_preview1_component_adapter_provider::{
+    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER,
+    WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
+};
+
+/// Returns whether `wasm` is a core WebAssembly module, as opposed to a component
+#[must_use]
+pub fn is_core_module(wasm: impl AsRef<[u8]>) -> bool {
+    Parser::is_core_wasm(wasm.as_ref())
+}
+
+/// Returns whether the core module `wasm` is a command, i.e. exports a `_start` function
+///
+/// # Errors
+///
+/// Fails if `wasm` is not a valid core module
+pub fn is_command_module(wasm: impl AsRef<[u8]>) -> anyhow::Result<bool> {
+    for payload in Parser::new(0).parse_all(wasm.as_ref()) {
+        if let Payload::ExportSection(exports) = payload.context("failed to parse module")? {
+            for export in exports {
+                let export = export.context("failed to parse module export")?;
+                if export.name == "_start" {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Returns the WASIP2 adapter for the core module `wasm`, which is the reactor adapter, unless
+/// `allow_command` is set and the module is a command, see [`is_command_module`]
+///
+/// # Errors
+///
+/// Fails if `wasm` is not a valid core module
+pub fn wasip2_adapter_for(
+    wasm: impl AsRef<[u8]>,
+    allow_command: bool,
+) -> anyhow::Result<&'static [u8]> {
+    if allow_command && is_command_module(wasm)? {
+        Ok(WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER)
+    } else {
+        Ok(WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER)
+    }
+}
+
+/// Adapt a core module/wasip2 component to a wasip2 wasm component
+/// returning the bytes that are the adapted wasm module
+///
+/// # Errors
+///
+/// Fails if `wasm` is not a valid core module or it cannot be adapted using `adapter`
+pub fn adapt_wasip1_component(
+    wasm: impl AsRef<[u8]>,
+    adapter: impl AsRef<[u8]>,
+) -> anyhow::Result<Vec<u8>> {
+    // Build a component encoder
+    let encoder = ComponentEncoder::default()
+        .validate(true)
+        .module(wasm.as_ref())
+        .context("failed to encode wasm component")?;
+
+    // Adapt the module
+    let mut encoder = encoder
+        .adapter(WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, adapter.as_ref())
+        .context("failed to set adapter during encoding")?;
+
+    // Return the encoded module bytes
+    encoder
+        .encode()
+        .context("failed to serialize encoded component")
+}
+
+/// Adapt a wasip1 core module to a wasip2 component using the adapter returned by
+/// [`wasip2_adapter_for`]
+///
+/// # Errors
+///
+/// Fails if `wasm` is not a valid core module or it cannot be adapted
+pub fn adapt_wasip1_module(wasm: impl AsRef<[u8]>, allow_command: bool) -> anyhow::Result<Vec<u8>> {
+    let wasm = wasm.as_ref();
+    let adapter = wasip2_adapter_for(wasm, allow_command)?;
+    adapt_wasip1_component(wasm, adapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapt_wasip1() -> anyhow::Result<()> {
+        let reactor = wat::parse_str(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+            )"#,
+        )?;
+        let command = wat::parse_str(
+            r#"(module
+                (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                (memory (export "memory") 1)
+                (func (export "_start"))
+            )"#,
+        )?;
+        assert!(is_core_module(&reactor));
+        assert!(!is_command_module(&reactor)?);
+        assert!(is_command_module(&command)?);
+        assert_eq!(
+            wasip2_adapter_for(&reactor, true)?,
+            WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER
+        );
+        // Commands are adapted using the reactor adapter, unless the command adapter is allowed
+        assert_eq!(
+            wasip2_adapter_for(&command, false)?,
+            WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER
+        );
+        assert_eq!(
+            wasip2_adapter_for(&command, true)?,
+            WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER
+        );
+        for wasm in [&reactor, &command] {
+            for allow_command in [false, true] {
+                let component = adapt_wasip1_module(wasm, allow_command)?;
+                assert!(!is_core_module(&component));
+                assert!(Parser::is_component(&component));
+            }
+        }
+        assert!(adapt_wasip1_module(b"\0asm", false).is_err());
+        Ok(())
+    }
+}
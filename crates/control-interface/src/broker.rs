@@ -67,6 +67,17 @@ pub mod v1 {
         )
     }
 
+    pub fn rollback_config(
+        topic_prefix: &Option<String>,
+        lattice: &str,
+        config_name: &str,
+    ) -> String {
+        format!(
+            "{}.config.rollback.{config_name}",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
     pub fn put_label(topic_prefix: &Option<String>, lattice: &str, host_id: &str) -> String {
         format!(
             "{}.label.put.{host_id}",
@@ -136,6 +147,13 @@ pub mod v1 {
                 prefix(topic_prefix, lattice, CTL_API_VERSION_1)
             )
         }
+
+        pub fn drain_host(topic_prefix: &Option<String>, lattice: &str, host_id: &str) -> String {
+            format!(
+                "{}.host.drain.{host_id}",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+            )
+        }
     }
 
     pub mod queries {
@@ -181,5 +199,16 @@ pub mod v1 {
                 prefix(topic_prefix, lattice, CTL_API_VERSION_1),
             )
         }
+
+        pub fn config_history(
+            topic_prefix: &Option<String>,
+            lattice: &str,
+            config_name: &str,
+        ) -> String {
+            format!(
+                "{}.config.history.{config_name}",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1),
+            )
+        }
     }
 }
@@ -12,6 +12,7 @@ use serde::de::DeserializeOwned;
 use tokio::sync::mpsc::Receiver;
 use tracing::{debug, error, instrument, trace};
 
+use crate::types::config::{ConfigRevision, ConfigRollbackRequest};
 use crate::types::ctl::{
     CtlResponse, ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
     UpdateComponentCommand,
@@ -21,7 +22,7 @@ use crate::types::link::Link;
 use crate::types::registry::RegistryCredential;
 use crate::types::rpc::{
     ComponentAuctionAck, ComponentAuctionRequest, DeleteInterfaceLinkDefinitionRequest,
-    ProviderAuctionAck, ProviderAuctionRequest,
+    HostCapacity, ProviderAuctionAck, ProviderAuctionRequest,
 };
 use crate::{broker, json_deserialize, json_serialize, otel, IdentifierKind, Result};
 
@@ -212,6 +213,9 @@ impl Client {
     /// _duration_, and then return the set of gathered results. It is then up to the client to
     /// choose from among the "auction winners" to issue the appropriate command to start an component.
     /// Clients cannot assume that auctions will always return at least one result.
+    ///
+    /// Results are ordered from the least to the most utilized host according to the
+    /// [`HostCapacity`] reported by the hosts, followed by hosts not reporting their capacity.
     #[instrument(level = "debug", skip_all)]
     pub async fn perform_component_auction(
         &self,
@@ -228,7 +232,10 @@ impl Client {
                 .build()?,
         )?;
         debug!("component_auction:publish {}", &subject);
-        self.publish_and_wait(subject, bytes).await
+        let mut acks: Vec<CtlResponse<ComponentAuctionAck>> =
+            self.publish_and_wait(subject, bytes).await?;
+        acks.sort_by_key(|ack| utilization(ack.data().and_then(ComponentAuctionAck::capacity)));
+        Ok(acks)
     }
 
     /// Performs a provider auction within the lattice, publishing a set of constraints and the
@@ -240,6 +247,9 @@ impl Client {
     ///
     /// Clients should not assume that auctions will always return at least one result.
     ///
+    /// Results are ordered from the least to the most utilized host according to the
+    /// [`HostCapacity`] reported by the hosts, followed by hosts not reporting their capacity.
+    ///
     /// # Arguments
     ///
     /// * `provider_ref` - The ID of the provider to auction
@@ -262,7 +272,10 @@ impl Client {
                 .build()?,
         )?;
         debug!("provider_auction:publish {}", &subject);
-        self.publish_and_wait(subject, bytes).await
+        let mut acks: Vec<CtlResponse<ProviderAuctionAck>> =
+            self.publish_and_wait(subject, bytes).await?;
+        acks.sort_by_key(|ack| utilization(ack.data().and_then(ProviderAuctionAck::capacity)));
+        Ok(acks)
     }
 
     /// Sends a request to the given host to scale a given component.
@@ -524,6 +537,67 @@ impl Client {
         }
     }
 
+    /// Get the revision history of the named config item.
+    ///
+    /// Hosts record a [`ConfigRevision`] whenever a config item is put, deleted or rolled back.
+    /// Only the most recent revisions are retained. Deleting a config item does not remove its
+    /// history, so past values remain available to callers permitted to get the history.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_name` - The name of the config to fetch the history of
+    ///
+    /// # Returns
+    ///
+    /// The revisions of the config item, ordered from oldest to newest. If no revisions were
+    /// recorded, the list is empty
+    ///
+    #[instrument(level = "debug", skip_all)]
+    pub async fn get_config_history(
+        &self,
+        config_name: &str,
+    ) -> Result<CtlResponse<Vec<ConfigRevision>>> {
+        let subject =
+            broker::v1::queries::config_history(&self.topic_prefix, &self.lattice, config_name);
+        debug!(%subject, %config_name, "Getting config history");
+        match self
+            .request_timeout(subject, Vec::default(), self.timeout)
+            .await
+        {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive a response to get config history request: {e}").into())
+            }
+        }
+    }
+
+    /// Roll the named config item back to the values of an earlier revision.
+    ///
+    /// The rollback is recorded as a new revision. It fails if the config item changes while it is
+    /// being rolled back, or if `revision` deleted the config item.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_name` - Name of the configuration that should be rolled back
+    /// * `revision` - The revision, whose values should be restored
+    ///
+    #[instrument(level = "debug", skip_all)]
+    pub async fn rollback_config(
+        &self,
+        config_name: &str,
+        revision: u64,
+    ) -> Result<CtlResponse<()>> {
+        let subject = broker::v1::rollback_config(&self.topic_prefix, &self.lattice, config_name);
+        debug!(%subject, %config_name, revision, "Rolling back config");
+        let data = json_serialize(ConfigRollbackRequest::new(revision))?;
+        match self.request_timeout(subject, data, self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive a response to rollback config request: {e}").into())
+            }
+        }
+    }
+
     /// Put a new (or update an existing) label on the given host.
     ///
     /// # Arguments
@@ -740,6 +814,40 @@ impl Client {
         }
     }
 
+    /// Issues a command to a specific host to drain and then stop.
+    ///
+    /// A draining host stops bidding on auctions, reports itself as not ready and stops accepting
+    /// new invocations. It waits for in-flight invocations to finish before stopping. The target host
+    /// will acknowledge receipt of the command before it starts draining.
+    ///
+    /// To track progress, a client should monitor for the "host draining" and "host stopped" events
+    ///
+    /// # Arguments
+    ///
+    /// * `host_id` - ID of the host to drain
+    /// * `timeout_ms` - (optional) amount of time to wait for in-flight invocations to finish
+    ///
+    #[instrument(level = "debug", skip_all)]
+    pub async fn drain_host(
+        &self,
+        host_id: &str,
+        timeout_ms: Option<u64>,
+    ) -> Result<CtlResponse<()>> {
+        let host_id = IdentifierKind::is_host_id(host_id)?;
+        let subject =
+            broker::v1::commands::drain_host(&self.topic_prefix, &self.lattice, host_id.as_str());
+        debug!("drain_host:request {}", &subject);
+        let bytes = json_serialize(StopHostCommand {
+            host_id,
+            timeout: timeout_ms,
+        })?;
+
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => Ok(json_deserialize(&msg.payload)?),
+            Err(e) => Err(format!("Did not receive drain host acknowledgement: {e}").into()),
+        }
+    }
+
     /// Publish a message and wait for a response
     async fn publish_and_wait<D: DeserializeOwned>(
         &self,
@@ -856,6 +964,12 @@ pub(crate) async fn collect_sub_timeout<T: DeserializeOwned>(
     items
 }
 
+/// Returns the key used to order auction acknowledgements by the utilization of the bidding host,
+/// ordering hosts not reporting their capacity last
+fn utilization(capacity: Option<&HostCapacity>) -> u32 {
+    capacity.map_or(u32::MAX, HostCapacity::utilization)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,6 +27,7 @@ pub use client::{Client, ClientBuilder};
 
 mod types;
 pub use types::component::*;
+pub use types::config::*;
 pub use types::ctl::*;
 pub use types::host::*;
 pub use types::link::*;
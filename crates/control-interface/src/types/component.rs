@@ -34,6 +34,11 @@ pub struct ComponentDescription {
     /// The maximum number of concurrent requests this instance can handle
     #[serde(default)]
     pub(crate) max_instances: u32,
+
+    /// The maximum amount of linear memory, in bytes, each instance of this component may use,
+    /// if restricted beyond the host limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_memory: Option<u64>,
 }
 
 #[derive(Default, Clone, PartialEq, Eq)]
@@ -45,6 +50,7 @@ pub struct ComponentDescriptionBuilder {
     annotations: Option<BTreeMap<String, String>>,
     revision: Option<i32>,
     max_instances: Option<u32>,
+    max_memory: Option<u64>,
 }
 
 impl ComponentDescriptionBuilder {
@@ -84,6 +90,12 @@ impl ComponentDescriptionBuilder {
         self
     }
 
+    #[must_use]
+    pub fn max_memory(mut self, v: u64) -> Self {
+        self.max_memory = Some(v);
+        self
+    }
+
     pub fn build(self) -> Result<ComponentDescription> {
         Ok(ComponentDescription {
             image_ref: self
@@ -94,6 +106,7 @@ impl ComponentDescriptionBuilder {
             revision: self.revision.unwrap_or_default(),
             max_instances: self.max_instances.unwrap_or_default(),
             annotations: self.annotations,
+            max_memory: self.max_memory,
         })
     }
 }
@@ -129,6 +142,11 @@ impl ComponentDescription {
         self.max_instances
     }
 
+    /// Get the maximum amount of linear memory, in bytes, each instance of the component may use
+    pub fn max_memory(&self) -> Option<u64> {
+        self.max_memory
+    }
+
     #[must_use]
     pub fn builder() -> ComponentDescriptionBuilder {
         ComponentDescriptionBuilder::default()
@@ -264,6 +282,7 @@ mod tests {
                 annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
                 revision: 0,
                 max_instances: 1,
+                max_memory: Some(1024),
             },
             ComponentDescription::builder()
                 .id("id".into())
@@ -273,6 +292,7 @@ mod tests {
                 .annotations(BTreeMap::from([("a".into(), "b".into())]))
                 .revision(0)
                 .max_instances(1)
+                .max_memory(1024)
                 .build()
                 .unwrap()
         )
@@ -0,0 +1,263 @@
+//! Data types used when managing the revision history of named config
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// A revision of a named config, recorded by the host whenever the config is put, deleted or
+/// rolled back
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ConfigRevision {
+    /// Number of the revision, which is the revision of the change in the config store and
+    /// increases with each change of the config
+    pub(crate) revision: u64,
+    /// Values of the config after the change, or `None` if the config was deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) values: Option<BTreeMap<String, String>>,
+    /// NATS user that made the change, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) author: Option<String>,
+    /// Time of the change, in RFC 3339 format
+    pub(crate) timestamp: String,
+    /// If the change was a rollback, the revision that was restored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rollback_of: Option<u64>,
+}
+
+impl ConfigRevision {
+    /// Get the number of the revision
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Get the values of the config after the change, if the config was not deleted
+    #[must_use]
+    pub fn values(&self) -> Option<&BTreeMap<String, String>> {
+        self.values.as_ref()
+    }
+
+    /// Get the NATS user that made the change
+    #[must_use]
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Get the time of the change, in RFC 3339 format
+    #[must_use]
+    pub fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    /// Get the revision restored by the change, if it was a rollback
+    #[must_use]
+    pub fn rollback_of(&self) -> Option<u64> {
+        self.rollback_of
+    }
+
+    /// Returns the changes of the config values from this revision to `other`, ordered by key.
+    /// Deleted configs are treated as empty
+    #[must_use]
+    pub fn diff(&self, other: &ConfigRevision) -> Vec<ConfigChange> {
+        let empty = BTreeMap::default();
+        let from = self.values.as_ref().unwrap_or(&empty);
+        let to = other.values.as_ref().unwrap_or(&empty);
+        let mut changes = Vec::new();
+        for (key, value) in from {
+            match to.get(key) {
+                None => changes.push(ConfigChange::Removed {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+                Some(new) if new != value => changes.push(ConfigChange::Changed {
+                    key: key.clone(),
+                    from: value.clone(),
+                    to: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (key, value) in to {
+            if !from.contains_key(key) {
+                changes.push(ConfigChange::Added {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.key().cmp(b.key()));
+        changes
+    }
+
+    #[must_use]
+    pub fn builder() -> ConfigRevisionBuilder {
+        ConfigRevisionBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct ConfigRevisionBuilder {
+    revision: Option<u64>,
+    values: Option<BTreeMap<String, String>>,
+    author: Option<String>,
+    timestamp: Option<String>,
+    rollback_of: Option<u64>,
+}
+
+impl ConfigRevisionBuilder {
+    #[must_use]
+    pub fn revision(mut self, v: u64) -> Self {
+        self.revision = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn values(mut self, v: BTreeMap<String, String>) -> Self {
+        self.values = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn author(mut self, v: String) -> Self {
+        self.author = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn timestamp(mut self, v: String) -> Self {
+        self.timestamp = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn rollback_of(mut self, v: u64) -> Self {
+        self.rollback_of = Some(v);
+        self
+    }
+
+    pub fn build(self) -> Result<ConfigRevision> {
+        Ok(ConfigRevision {
+            revision: self
+                .revision
+                .ok_or_else(|| "revision is required".to_string())?,
+            values: self.values,
+            author: self.author,
+            timestamp: self
+                .timestamp
+                .ok_or_else(|| "timestamp is required".to_string())?,
+            rollback_of: self.rollback_of,
+        })
+    }
+}
+
+/// A change of a single config value between two [`ConfigRevision`]s
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ConfigChange {
+    /// The key was added with `value`
+    Added { key: String, value: String },
+    /// The key holding `value` was removed
+    Removed { key: String, value: String },
+    /// The value of the key changed
+    Changed {
+        key: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl ConfigChange {
+    /// Get the key that changed
+    #[must_use]
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Added { key, .. } | Self::Removed { key, .. } | Self::Changed { key, .. } => key,
+        }
+    }
+}
+
+/// A request to roll a named config back to the values of an earlier revision
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ConfigRollbackRequest {
+    /// The revision to restore
+    pub(crate) revision: u64,
+}
+
+impl ConfigRollbackRequest {
+    /// Create a request to restore `revision`
+    #[must_use]
+    pub fn new(revision: u64) -> Self {
+        Self { revision }
+    }
+
+    /// Get the revision to restore
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{ConfigChange, ConfigRevision};
+
+    #[test]
+    fn config_revision_diff() {
+        let from = ConfigRevision::builder()
+            .revision(1)
+            .timestamp("2024-10-01T12:00:00.000Z".into())
+            .values(BTreeMap::from([
+                ("address".into(), "0.0.0.0:8080".into()),
+                ("cors_allowed_origins".into(), "*".into()),
+                ("timeout_ms".into(), "500".into()),
+            ]))
+            .build()
+            .unwrap();
+        let to = ConfigRevision::builder()
+            .revision(2)
+            .timestamp("2024-10-01T13:00:00.000Z".into())
+            .author("UADMIN".into())
+            .values(BTreeMap::from([
+                ("address".into(), "0.0.0.0:8081".into()),
+                ("allowed_origins".into(), "*".into()),
+                ("timeout_ms".into(), "500".into()),
+            ]))
+            .build()
+            .unwrap();
+        assert_eq!(
+            from.diff(&to),
+            [
+                ConfigChange::Changed {
+                    key: "address".into(),
+                    from: "0.0.0.0:8080".into(),
+                    to: "0.0.0.0:8081".into(),
+                },
+                ConfigChange::Added {
+                    key: "allowed_origins".into(),
+                    value: "*".into(),
+                },
+                ConfigChange::Removed {
+                    key: "cors_allowed_origins".into(),
+                    value: "*".into(),
+                },
+            ]
+        );
+        assert!(to.diff(&to).is_empty());
+
+        let deleted = ConfigRevision {
+            revision: 3,
+            ..Default::default()
+        };
+        assert_eq!(to.diff(&deleted).len(), 3);
+        assert!(deleted
+            .diff(&to)
+            .iter()
+            .all(|change| matches!(change, ConfigChange::Added { .. })));
+    }
+}
@@ -116,6 +116,14 @@ pub struct ScaleComponentCommand {
     /// Normally this is implemented by the receiver (ex. wasmcloud host) as a *separate* update component call
     /// being made shortly after this command (scale) is processed.
     pub(crate) allow_update: bool,
+    /// The maximum amount of fuel a single invocation of this component may consume. Only enforced
+    /// by hosts with fuel metering enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_fuel: Option<u64>,
+    /// The maximum amount of linear memory, in bytes, a single instance of this component may use.
+    /// Hosts do not allow this to exceed their own linear memory limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_memory: Option<u64>,
 }
 
 impl ScaleComponentCommand {
@@ -154,6 +162,16 @@ impl ScaleComponentCommand {
         &self.host_id
     }
 
+    #[must_use]
+    pub fn max_fuel(&self) -> Option<u64> {
+        self.max_fuel
+    }
+
+    #[must_use]
+    pub fn max_memory(&self) -> Option<u64> {
+        self.max_memory
+    }
+
     #[must_use]
     pub fn builder() -> ScaleComponentCommandBuilder {
         ScaleComponentCommandBuilder::default()
@@ -171,6 +189,8 @@ pub struct ScaleComponentCommandBuilder {
     host_id: Option<String>,
     config: Option<Vec<String>>,
     allow_update: Option<bool>,
+    max_fuel: Option<u64>,
+    max_memory: Option<u64>,
 }
 
 impl ScaleComponentCommandBuilder {
@@ -221,6 +241,18 @@ impl ScaleComponentCommandBuilder {
         self
     }
 
+    #[must_use]
+    pub fn max_fuel(mut self, v: u64) -> Self {
+        self.max_fuel = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn max_memory(mut self, v: u64) -> Self {
+        self.max_memory = Some(v);
+        self
+    }
+
     pub fn build(self) -> Result<ScaleComponentCommand> {
         Ok(ScaleComponentCommand {
             component_ref: self
@@ -236,6 +268,8 @@ impl ScaleComponentCommandBuilder {
                 .ok_or_else(|| "host id is required for scaling hosts host".to_string())?,
             config: self.config.unwrap_or_default(),
             allow_update: self.allow_update.unwrap_or_default(),
+            max_fuel: self.max_fuel,
+            max_memory: self.max_memory,
         })
     }
 }
@@ -618,6 +652,8 @@ mod tests {
                 allow_update: true,
                 annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
                 max_instances: 1,
+                max_fuel: Some(1_000_000),
+                max_memory: Some(64 * 1024 * 1024),
             },
             ScaleComponentCommand::builder()
                 .component_ref("component_ref")
@@ -627,6 +663,8 @@ mod tests {
                 .allow_update(true)
                 .annotations(BTreeMap::from([("a".into(), "b".into())]))
                 .max_instances(1)
+                .max_fuel(1_000_000)
+                .max_memory(64 * 1024 * 1024)
                 .build()
                 .unwrap()
         )
@@ -1,6 +1,7 @@
 //! Collection of types that are commonly used/necessary in control interface operations
 
 pub mod component;
+pub mod config;
 pub mod ctl;
 pub mod host;
 pub mod link;
@@ -26,6 +26,9 @@ pub struct ProviderDescription {
     /// this provider instance
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) annotations: Option<BTreeMap<String, String>>,
+    /// The number of times the provider process was restarted by the host
+    #[serde(default)]
+    pub(crate) restarts: u32,
 }
 
 impl ProviderDescription {
@@ -54,6 +57,11 @@ impl ProviderDescription {
         self.annotations.as_ref()
     }
 
+    /// Get the number of times the provider process was restarted by the host
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
     #[must_use]
     pub fn builder() -> ProviderDescriptionBuilder {
         ProviderDescriptionBuilder::default()
@@ -69,6 +77,7 @@ pub struct ProviderDescriptionBuilder {
     name: Option<String>,
     revision: Option<i32>,
     annotations: Option<BTreeMap<String, String>>,
+    restarts: Option<u32>,
 }
 
 impl ProviderDescriptionBuilder {
@@ -108,6 +117,13 @@ impl ProviderDescriptionBuilder {
         self
     }
 
+    /// The number of times the provider process was restarted by the host
+    #[must_use]
+    pub fn restarts(mut self, v: u32) -> Self {
+        self.restarts = Some(v);
+        self
+    }
+
     /// Build a [`ProviderDescription`]
     pub fn build(self) -> Result<ProviderDescription> {
         Ok(ProviderDescription {
@@ -116,6 +132,7 @@ impl ProviderDescriptionBuilder {
             name: self.name,
             revision: self.revision.unwrap_or_default(),
             annotations: self.annotations,
+            restarts: self.restarts.unwrap_or_default(),
         })
     }
 }
@@ -135,6 +152,7 @@ mod tests {
                 name: Some("name".into()),
                 annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
                 revision: 0,
+                restarts: 1,
             },
             ProviderDescription::builder()
                 .id("id")
@@ -142,6 +160,7 @@ mod tests {
                 .name("name")
                 .annotations(BTreeMap::from([("a".into(), "b".into())]))
                 .revision(0)
+                .restarts(1)
                 .build()
                 .unwrap()
         )
@@ -6,6 +6,131 @@ use serde::{Deserialize, Serialize};
 
 use crate::{ComponentId, LinkName, Result, WitNamespace, WitPackage};
 
+/// Capacity of a host bidding in an auction, which can be used to pick the least loaded host
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct HostCapacity {
+    /// Number of component instance slots committed to running components
+    #[serde(default)]
+    pub(crate) component_slots_used: u32,
+    /// Number of component instance slots still available on the host
+    #[serde(default)]
+    pub(crate) component_slots_remaining: u32,
+    /// Amount of linear memory in bytes committed to running components
+    #[serde(default)]
+    pub(crate) linear_memory_committed: u64,
+    /// Load average of the host over the last minute relative to the number of CPUs, in percent,
+    /// if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cpu_load: Option<u32>,
+    /// Number of providers running on the host
+    #[serde(default)]
+    pub(crate) providers: u32,
+}
+
+impl HostCapacity {
+    /// Get the number of component instance slots committed to running components
+    #[must_use]
+    pub fn component_slots_used(&self) -> u32 {
+        self.component_slots_used
+    }
+
+    /// Get the number of component instance slots still available on the host
+    #[must_use]
+    pub fn component_slots_remaining(&self) -> u32 {
+        self.component_slots_remaining
+    }
+
+    /// Get the amount of linear memory in bytes committed to running components
+    #[must_use]
+    pub fn linear_memory_committed(&self) -> u64 {
+        self.linear_memory_committed
+    }
+
+    /// Get the load average of the host over the last minute relative to the number of CPUs, in
+    /// percent
+    #[must_use]
+    pub fn cpu_load(&self) -> Option<u32> {
+        self.cpu_load
+    }
+
+    /// Get the number of providers running on the host
+    #[must_use]
+    pub fn providers(&self) -> u32 {
+        self.providers
+    }
+
+    /// Get the utilization of the host in percent, which is the higher of the share of used
+    /// component slots and the CPU load
+    #[must_use]
+    pub fn utilization(&self) -> u32 {
+        let slots =
+            u64::from(self.component_slots_used) + u64::from(self.component_slots_remaining);
+        let slots = (u64::from(self.component_slots_used) * 100)
+            .checked_div(slots)
+            .unwrap_or(100);
+        u32::try_from(slots)
+            .unwrap_or(100)
+            .max(self.cpu_load.unwrap_or_default())
+    }
+
+    #[must_use]
+    pub fn builder() -> HostCapacityBuilder {
+        HostCapacityBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct HostCapacityBuilder {
+    component_slots_used: Option<u32>,
+    component_slots_remaining: Option<u32>,
+    linear_memory_committed: Option<u64>,
+    cpu_load: Option<u32>,
+    providers: Option<u32>,
+}
+
+impl HostCapacityBuilder {
+    #[must_use]
+    pub fn component_slots_used(mut self, v: u32) -> Self {
+        self.component_slots_used = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn component_slots_remaining(mut self, v: u32) -> Self {
+        self.component_slots_remaining = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn linear_memory_committed(mut self, v: u64) -> Self {
+        self.linear_memory_committed = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn cpu_load(mut self, v: u32) -> Self {
+        self.cpu_load = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn providers(mut self, v: u32) -> Self {
+        self.providers = Some(v);
+        self
+    }
+
+    pub fn build(self) -> Result<HostCapacity> {
+        Ok(HostCapacity {
+            component_slots_used: self.component_slots_used.unwrap_or_default(),
+            component_slots_remaining: self.component_slots_remaining.unwrap_or_default(),
+            linear_memory_committed: self.linear_memory_committed.unwrap_or_default(),
+            cpu_load: self.cpu_load,
+            providers: self.providers.unwrap_or_default(),
+        })
+    }
+}
+
 /// A host response to a request to start a component.
 ///
 /// This acknowledgement confirms that the host has enough capacity to start the component
@@ -24,6 +149,9 @@ pub struct ComponentAuctionAck {
     /// Constraints that were used in the auction
     #[serde(default)]
     pub(crate) constraints: BTreeMap<String, String>,
+    /// Capacity of the bidding host, if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) capacity: Option<HostCapacity>,
 }
 
 impl ComponentAuctionAck {
@@ -39,6 +167,7 @@ impl ComponentAuctionAck {
             component_id: component_id.into(),
             host_id: host_id.into(),
             constraints: constraints.into(),
+            capacity: None,
         }
     }
 
@@ -66,6 +195,12 @@ impl ComponentAuctionAck {
         &self.constraints
     }
 
+    /// Get the capacity of the bidding host, if reported
+    #[must_use]
+    pub fn capacity(&self) -> Option<&HostCapacity> {
+        self.capacity.as_ref()
+    }
+
     pub fn builder() -> ComponentAuctionAckBuilder {
         ComponentAuctionAckBuilder::default()
     }
@@ -77,6 +212,7 @@ pub struct ComponentAuctionAckBuilder {
     component_id: Option<String>,
     host_id: Option<String>,
     constraints: Option<BTreeMap<String, String>>,
+    capacity: Option<HostCapacity>,
 }
 
 impl ComponentAuctionAckBuilder {
@@ -104,6 +240,12 @@ impl ComponentAuctionAckBuilder {
         self
     }
 
+    #[must_use]
+    pub fn capacity(mut self, v: HostCapacity) -> Self {
+        self.capacity = Some(v);
+        self
+    }
+
     pub fn build(self) -> Result<ComponentAuctionAck> {
         Ok(ComponentAuctionAck {
             component_ref: self
@@ -116,6 +258,7 @@ impl ComponentAuctionAckBuilder {
                 .host_id
                 .ok_or_else(|| "host_id is required".to_string())?,
             constraints: self.constraints.unwrap_or_default(),
+            capacity: self.capacity,
         })
     }
 }
@@ -218,6 +361,9 @@ pub struct ProviderAuctionAck {
     /// The constraints provided for the auction
     #[serde(default)]
     pub(crate) constraints: BTreeMap<String, String>,
+    /// Capacity of the bidding host, if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) capacity: Option<HostCapacity>,
 }
 
 impl ProviderAuctionAck {
@@ -245,6 +391,12 @@ impl ProviderAuctionAck {
         &self.constraints
     }
 
+    /// Get the capacity of the bidding host, if reported
+    #[must_use]
+    pub fn capacity(&self) -> Option<&HostCapacity> {
+        self.capacity.as_ref()
+    }
+
     #[must_use]
     pub fn builder() -> ProviderAuctionAckBuilder {
         ProviderAuctionAckBuilder::default()
@@ -257,6 +409,7 @@ pub struct ProviderAuctionAckBuilder {
     provider_ref: Option<String>,
     provider_id: Option<String>,
     constraints: Option<BTreeMap<String, String>>,
+    capacity: Option<HostCapacity>,
 }
 
 impl ProviderAuctionAckBuilder {
@@ -284,6 +437,12 @@ impl ProviderAuctionAckBuilder {
         self
     }
 
+    #[must_use]
+    pub fn capacity(mut self, v: HostCapacity) -> Self {
+        self.capacity = Some(v);
+        self
+    }
+
     pub fn build(self) -> Result<ProviderAuctionAck> {
         Ok(ProviderAuctionAck {
             provider_ref: self
@@ -296,6 +455,7 @@ impl ProviderAuctionAckBuilder {
                 .host_id
                 .ok_or_else(|| "host_id is required".to_string())?,
             constraints: self.constraints.unwrap_or_default(),
+            capacity: self.capacity,
         })
     }
 }
@@ -502,7 +662,7 @@ mod tests {
 
     use super::{
         ComponentAuctionAck, ComponentAuctionRequest, DeleteInterfaceLinkDefinitionRequest,
-        ProviderAuctionAck, ProviderAuctionRequest,
+        HostCapacity, ProviderAuctionAck, ProviderAuctionRequest,
     };
 
     #[test]
@@ -512,18 +672,46 @@ mod tests {
                 component_ref: "component_ref".into(),
                 component_id: "component_id".into(),
                 host_id: "host_id".into(),
-                constraints: BTreeMap::from([("a".into(), "b".into())])
+                constraints: BTreeMap::from([("a".into(), "b".into())]),
+                capacity: Some(HostCapacity {
+                    component_slots_used: 10,
+                    component_slots_remaining: 90,
+                    ..Default::default()
+                }),
             },
             ComponentAuctionAck::builder()
                 .component_ref("component_ref".into())
                 .component_id("component_id".into())
                 .host_id("host_id".into())
                 .constraints(BTreeMap::from([("a".into(), "b".into())]))
+                .capacity(
+                    HostCapacity::builder()
+                        .component_slots_used(10)
+                        .component_slots_remaining(90)
+                        .build()
+                        .unwrap()
+                )
                 .build()
                 .unwrap()
         )
     }
 
+    #[test]
+    fn host_capacity_utilization() {
+        let capacity = HostCapacity::builder()
+            .component_slots_used(25)
+            .component_slots_remaining(75)
+            .build()
+            .unwrap();
+        assert_eq!(capacity.utilization(), 25);
+        let capacity = HostCapacity {
+            cpu_load: Some(80),
+            ..capacity
+        };
+        assert_eq!(capacity.utilization(), 80);
+        assert_eq!(HostCapacity::default().utilization(), 100);
+    }
+
     #[test]
     fn component_auction_request_builder() {
         assert_eq!(
@@ -548,7 +736,8 @@ mod tests {
                 provider_ref: "provider_ref".into(),
                 provider_id: "provider_id".into(),
                 host_id: "host_id".into(),
-                constraints: BTreeMap::from([("a".into(), "b".into())])
+                constraints: BTreeMap::from([("a".into(), "b".into())]),
+                capacity: None,
             },
             ProviderAuctionAck::builder()
                 .provider_ref("provider_ref".into())
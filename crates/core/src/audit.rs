@@ -0,0 +1,169 @@
+//! Audit records of control interface operations
+
+use serde::{Deserialize, Serialize};
+
+/// Placeholder replacing redacted values in audited payloads
+pub const REDACTED: &str = "<redacted>";
+
+/// Substrings of object keys, whose values are redacted from audited payloads
+const SECRET_KEY_PATTERNS: &[&str] = &[
+    "password",
+    "passwd",
+    "token",
+    "secret",
+    "credential",
+    "jwt",
+    "seed",
+    "private",
+    "apikey",
+    "api_key",
+    "api-key",
+    "access_key",
+    "auth",
+    "cookie",
+    "signature",
+];
+
+/// Operations, whose payloads are maps of arbitrary config values, all of which are redacted
+/// from audited payloads
+const CONFIG_VALUE_OPERATIONS: &[&str] = &["config.put"];
+
+/// Returns the name of the JetStream stream retaining the audit records of `lattice`
+#[must_use]
+pub fn audit_stream_name(lattice: &str) -> String {
+    format!("AUDIT_{lattice}")
+}
+
+/// Returns the subject, on which audit records of `operation` (e.g. `component.scale`) in
+/// `lattice` are published
+#[must_use]
+pub fn audit_subject(lattice: &str, operation: &str) -> String {
+    format!("wasmcloud.{lattice}.audit.{operation}")
+}
+
+/// Record of a control interface request handled by a host and its outcome
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditRecord {
+    /// Time the request was handled at, in RFC 3339 format
+    pub timestamp: String,
+    /// Public key of the host, which handled the request
+    pub host_id: String,
+    /// Subject the request was received on
+    pub subject: String,
+    /// Operation requested, e.g. `component.scale`
+    pub operation: String,
+    /// NATS user, which sent the request, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Payload of the request with secrets redacted
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    /// Whether the request succeeded
+    pub success: bool,
+    /// Message describing the outcome of the request
+    #[serde(default)]
+    pub message: String,
+}
+
+/// Returns whether the value of `key` is redacted from audited payloads
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_PATTERNS
+        .iter()
+        .any(|pattern| key.contains(pattern))
+}
+
+/// Returns the payload of a control interface request for `operation` to be audited, with the
+/// values of all keys looking like they hold secrets redacted. Config values cannot be told apart
+/// from secrets by their keys, so the values of `config.put` payloads are redacted entirely.
+///
+/// Payloads, which are not JSON, are replaced by a description of their size.
+#[must_use]
+pub fn sanitize_audit_payload(operation: &str, payload: &[u8]) -> serde_json::Value {
+    if payload.is_empty() {
+        return serde_json::Value::Null;
+    }
+    let Ok(mut value) = serde_json::from_slice(payload) else {
+        return format!("<{} bytes>", payload.len()).into();
+    };
+    match &mut value {
+        serde_json::Value::Object(object) if CONFIG_VALUE_OPERATIONS.contains(&operation) => {
+            object
+                .values_mut()
+                .filter(|value| !value.is_null())
+                .for_each(|value| *value = REDACTED.into());
+        }
+        value => redact(value),
+    }
+    value
+}
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = REDACTED.into();
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn sanitize() {
+        assert_eq!(
+            sanitize_audit_payload("registry.put", b""),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            sanitize_audit_payload("component.scale", b"\0asm"),
+            json!("<4 bytes>")
+        );
+        let payload = json!({
+            "ghcr.io": {
+                "username": "user",
+                "password": "hunter2",
+                "token": null,
+                "registryType": "oci",
+            },
+            "links": [{ "sourceId": "http", "clientSecret": { "value": "s3cr3t" } }],
+            "headers": { "Authorization": "Bearer s3cr3t", "api_key": "s3cr3t", "apiKey": "s3cr3t" },
+            "maxInstances": 1,
+        });
+        let payload = serde_json::to_vec(&payload).expect("failed to encode");
+        assert_eq!(
+            sanitize_audit_payload("registry.put", &payload),
+            json!({
+                "ghcr.io": {
+                    "username": "user",
+                    "password": REDACTED,
+                    "token": null,
+                    "registryType": "oci",
+                },
+                "links": [{ "sourceId": "http", "clientSecret": REDACTED }],
+                "headers": { "Authorization": REDACTED, "api_key": REDACTED, "apiKey": REDACTED },
+                "maxInstances": 1,
+            })
+        );
+
+        let payload = json!({ "address": "0.0.0.0:8080", "dsn": "postgres://u:p@db", "tls": null });
+        assert_eq!(
+            sanitize_audit_payload(
+                "config.put",
+                &serde_json::to_vec(&payload).expect("failed to encode")
+            ),
+            json!({ "address": REDACTED, "dsn": REDACTED, "tls": null })
+        );
+    }
+}
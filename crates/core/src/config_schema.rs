@@ -0,0 +1,204 @@
+//! Validation of named config against JSON schemas embedded in components and providers
+
+use core::fmt;
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::paths::PathChunk;
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Violation of a config schema
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConfigSchemaViolation {
+    /// Config key the violation concerns, if it concerns a single key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Description of the violation
+    pub message: String,
+}
+
+impl fmt::Display for ConfigSchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "`{field}`: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Returns whether config values of the property described by `schema` are validated as strings.
+///
+/// Config values are always strings, so values of properties declaring non-string types are
+/// parsed as JSON before validation, e.g. `8080` as a number and `true` as a boolean.
+fn is_string_property(schema: Option<&Value>) -> bool {
+    match schema.and_then(|schema| schema.get("type")) {
+        Some(Value::String(ty)) => ty == "string",
+        Some(Value::Array(types)) => types.iter().any(|ty| ty == "string"),
+        _ => true,
+    }
+}
+
+/// Validates `config` against the JSON `schema` and returns all violations found, ordered by
+/// config key. Fails if `schema` is not a valid JSON schema
+pub fn validate_config(
+    schema: &Value,
+    config: &HashMap<String, String>,
+) -> anyhow::Result<Vec<ConfigSchemaViolation>> {
+    let compiled =
+        JSONSchema::compile(schema).map_err(|e| anyhow!("invalid config schema: {e}"))?;
+    let properties = schema.get("properties");
+    let instance: serde_json::Map<String, Value> = config
+        .iter()
+        .map(|(k, v)| {
+            let value = if is_string_property(properties.and_then(|p| p.get(k))) {
+                Value::String(v.clone())
+            } else {
+                serde_json::from_str(v).unwrap_or_else(|_| Value::String(v.clone()))
+            };
+            (k.clone(), value)
+        })
+        .collect();
+    let instance = Value::Object(instance);
+    let Err(errors) = compiled.validate(&instance) else {
+        return Ok(Vec::default());
+    };
+    let mut violations = Vec::new();
+    for error in errors {
+        match &error.kind {
+            ValidationErrorKind::AdditionalProperties { unexpected } => {
+                violations.extend(unexpected.iter().map(|field| ConfigSchemaViolation {
+                    field: Some(field.clone()),
+                    message: "unknown config key".into(),
+                }));
+            }
+            ValidationErrorKind::Required { property } => {
+                violations.push(ConfigSchemaViolation {
+                    field: Some(
+                        property
+                            .as_str()
+                            .map_or_else(|| property.to_string(), Into::into),
+                    ),
+                    message: "required config key is missing".into(),
+                });
+            }
+            _ => {
+                let field = match error.instance_path.iter().next() {
+                    Some(PathChunk::Property(field)) => Some(field.to_string()),
+                    _ => None,
+                };
+                violations.push(ConfigSchemaViolation {
+                    field,
+                    message: error.to_string(),
+                });
+            }
+        }
+    }
+    violations.sort_by(|a, b| a.field.cmp(&b.field));
+    Ok(violations)
+}
+
+/// Validates `config` against the properties of the JSON `schema` only, ignoring the keys it
+/// requires. This is used for partial config, e.g. the config of a link, which is merged with
+/// other config at runtime
+pub fn validate_config_properties(
+    schema: &Value,
+    config: &HashMap<String, String>,
+) -> anyhow::Result<Vec<ConfigSchemaViolation>> {
+    let mut schema = schema.clone();
+    if let Value::Object(schema) = &mut schema {
+        schema.remove("required");
+    }
+    validate_config(&schema, config)
+}
+
+/// Formats `violations` of the schema of `target` for use in error messages
+#[must_use]
+pub fn format_config_violations(target: &str, violations: &[ConfigSchemaViolation]) -> String {
+    let violations = violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    format!("config does not match the schema of `{target}`: {violations}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn validate() -> anyhow::Result<()> {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "address": { "type": "string" },
+                "cors_allowed_origins": { "type": "string" },
+                "timeout_ms": { "type": "integer", "minimum": 1 },
+                "tls": { "type": "boolean" },
+            },
+            "required": ["address"],
+            "additionalProperties": false,
+        });
+        let config = HashMap::from([
+            ("address".into(), "0.0.0.0:8080".into()),
+            ("timeout_ms".into(), "500".into()),
+            ("tls".into(), "true".into()),
+        ]);
+        assert_eq!(validate_config(&schema, &config)?, []);
+
+        let config = HashMap::from([
+            ("cors_allowd_origins".into(), "*".into()),
+            ("timeout_ms".into(), "soon".into()),
+        ]);
+        let violations = validate_config(&schema, &config)?;
+        let fields: Vec<_> = violations.iter().map(|v| v.field.as_deref()).collect();
+        assert_eq!(
+            fields,
+            [
+                Some("address"),
+                Some("cors_allowd_origins"),
+                Some("timeout_ms")
+            ]
+        );
+        assert_eq!(
+            violations[1].to_string(),
+            "`cors_allowd_origins`: unknown config key"
+        );
+        assert!(format_config_violations("http-server", &violations)
+            .starts_with("config does not match the schema of `http-server`: `address`: "));
+
+        assert!(validate_config(&json!({ "type": 1 }), &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_properties() -> anyhow::Result<()> {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "address": { "type": "string" },
+                "timeout_ms": { "type": "integer", "minimum": 1 },
+            },
+            "required": ["address"],
+            "additionalProperties": false,
+        });
+        let config = HashMap::from([("timeout_ms".into(), "500".into())]);
+        assert_eq!(validate_config_properties(&schema, &config)?, []);
+        assert_eq!(validate_config(&schema, &config)?.len(), 1);
+
+        let config = HashMap::from([
+            ("timeout_ms".into(), "0".into()),
+            ("tls".into(), "true".into()),
+        ]);
+        let violations = validate_config_properties(&schema, &config)?;
+        let fields: Vec<_> = violations.iter().map(|v| v.field.as_deref()).collect();
+        assert_eq!(fields, [Some("timeout_ms"), Some("tls")]);
+        Ok(())
+    }
+}
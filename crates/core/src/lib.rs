@@ -1,5 +1,11 @@
 #![forbid(clippy::unwrap_used)]
 
+pub mod audit;
+pub use audit::*;
+
+pub mod config_schema;
+pub use config_schema::*;
+
 pub mod logging;
 pub mod nats;
 pub mod tls;
@@ -17,6 +23,10 @@ pub use otel::*;
 pub mod oci;
 #[cfg(feature = "oci")]
 pub use oci::*;
+#[cfg(feature = "oci")]
+pub mod oci_cache;
+#[cfg(feature = "oci")]
+pub use oci_cache::*;
 
 pub mod par;
 pub use par::*;
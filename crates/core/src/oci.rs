@@ -3,21 +3,25 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::{bail, Context as _};
+use futures::TryStreamExt as _;
 use oci_client::client::ClientProtocol;
-use oci_client::client::ImageData;
 use oci_client::Reference;
 use oci_wasm::WASM_LAYER_MEDIA_TYPE;
 use oci_wasm::WASM_MANIFEST_MEDIA_TYPE;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
 use wascap::jwt;
 
-use crate::RegistryConfig;
-use crate::{tls, UseParFileCache};
+use crate::{tls, OciCache, RegistryConfig, UseParFileCache};
 
 const PROVIDER_ARCHIVE_MEDIA_TYPE: &str = "application/vnd.wasmcloud.provider.archive.layer.v1+par";
 const WASM_MEDIA_TYPE: &str = "application/vnd.module.wasm.content.layer.v1+wasm";
 const OCI_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar";
+const COSIGN_SIGNATURE_MEDIA_TYPE: &str = "application/vnd.dev.cosign.simplesigning.v1+json";
+const COSIGN_SIGNATURE_ARTIFACT_TYPE: &str = "application/vnd.dev.cosign.artifact.sig.v1+json";
+const COSIGN_SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+/// Maximum size of a cosign signature payload, which is a small JSON document
+const MAX_SIGNATURE_PAYLOAD_SIZE: usize = 64 * 1024;
 
 /// Whether to update an OCI artifact cache
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -37,6 +41,8 @@ pub struct OciFetcher {
     allow_latest: bool,
     allow_insecure: bool,
     auth: oci_client::secrets::RegistryAuth,
+    cache_dir: Option<PathBuf>,
+    offline: bool,
 }
 
 impl Default for OciFetcher {
@@ -46,6 +52,8 @@ impl Default for OciFetcher {
             allow_latest: false,
             allow_insecure: false,
             auth: oci_client::secrets::RegistryAuth::Anonymous,
+            cache_dir: None,
+            offline: false,
         }
     }
 }
@@ -65,6 +73,8 @@ impl From<&RegistryConfig> for OciFetcher {
             allow_latest: *allow_latest,
             allow_insecure: *allow_insecure,
             additional_ca_paths: additional_ca_paths.clone(),
+            cache_dir: None,
+            offline: false,
         }
     }
 }
@@ -84,10 +94,21 @@ impl From<RegistryConfig> for OciFetcher {
             allow_latest,
             allow_insecure,
             additional_ca_paths,
+            cache_dir: None,
+            offline: false,
         }
     }
 }
 
+/// Cosign signature of an OCI artifact
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OciSignature {
+    /// Signed payload, a simple signing JSON document referencing the digest of the artifact
+    pub payload: Vec<u8>,
+    /// Base64-encoded signature of `payload`
+    pub signature: String,
+}
+
 /// Default directory in which OCI artifacts are cached
 pub async fn oci_cache_dir() -> anyhow::Result<PathBuf> {
     let path = temp_dir().join("wasmcloud_ocicache");
@@ -97,35 +118,6 @@ pub async fn oci_cache_dir() -> anyhow::Result<PathBuf> {
     Ok(path)
 }
 
-#[allow(unused)]
-async fn cache_oci_image(
-    image: ImageData,
-    cache_filepath: impl AsRef<Path>,
-    digest_filepath: impl AsRef<Path>,
-) -> std::io::Result<()> {
-    let mut cache_file = fs::File::create(cache_filepath).await?;
-    let content = image
-        .layers
-        .into_iter()
-        .flat_map(|l| l.data)
-        .collect::<Vec<_>>();
-    cache_file.write_all(&content).await?;
-    cache_file.flush().await?;
-    if let Some(digest) = image.digest {
-        let mut digest_file = fs::File::create(digest_filepath).await?;
-        digest_file.write_all(digest.as_bytes()).await?;
-        digest_file.flush().await?;
-    }
-    Ok(())
-}
-
-fn prune_filepath(img: &str) -> String {
-    let mut img = img.replace(':', "_");
-    img = img.replace('/', "_");
-    img = img.replace('.', "_");
-    img
-}
-
 /// A type to indicate whether there was a cache hit or miss when loading artifacts
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CacheResult {
@@ -134,27 +126,17 @@ pub enum CacheResult {
 }
 
 impl OciFetcher {
-    /// Fetch an OCI artifact to a path and return that path. Returns the path and whether or not
-    /// there was a cache hit/miss
-    pub async fn fetch_path(
-        &self,
-        output_dir: impl AsRef<Path>,
-        img: impl AsRef<str>,
-        accepted_media_types: Vec<&str>,
-        cache: OciArtifactCacheUpdate,
-    ) -> anyhow::Result<(PathBuf, CacheResult)> {
-        let output_dir = output_dir.as_ref();
+    /// Parses `img` as an OCI reference, rejecting references tagged `latest` unless allowed
+    fn reference(&self, img: impl AsRef<str>) -> anyhow::Result<Reference> {
         let img = img.as_ref().to_lowercase(); // the OCI spec does not allow for capital letters in references
         if !self.allow_latest && img.ends_with(":latest") {
             bail!("fetching images tagged 'latest' is currently prohibited in this host. This option can be overridden with WASMCLOUD_OCI_ALLOW_LATEST")
         }
-        let pruned_filepath = prune_filepath(&img);
-        let cache_file = output_dir.join(&pruned_filepath);
-        let mut digest_file = output_dir.join(&pruned_filepath).clone();
-        digest_file.set_extension("digest");
-
-        let img = Reference::from_str(&img)?;
+        Ok(Reference::from_str(&img)?)
+    }
 
+    /// Constructs an OCI client for fetching `img`
+    fn client(&self, img: &Reference) -> anyhow::Result<oci_client::Client> {
         let protocol = if self.allow_insecure {
             ClientProtocol::HttpsExcept(vec![img.registry().to_string()])
         } else {
@@ -172,27 +154,86 @@ impl OciFetcher {
                     }),
             );
         }
-        let c = oci_client::Client::new(oci_client::client::ClientConfig {
+        Ok(oci_client::Client::new(oci_client::client::ClientConfig {
             protocol,
             extra_root_certificates: certs,
             ..Default::default()
-        });
+        }))
+    }
 
-        // In case of a cache miss where the file does not exist, pull a fresh OCI Image
-        if fs::metadata(&cache_file).await.is_ok() {
-            let (_, oci_digest) = c
-                .pull_manifest(&img, &self.auth)
+    /// Returns the store, in which fetched artifacts are cached
+    async fn cache(&self) -> anyhow::Result<OciCache> {
+        match &self.cache_dir {
+            Some(dir) => Ok(OciCache::new(dir)),
+            None => oci_cache_dir().await.map(OciCache::new),
+        }
+    }
+
+    /// Fetch an OCI artifact to a path and return that path. Returns the path and whether or not
+    /// there was a cache hit/miss.
+    ///
+    /// Artifacts are stored in an [`OciCache`] rooted at `output_dir`. If the fetcher is offline or
+    /// the registry cannot be reached, the artifact last recorded for the reference is returned.
+    pub async fn fetch_path(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: impl AsRef<str>,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<(PathBuf, CacheResult)> {
+        let store = OciCache::new(output_dir.as_ref());
+        let img = self.reference(img)?;
+        let whole = img.whole();
+
+        let cached = || async {
+            let digest = store
+                .resolve(&whole)
+                .await?
+                .with_context(|| format!("no artifact cached for `{whole}`"))?;
+            store
+                .get(&digest)
+                .await?
+                .with_context(|| format!("artifact `{digest}` of `{whole}` is not cached"))
+        };
+        if self.offline {
+            let path = cached()
                 .await
-                .context("failed to fetch OCI manifest")?;
-            // If the digest file doesn't exist that is ok, we just unwrap to an empty string
-            let file_digest = fs::read_to_string(&digest_file).await.unwrap_or_default();
-            if !oci_digest.is_empty() && !file_digest.is_empty() && file_digest == oci_digest {
-                return Ok((cache_file, CacheResult::Hit));
+                .context("failed to fetch OCI artifact offline")?;
+            return Ok((path, CacheResult::Hit));
+        }
+
+        let c = self.client(&img)?;
+        let digest = match img.digest() {
+            Some(digest) => digest.to_string(),
+            None => match c.fetch_manifest_digest(&img, &self.auth).await {
+                Ok(digest) => digest,
+                Err(err) => {
+                    let path = cached().await.map_err(|_| {
+                        anyhow::Error::new(err).context("failed to fetch OCI manifest digest")
+                    })?;
+                    warn!(
+                        img = whole,
+                        "failed to reach OCI registry, using cached artifact"
+                    );
+                    return Ok((path, CacheResult::Hit));
+                }
+            },
+        };
+        if let Some(path) = store.get(&digest).await? {
+            if let OciArtifactCacheUpdate::Update = cache {
+                store.record(&whole, &digest).await?;
             }
+            return Ok((path, CacheResult::Hit));
         }
 
+        // Pull the resolved digest, such that the artifact cannot change in the meantime
+        let pinned = Reference::with_digest(
+            img.registry().into(),
+            img.repository().into(),
+            digest.clone(),
+        );
         let imgdata = c
-            .pull(&img, &self.auth, accepted_media_types)
+            .pull(&pinned, &self.auth, accepted_media_types)
             .await
             .context("failed to fetch OCI bytes")?;
         // As a client, we should reject invalid OCI artifacts
@@ -208,14 +249,204 @@ impl OciFetcher {
                 imgdata.layers.len()
             )
         }
-        // Update the OCI artifact cache if specified
+        let content = imgdata
+            .layers
+            .into_iter()
+            .flat_map(|l| l.data)
+            .collect::<Vec<_>>();
+        let path = store
+            .insert(&digest, content)
+            .await
+            .context("failed to cache OCI bytes")?;
+        // Update the tag resolution record if specified
         if let OciArtifactCacheUpdate::Update = cache {
-            cache_oci_image(imgdata, &cache_file, digest_file)
-                .await
-                .context("failed to cache OCI bytes")?;
+            store.record(&whole, &digest).await?;
+        }
+
+        Ok((path, CacheResult::Miss))
+    }
+
+    /// Fetch the manifest digest of an OCI artifact. The digest is recorded in the cache and
+    /// resolved from there if the fetcher is offline or the registry cannot be reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reference is invalid or fetching the digest fails
+    pub async fn fetch_digest(&self, img: impl AsRef<str>) -> anyhow::Result<String> {
+        let img = self.reference(img)?;
+        let whole = img.whole();
+        let store = self.cache().await?;
+        let cached = || async {
+            store
+                .resolve(&whole)
+                .await?
+                .with_context(|| format!("no artifact cached for `{whole}`"))
+        };
+        if self.offline {
+            return cached().await;
         }
+        match self
+            .client(&img)?
+            .fetch_manifest_digest(&img, &self.auth)
+            .await
+        {
+            Ok(digest) => {
+                if let Err(err) = store.record(&whole, &digest).await {
+                    warn!(?err, img = whole, "failed to record OCI manifest digest");
+                }
+                Ok(digest)
+            }
+            Err(err) => {
+                let digest = cached().await.map_err(|_| {
+                    anyhow::Error::new(err).context("failed to fetch OCI manifest digest")
+                })?;
+                warn!(
+                    img = whole,
+                    "failed to reach OCI registry, using cached digest"
+                );
+                Ok(digest)
+            }
+        }
+    }
 
-        Ok((cache_file, CacheResult::Miss))
+    /// Fetch the cosign signatures of the OCI artifact with manifest digest `digest` in the
+    /// repository of `img`.
+    ///
+    /// Signatures are looked up under the `sha256-<digest>.sig` tag used by cosign and, if none are
+    /// found there, as referrers of the artifact. Fetched signatures are cached, and cached
+    /// signatures are returned if the fetcher is offline or the registry could not be queried.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reference or digest is invalid
+    pub async fn fetch_signatures(
+        &self,
+        img: impl AsRef<str>,
+        digest: impl AsRef<str>,
+    ) -> anyhow::Result<Vec<OciSignature>> {
+        let img = self.reference(img)?;
+        let digest = digest.as_ref();
+        let store = self.cache().await?;
+        if self.offline {
+            return store.signatures(digest).await;
+        }
+        match self.pull_all_signatures(&img, digest).await {
+            Ok(signatures) => {
+                if !signatures.is_empty() {
+                    if let Err(err) = store.put_signatures(digest, &signatures).await {
+                        warn!(?err, digest, "failed to cache signatures");
+                    }
+                }
+                Ok(signatures)
+            }
+            Err(err) => {
+                warn!(
+                    ?err,
+                    digest, "failed to fetch signatures, using cached signatures"
+                );
+                store.signatures(digest).await
+            }
+        }
+    }
+
+    /// Pull the cosign signatures of the OCI artifact with manifest digest `digest` from the
+    /// signature tag or, if none are found there, the referrers of the artifact.
+    /// Returns an error if neither the signature tag nor the referrers could be fetched
+    async fn pull_all_signatures(
+        &self,
+        img: &Reference,
+        digest: &str,
+    ) -> anyhow::Result<Vec<OciSignature>> {
+        let c = self.client(img)?;
+        let tag = digest.replacen(':', "-", 1) + ".sig";
+        let sig = Reference::with_tag(img.registry().into(), img.repository().into(), tag);
+        let tag_fetched = match self.pull_signatures(&c, &sig).await {
+            Ok(signatures) if !signatures.is_empty() => return Ok(signatures),
+            Ok(_) => true,
+            Err(err) => {
+                debug!(?err, %sig, "failed to fetch signature tag");
+                false
+            }
+        };
+
+        let img = Reference::with_digest(
+            img.registry().into(),
+            img.repository().into(),
+            digest.into(),
+        );
+        let referrers = match c
+            .pull_referrers(&img, Some(COSIGN_SIGNATURE_ARTIFACT_TYPE))
+            .await
+        {
+            Ok(referrers) => referrers,
+            Err(err) if tag_fetched => {
+                debug!(?err, %img, "failed to fetch signature referrers");
+                return Ok(Vec::default());
+            }
+            Err(err) => {
+                return Err(anyhow::Error::new(err).context("failed to fetch signature referrers"))
+            }
+        };
+        let mut signatures = Vec::default();
+        for referrer in referrers.manifests {
+            let sig = img.clone_with_digest(referrer.digest);
+            match self.pull_signatures(&c, &sig).await {
+                Ok(sigs) => signatures.extend(sigs),
+                Err(err) => debug!(?err, %sig, "failed to fetch signature referrer"),
+            }
+        }
+        Ok(signatures)
+    }
+
+    /// Pull the cosign signatures stored in the signature manifest under `sig`
+    async fn pull_signatures(
+        &self,
+        c: &oci_client::Client,
+        sig: &Reference,
+    ) -> anyhow::Result<Vec<OciSignature>> {
+        let (manifest, _) = c
+            .pull_image_manifest(sig, &self.auth)
+            .await
+            .context("failed to fetch signature manifest")?;
+        let mut signatures = Vec::with_capacity(manifest.layers.len());
+        for layer in &manifest.layers {
+            if layer.media_type != COSIGN_SIGNATURE_MEDIA_TYPE {
+                continue;
+            }
+            let Some(signature) = layer
+                .annotations
+                .as_ref()
+                .and_then(|annotations| annotations.get(COSIGN_SIGNATURE_ANNOTATION))
+            else {
+                continue;
+            };
+            if usize::try_from(layer.size).map_or(true, |size| size > MAX_SIGNATURE_PAYLOAD_SIZE) {
+                bail!(
+                    "signature payload of {} bytes exceeds maximum of {MAX_SIGNATURE_PAYLOAD_SIZE} bytes",
+                    layer.size
+                )
+            }
+            let mut stream = c
+                .pull_blob_stream(sig, layer)
+                .await
+                .context("failed to fetch signature payload")?;
+            let mut payload = Vec::default();
+            while let Some(chunk) = stream
+                .try_next()
+                .await
+                .context("failed to read signature payload")?
+            {
+                if payload.len() + chunk.len() > MAX_SIGNATURE_PAYLOAD_SIZE {
+                    bail!("signature payload exceeds maximum of {MAX_SIGNATURE_PAYLOAD_SIZE} bytes")
+                }
+                payload.extend_from_slice(&chunk);
+            }
+            signatures.push(OciSignature {
+                payload,
+                signature: signature.clone(),
+            });
+        }
+        Ok(signatures)
     }
 
     /// Fetch component from OCI
@@ -226,7 +457,7 @@ impl OciFetcher {
     pub async fn fetch_component(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<Vec<u8>> {
         let (path, _) = self
             .fetch_path(
-                oci_cache_dir().await?,
+                self.cache().await?.dir(),
                 oci_ref,
                 vec![WASM_MEDIA_TYPE, OCI_MEDIA_TYPE, WASM_LAYER_MEDIA_TYPE],
                 OciArtifactCacheUpdate::Update,
@@ -250,7 +481,7 @@ impl OciFetcher {
     ) -> anyhow::Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
         let (path, cache) = self
             .fetch_path(
-                oci_cache_dir().await?,
+                self.cache().await?.dir(),
                 oci_ref.as_ref(),
                 vec![PROVIDER_ARCHIVE_MEDIA_TYPE, OCI_MEDIA_TYPE],
                 OciArtifactCacheUpdate::Update,
@@ -266,6 +497,18 @@ impl OciFetcher {
             .with_context(|| format!("failed to read `{}`", path.display()))
     }
 
+    /// Used to set the directory, in which fetched components and providers are cached
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Used to only serve components and providers from the cache, without contacting registries
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
     /// Used to set additional CA paths that will be used as part of fetching components and providers
     pub fn with_additional_ca_paths(mut self, paths: &[impl AsRef<Path>]) -> Self {
         self.additional_ca_paths = paths.iter().map(AsRef::as_ref).map(PathBuf::from).collect();
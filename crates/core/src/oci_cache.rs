@@ -0,0 +1,396 @@
+//! Persistent, content-addressed store of OCI artifacts
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{ensure, Context as _};
+use oci_client::Reference;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt as _;
+use tracing::{debug, warn};
+
+use crate::OciSignature;
+
+/// Store of OCI artifacts on disk, keyed by the digest of their manifest.
+///
+/// Artifacts are stored under `blobs/sha256/<hex>` as the concatenation of their layers. Tags are
+/// resolved to digests using records under `refs`, such that cached artifacts can be found without
+/// contacting the registry, and cosign signatures of artifacts are stored under
+/// `signatures/sha256/<hex>.json`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OciCache {
+    dir: PathBuf,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Signature {
+    payload: String,
+    signature: String,
+}
+
+impl OciCache {
+    /// Constructs a store rooted at `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the root directory of the store
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn digest_path(&self, kind: &str, digest: &str, extension: &str) -> anyhow::Result<PathBuf> {
+        let hex = digest
+            .strip_prefix("sha256:")
+            .with_context(|| format!("unsupported digest `{digest}`"))?;
+        ensure!(
+            hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()),
+            "invalid digest `{digest}`"
+        );
+        Ok(self
+            .dir
+            .join(kind)
+            .join("sha256")
+            .join(format!("{hex}{extension}")))
+    }
+
+    fn ref_path(&self, reference: &Reference) -> Option<PathBuf> {
+        let tag = reference.tag()?;
+        let name = format!(
+            "{}/{}:{tag}",
+            reference.resolve_registry(),
+            reference.repository()
+        );
+        Some(
+            self.dir
+                .join("refs")
+                .join(name.replace(['/', ':', '.'], "_")),
+        )
+    }
+
+    /// Resolves `reference` to the digest of the artifact it was last recorded to refer to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `reference` is invalid or reading the record fails
+    pub async fn resolve(&self, reference: impl AsRef<str>) -> anyhow::Result<Option<String>> {
+        let reference = Reference::from_str(&reference.as_ref().to_lowercase())?;
+        if let Some(digest) = reference.digest() {
+            return Ok(Some(digest.into()));
+        }
+        let Some(path) = self.ref_path(&reference) else {
+            return Ok(None);
+        };
+        match fs::read_to_string(&path).await {
+            Ok(digest) => Ok(Some(digest.trim().into())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => {
+                Err(anyhow::Error::new(err).context(format!("failed to read `{}`", path.display())))
+            }
+        }
+    }
+
+    /// Records that `reference` refers to the artifact with manifest digest `digest`.
+    ///
+    /// References pinned to a digest need not be recorded and are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `reference` is invalid or writing the record fails
+    pub async fn record(
+        &self,
+        reference: impl AsRef<str>,
+        digest: impl AsRef<str>,
+    ) -> anyhow::Result<()> {
+        let reference = Reference::from_str(&reference.as_ref().to_lowercase())?;
+        if reference.digest().is_some() {
+            return Ok(());
+        }
+        let Some(path) = self.ref_path(&reference) else {
+            return Ok(());
+        };
+        write_atomic(&path, digest.as_ref().as_bytes()).await
+    }
+
+    /// Returns the path of the artifact with manifest digest `digest`, if it is stored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `digest` is invalid
+    pub async fn get(&self, digest: impl AsRef<str>) -> anyhow::Result<Option<PathBuf>> {
+        let path = self.digest_path("blobs", digest.as_ref(), "")?;
+        if !fs::try_exists(&path).await.unwrap_or_default() {
+            return Ok(None);
+        }
+        // Track the last use of the artifact for garbage collection
+        let file = fs::OpenOptions::new().write(true).open(&path).await;
+        if let Err(err) = async { file?.into_std().await.set_modified(SystemTime::now()) }.await {
+            debug!(?err, path = %path.display(), "failed to update artifact modification time");
+        }
+        Ok(Some(path))
+    }
+
+    /// Stores the artifact with manifest digest `digest` and returns its path
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `digest` is invalid or writing the artifact fails
+    pub async fn insert(
+        &self,
+        digest: impl AsRef<str>,
+        data: impl AsRef<[u8]>,
+    ) -> anyhow::Result<PathBuf> {
+        let path = self.digest_path("blobs", digest.as_ref(), "")?;
+        write_atomic(&path, data.as_ref()).await?;
+        Ok(path)
+    }
+
+    /// Returns the signatures stored for the artifact with manifest digest `digest`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `digest` is invalid or reading the signatures fails
+    pub async fn signatures(&self, digest: impl AsRef<str>) -> anyhow::Result<Vec<OciSignature>> {
+        let path = self.digest_path("signatures", digest.as_ref(), ".json")?;
+        let buf = match fs::read(&path).await {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::default()),
+            Err(err) => {
+                return Err(
+                    anyhow::Error::new(err).context(format!("failed to read `{}`", path.display()))
+                )
+            }
+        };
+        let signatures: Vec<Signature> =
+            serde_json::from_slice(&buf).context("failed to decode signatures")?;
+        Ok(signatures
+            .into_iter()
+            .map(|Signature { payload, signature }| OciSignature {
+                payload: payload.into_bytes(),
+                signature,
+            })
+            .collect())
+    }
+
+    /// Stores `signatures` of the artifact with manifest digest `digest`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `digest` is invalid or writing the signatures fails
+    pub async fn put_signatures(
+        &self,
+        digest: impl AsRef<str>,
+        signatures: &[OciSignature],
+    ) -> anyhow::Result<()> {
+        let path = self.digest_path("signatures", digest.as_ref(), ".json")?;
+        let signatures = signatures
+            .iter()
+            .map(|OciSignature { payload, signature }| {
+                Ok(Signature {
+                    payload: String::from_utf8(payload.clone())
+                        .context("signature payload is not valid UTF-8")?,
+                    signature: signature.clone(),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let buf = serde_json::to_vec(&signatures).context("failed to encode signatures")?;
+        write_atomic(&path, &buf).await
+    }
+
+    /// Evicts least recently used artifacts until the total size of stored artifacts does not
+    /// exceed `max_size` bytes and returns the digests of evicted artifacts.
+    ///
+    /// Artifacts whose digests are in `referenced` and artifacts used within the last `grace`
+    /// period are never evicted, so the store may remain larger than `max_size`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if listing the stored artifacts fails
+    pub async fn gc(
+        &self,
+        max_size: u64,
+        referenced: &HashSet<String>,
+        grace: Duration,
+    ) -> anyhow::Result<Vec<String>> {
+        let dir = self.dir.join("blobs").join("sha256");
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::default()),
+            Err(err) => {
+                return Err(
+                    anyhow::Error::new(err).context(format!("failed to read `{}`", dir.display()))
+                )
+            }
+        };
+        let mut blobs = Vec::default();
+        let mut size = 0u64;
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(hex) = entry.file_name().into_string() else {
+                continue;
+            };
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() || hex.contains('.') {
+                continue;
+            }
+            size = size.saturating_add(metadata.len());
+            blobs.push((
+                metadata.modified()?,
+                format!("sha256:{hex}"),
+                metadata.len(),
+            ));
+        }
+        if size <= max_size {
+            return Ok(Vec::default());
+        }
+        blobs.sort();
+
+        let cutoff = SystemTime::now()
+            .checked_sub(grace)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut evicted = Vec::default();
+        for (modified, digest, len) in blobs {
+            if size <= max_size {
+                break;
+            }
+            if modified > cutoff || referenced.contains(&digest) {
+                continue;
+            }
+            let path = self.digest_path("blobs", &digest, "")?;
+            if let Err(err) = fs::remove_file(&path).await {
+                warn!(?err, path = %path.display(), "failed to evict artifact");
+                continue;
+            }
+            let signatures = self.digest_path("signatures", &digest, ".json")?;
+            if let Err(err) = fs::remove_file(&signatures).await {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!(?err, path = %signatures.display(), "failed to evict signatures");
+                }
+            }
+            size = size.saturating_sub(len);
+            evicted.push(digest);
+        }
+        if !evicted.is_empty() {
+            self.remove_records(&evicted).await?;
+        }
+        Ok(evicted)
+    }
+
+    /// Removes the records of references referring to any of `digests`
+    async fn remove_records(&self, digests: &[String]) -> anyhow::Result<()> {
+        let dir = self.dir.join("refs");
+        let Ok(mut entries) = fs::read_dir(&dir).await else {
+            return Ok(());
+        };
+        let mut records = BTreeMap::default();
+        while let Some(entry) = entries.next_entry().await? {
+            if let Ok(digest) = fs::read_to_string(entry.path()).await {
+                records.insert(entry.path(), digest);
+            }
+        }
+        for (path, digest) in records {
+            if digests.iter().any(|evicted| *evicted == digest.trim()) {
+                fs::remove_file(&path)
+                    .await
+                    .with_context(|| format!("failed to remove `{}`", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes `buf` to a temporary file next to `path` and moves it to `path` afterwards, such that
+/// readers never observe partially written files
+async fn write_atomic(path: &Path, buf: &[u8]) -> anyhow::Result<()> {
+    let dir = path.parent().context("path has no parent")?;
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("failed to create `{}`", dir.display()))?;
+    let tmp = dir.join(format!(".{}.tmp", ulid::Ulid::new()));
+    let res = async {
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(buf).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, path).await
+    }
+    .await;
+    if let Err(err) = res {
+        let _ = fs::remove_file(&tmp).await;
+        return Err(
+            anyhow::Error::new(err).context(format!("failed to write `{}`", path.display()))
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(c: char) -> String {
+        format!("sha256:{}", c.to_string().repeat(64))
+    }
+
+    #[tokio::test]
+    async fn oci_cache() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("wasmcloud-oci-cache-{}", ulid::Ulid::new()));
+        let cache = OciCache::new(&dir);
+        let (a, b, c) = (digest('a'), digest('b'), digest('c'));
+
+        assert_eq!(cache.resolve("ghcr.io/foo/bar:0.1.0").await?, None);
+        assert_eq!(cache.get(&a).await?, None);
+        assert!(cache.get("sha256:../../etc/passwd").await.is_err());
+
+        let path = cache.insert(&a, b"aaaa").await?;
+        cache.record("ghcr.io/foo/bar:0.1.0", &a).await?;
+        assert_eq!(cache.get(&a).await?, Some(path.clone()));
+        assert_eq!(fs::read(&path).await?, b"aaaa");
+        assert_eq!(
+            cache.resolve("GHCR.io/foo/bar:0.1.0").await?,
+            Some(a.clone())
+        );
+        assert_eq!(
+            cache.resolve(format!("ghcr.io/foo/bar@{b}")).await?,
+            Some(b.clone())
+        );
+
+        let signatures = vec![OciSignature {
+            payload: br#"{"critical":{}}"#.to_vec(),
+            signature: "c2lnbmF0dXJl".into(),
+        }];
+        cache.put_signatures(&a, &signatures).await?;
+        assert_eq!(cache.signatures(&a).await?, signatures);
+        assert_eq!(cache.signatures(&b).await?, Vec::default());
+
+        cache.insert(&b, b"bbbb").await?;
+        cache.record("ghcr.io/foo/bar:0.2.0", &b).await?;
+        cache.insert(&c, b"cccc").await?;
+
+        // Nothing is evicted within the grace period or if the size limit is not exceeded
+        assert!(cache
+            .gc(0, &HashSet::default(), Duration::from_secs(60))
+            .await?
+            .is_empty());
+        assert!(cache
+            .gc(12, &HashSet::default(), Duration::ZERO)
+            .await?
+            .is_empty());
+
+        let evicted = cache
+            .gc(4, &HashSet::from([b.clone()]), Duration::ZERO)
+            .await?;
+        assert_eq!(evicted.len(), 2);
+        assert!(evicted.contains(&a) && evicted.contains(&c));
+        assert_eq!(cache.get(&a).await?, None);
+        assert_eq!(cache.resolve("ghcr.io/foo/bar:0.1.0").await?, None);
+        assert_eq!(cache.signatures(&a).await?, Vec::default());
+        assert!(cache.get(&b).await?.is_some());
+        assert_eq!(cache.resolve("ghcr.io/foo/bar:0.2.0").await?, Some(b));
+
+        fs::remove_dir_all(&dir).await?;
+        Ok(())
+    }
+}
@@ -18,26 +18,36 @@ pub mod registry;
 /// Secret management
 pub mod secrets;
 
+/// Key-value storage of lattice data and named config
+pub mod store;
+
 /// wasmCloud host metrics
 pub(crate) mod metrics;
 
+/// OCI artifact signature verification
+pub(crate) mod signature;
+
 pub use metrics::HostMetrics;
 pub use oci::Config as OciConfig;
 pub use policy::{
     HostInfo as PolicyHostInfo, Manager as PolicyManager, Response as PolicyResponse,
 };
 pub use secrets::Manager as SecretsManager;
+pub use signature::SignatureVerificationError;
 pub use wasmbus::{Host as WasmbusHost, HostConfig as WasmbusHostConfig};
 pub use wasmcloud_core::{OciFetcher, RegistryAuth, RegistryConfig, RegistryType};
 
 pub use url;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, ensure, Context as _};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher as _};
 use tokio::fs;
-use tracing::{debug, instrument, warn};
+use tokio::sync::mpsc;
+use tracing::{debug, error, instrument, warn};
 use url::Url;
 use wascap::jwt;
 
@@ -102,12 +112,80 @@ impl ResourceRef<'_> {
     }
 }
 
+/// Returns whether `reference` refers to an OCI artifact, as opposed to a local file
+pub(crate) fn is_oci_reference(reference: &str) -> bool {
+    matches!(ResourceRef::try_from(reference), Ok(ResourceRef::Oci(_)))
+}
+
+/// Returns the OCI reference `reference` refers to, without any scheme prefix
+pub(crate) fn oci_reference(reference: &str) -> Option<&str> {
+    match ResourceRef::try_from(reference) {
+        Ok(ResourceRef::Oci(oci_ref)) => Some(oci_ref),
+        _ => None,
+    }
+}
+
+/// Watches the file at `path` and returns the watcher along with a receiver, which yields a value
+/// whenever the file is created or modified. Changes occurring while a value is pending are
+/// coalesced. The file is watched as long as the returned watcher is not dropped.
+pub(crate) fn watch_file(
+    path: &Path,
+) -> anyhow::Result<(notify::RecommendedWatcher, mpsc::Receiver<()>)> {
+    // Watch the parent directory, since editors commonly replace files instead of writing
+    // them in place
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let name = path.file_name().map(ToOwned::to_owned);
+    let (changes_tx, changes_rx) = mpsc::channel(1);
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event)
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_)
+                        | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_))
+                ) && event.paths.iter().any(|p| p.file_name() == name.as_deref()) =>
+            {
+                // A pending change covers this change
+                let _ = changes_tx.try_send(());
+            }
+            Ok(..) => {}
+            Err(err) => error!(?err, "failed to watch file"),
+        })
+        .context("failed to create file watcher")?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch `{}`", dir.display()))?;
+    Ok((watcher, changes_rx))
+}
+
+/// Returns the fetcher of OCI artifacts from the registry at `authority`
+fn oci_fetcher(
+    authority: Option<&str>,
+    oci_opts: &OciConfig,
+    registry_config: &HashMap<String, RegistryConfig>,
+) -> OciFetcher {
+    let fetcher = authority
+        .and_then(|authority| registry_config.get(authority))
+        .map(OciFetcher::from)
+        .unwrap_or_default()
+        .with_additional_ca_paths(&oci_opts.additional_ca_paths)
+        .with_offline(oci_opts.offline);
+    if let Some(dir) = &oci_opts.cache_dir {
+        fetcher.with_cache_dir(dir)
+    } else {
+        fetcher
+    }
+}
+
 /// Fetch an component from a reference.
-#[instrument(level = "debug", skip(allow_file_load, registry_config))]
+#[instrument(level = "debug", skip(allow_file_load, oci_opts, registry_config))]
 pub async fn fetch_component(
     component_ref: &str,
     allow_file_load: bool,
-    additional_ca_paths: &Vec<PathBuf>,
+    oci_opts: &OciConfig,
     registry_config: &HashMap<String, RegistryConfig>,
 ) -> anyhow::Result<Vec<u8>> {
     match ResourceRef::try_from(component_ref)? {
@@ -120,26 +198,24 @@ pub async fn fetch_component(
                 .await
                 .context("failed to read component")
         }
-        ref oci_ref @ ResourceRef::Oci(component_ref) => oci_ref
-            .authority()
-            .and_then(|authority| registry_config.get(authority))
-            .map(OciFetcher::from)
-            .unwrap_or_default()
-            .with_additional_ca_paths(additional_ca_paths)
-            .fetch_component(component_ref)
-            .await
-            .with_context(|| {
-                format!("failed to fetch component under OCI reference `{component_ref}`")
-            }),
+        ref oci_ref @ ResourceRef::Oci(component_ref) => {
+            oci_fetcher(oci_ref.authority(), oci_opts, registry_config)
+                .fetch_component(component_ref)
+                .await
+                .with_context(|| {
+                    format!("failed to fetch component under OCI reference `{component_ref}`")
+                })
+        }
     }
 }
 
 /// Fetch a provider from a reference.
-#[instrument(skip(registry_config, host_id), fields(provider_ref = %provider_ref.as_ref()))]
+#[instrument(skip(registry_config, oci_opts, host_id), fields(provider_ref = %provider_ref.as_ref()))]
 pub async fn fetch_provider(
     provider_ref: impl AsRef<str>,
     host_id: impl AsRef<str>,
     allow_file_load: bool,
+    oci_opts: &OciConfig,
     registry_config: &HashMap<String, RegistryConfig>,
 ) -> anyhow::Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
     match ResourceRef::try_from(provider_ref.as_ref())? {
@@ -157,16 +233,14 @@ pub async fn fetch_provider(
             .await
             .context("failed to read provider")
         }
-        ref oci_ref @ ResourceRef::Oci(provider_ref) => oci_ref
-            .authority()
-            .and_then(|authority| registry_config.get(authority))
-            .map(OciFetcher::from)
-            .unwrap_or_default()
-            .fetch_provider(&provider_ref, host_id)
-            .await
-            .with_context(|| {
-                format!("failed to fetch provider under OCI reference `{provider_ref}`")
-            }),
+        ref oci_ref @ ResourceRef::Oci(provider_ref) => {
+            oci_fetcher(oci_ref.authority(), oci_opts, registry_config)
+                .fetch_provider(&provider_ref, host_id)
+                .await
+                .with_context(|| {
+                    format!("failed to fetch provider under OCI reference `{provider_ref}`")
+                })
+        }
     }
 }
 
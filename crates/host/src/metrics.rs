@@ -1,4 +1,7 @@
-use wasmcloud_tracing::{Counter, Histogram, KeyValue, Meter, Unit};
+use std::sync::Arc;
+use std::time::Duration;
+
+use wasmcloud_tracing::{Counter, Gauge, Histogram, KeyValue, Meter, Unit, UpDownCounter};
 
 /// `HostMetrics` encapsulates the set of metrics emitted by the wasmcloud host
 #[derive(Clone, Debug)]
@@ -10,6 +13,30 @@ pub struct HostMetrics {
     pub component_invocations: Counter<u64>,
     /// The count of the number of times an component invocation resulted in an error.
     pub component_errors: Counter<u64>,
+    /// The amount of fuel consumed by each component invocation, only recorded if fuel metering is enabled.
+    pub component_fuel_consumed: Histogram<u64>,
+    /// The number of component instances currently handling an invocation.
+    pub component_active_instances: UpDownCounter<i64>,
+    /// The configured maximum number of concurrent instances of each component.
+    pub component_max_instances: Gauge<u64>,
+    /// The number of capability provider processes currently running.
+    pub provider_processes: UpDownCounter<i64>,
+    /// The count of the number of times a capability provider process was restarted.
+    pub provider_restarts: Counter<u64>,
+    /// The time it took to fetch named configuration in nanoseconds.
+    pub config_fetch_duration_ns: Histogram<u64>,
+    /// The time it took to fetch secrets in nanoseconds.
+    pub secrets_fetch_duration_ns: Histogram<u64>,
+    /// The time it took to receive a policy decision from the policy service in nanoseconds.
+    pub policy_decision_duration_ns: Histogram<u64>,
+    /// The count of the number of requests denied by policy.
+    pub policy_denials: Counter<u64>,
+    /// The number of bytes of components and providers fetched from OCI registries.
+    pub oci_fetch_bytes: Counter<u64>,
+    /// The time it took to fetch a component or provider from an OCI registry in nanoseconds.
+    pub oci_fetch_duration_ns: Histogram<u64>,
+    /// The count of the number of control interface requests handled.
+    pub ctl_requests: Counter<u64>,
 
     /// The host's ID.
     // TODO this is actually configured as an InstrumentationScope attribute on the global meter,
@@ -42,21 +69,99 @@ impl HostMetrics {
             .with_description("Number of component errors")
             .init();
 
+        let component_fuel_consumed = meter
+            .u64_histogram("wasmcloud_host.component.fuel_consumed")
+            .with_description("Amount of fuel consumed by each component invocation")
+            .init();
+
+        let component_active_instances = meter
+            .i64_up_down_counter("wasmcloud_host.component.active_instances")
+            .with_description("Number of component instances currently handling an invocation")
+            .init();
+
+        let component_max_instances = meter
+            .u64_gauge("wasmcloud_host.component.max_instances")
+            .with_description("Configured maximum number of concurrent instances of a component")
+            .init();
+
+        let provider_processes = meter
+            .i64_up_down_counter("wasmcloud_host.provider.processes")
+            .with_description("Number of running capability provider processes")
+            .init();
+
+        let provider_restarts = meter
+            .u64_counter("wasmcloud_host.provider.restarts")
+            .with_description("Number of capability provider process restarts")
+            .init();
+
+        let config_fetch_duration_ns = meter
+            .u64_histogram("wasmcloud_host.config.fetch.duration")
+            .with_description("Duration in nanoseconds each named configuration fetch took")
+            .with_unit(Unit::new("nanoseconds"))
+            .init();
+
+        let secrets_fetch_duration_ns = meter
+            .u64_histogram("wasmcloud_host.secrets.fetch.duration")
+            .with_description("Duration in nanoseconds each secrets fetch took")
+            .with_unit(Unit::new("nanoseconds"))
+            .init();
+
+        let policy_decision_duration_ns = meter
+            .u64_histogram("wasmcloud_host.policy.decision.duration")
+            .with_description("Duration in nanoseconds each policy decision request took")
+            .with_unit(Unit::new("nanoseconds"))
+            .init();
+
+        let policy_denials = meter
+            .u64_counter("wasmcloud_host.policy.denials")
+            .with_description("Number of requests denied by policy")
+            .init();
+
+        let oci_fetch_bytes = meter
+            .u64_counter("wasmcloud_host.oci.fetch.bytes")
+            .with_description("Number of bytes fetched from OCI registries")
+            .with_unit(Unit::new("bytes"))
+            .init();
+
+        let oci_fetch_duration_ns = meter
+            .u64_histogram("wasmcloud_host.oci.fetch.duration")
+            .with_description("Duration in nanoseconds each OCI artifact fetch took")
+            .with_unit(Unit::new("nanoseconds"))
+            .init();
+
+        let ctl_requests = meter
+            .u64_counter("wasmcloud_host.ctl.requests")
+            .with_description("Number of control interface requests")
+            .init();
+
         Self {
             handle_rpc_message_duration_ns: wasmcloud_host_handle_rpc_message_duration_ns,
             component_invocations: component_invocation_count,
             component_errors: component_error_count,
+            component_fuel_consumed,
+            component_active_instances,
+            component_max_instances,
+            provider_processes,
+            provider_restarts,
+            config_fetch_duration_ns,
+            secrets_fetch_duration_ns,
+            policy_decision_duration_ns,
+            policy_denials,
+            oci_fetch_bytes,
+            oci_fetch_duration_ns,
+            ctl_requests,
             host_id,
             lattice_id,
         }
     }
 
-    /// Record the result of invoking a component, including the elapsed time, any attributes, and whether the invocation resulted in an error.
+    /// Record the result of invoking a component, including the elapsed time, any attributes, whether the invocation resulted in an error and the amount of fuel consumed, if metered.
     pub(crate) fn record_component_invocation(
         &self,
         elapsed: u64,
         attributes: &[KeyValue],
         error: bool,
+        fuel_consumed: Option<u64>,
     ) {
         self.handle_rpc_message_duration_ns
             .record(elapsed, attributes);
@@ -64,5 +169,299 @@ impl HostMetrics {
         if error {
             self.component_errors.add(1, attributes);
         }
+        if let Some(fuel_consumed) = fuel_consumed {
+            self.component_fuel_consumed
+                .record(fuel_consumed, attributes);
+        }
+    }
+
+    /// Returns the common attributes of all host metrics.
+    fn attributes(&self, attributes: impl IntoIterator<Item = KeyValue>) -> Vec<KeyValue> {
+        [
+            KeyValue::new("lattice", self.lattice_id.clone()),
+            KeyValue::new("host", self.host_id.clone()),
+        ]
+        .into_iter()
+        .chain(attributes)
+        .collect()
+    }
+
+    /// Record the configured maximum number of instances of a component, which is `0` once the component is removed from the host.
+    pub(crate) fn record_component_max_instances(&self, component_id: &str, max_instances: u64) {
+        self.component_max_instances.record(
+            max_instances,
+            &self.attributes([KeyValue::new("component.id", component_id.to_string())]),
+        );
+    }
+
+    /// Record a component instance starting to handle an invocation, which is considered finished once the returned guard is dropped.
+    pub(crate) fn start_component_instance(
+        self: &Arc<Self>,
+        component_id: Arc<str>,
+    ) -> ActiveInstanceGuard {
+        self.record_component_active_instances(&component_id, 1);
+        ActiveInstanceGuard {
+            metrics: Arc::clone(self),
+            component_id,
+        }
+    }
+
+    fn record_component_active_instances(&self, component_id: &str, delta: i64) {
+        self.component_active_instances.add(
+            delta,
+            &self.attributes([KeyValue::new("component.id", component_id.to_string())]),
+        );
+    }
+
+    /// Record a capability provider process being spawned (`delta` of `1`) or exiting (`delta` of `-1`).
+    pub(crate) fn record_provider_processes(&self, provider_id: &str, delta: i64) {
+        self.provider_processes.add(
+            delta,
+            &self.attributes([KeyValue::new("provider.id", provider_id.to_string())]),
+        );
+    }
+
+    /// Record a restart of a capability provider process.
+    pub(crate) fn record_provider_restart(&self, provider_id: &str) {
+        self.provider_restarts.add(
+            1,
+            &self.attributes([KeyValue::new("provider.id", provider_id.to_string())]),
+        );
+    }
+
+    /// Record the time it took to fetch named configuration and secrets.
+    pub(crate) fn record_config_fetch(&self, config: Duration, secrets: Duration) {
+        let attributes = self.attributes([]);
+        self.config_fetch_duration_ns
+            .record(duration_ns(config), &attributes);
+        self.secrets_fetch_duration_ns
+            .record(duration_ns(secrets), &attributes);
+    }
+
+    /// Record a policy decision of `kind`, including the time it took to request it from the policy service, if it was not cached.
+    pub(crate) fn record_policy_decision(
+        &self,
+        kind: &str,
+        elapsed: Option<Duration>,
+        permitted: bool,
+    ) {
+        let attributes = self.attributes([KeyValue::new("kind", kind.to_string())]);
+        if let Some(elapsed) = elapsed {
+            self.policy_decision_duration_ns
+                .record(duration_ns(elapsed), &attributes);
+        }
+        if !permitted {
+            self.policy_denials.add(1, &attributes);
+        }
+    }
+
+    /// Record fetching `bytes` of a component or provider, as indicated by `kind`, from an OCI registry.
+    pub(crate) fn record_oci_fetch(&self, kind: &'static str, bytes: u64, elapsed: Duration) {
+        let attributes = self.attributes([KeyValue::new("kind", kind)]);
+        self.oci_fetch_bytes.add(bytes, &attributes);
+        self.oci_fetch_duration_ns
+            .record(duration_ns(elapsed), &attributes);
+    }
+
+    /// Record handling a control interface request for `operation`.
+    pub(crate) fn record_ctl_request(&self, operation: String) {
+        self.ctl_requests
+            .add(1, &self.attributes([KeyValue::new("operation", operation)]));
+    }
+}
+
+/// Guard of a component instance handling an invocation, see [`HostMetrics::start_component_instance`]
+#[derive(Debug)]
+pub(crate) struct ActiveInstanceGuard {
+    metrics: Arc<HostMetrics>,
+    component_id: Arc<str>,
+}
+
+impl Drop for ActiveInstanceGuard {
+    fn drop(&mut self) {
+        self.metrics
+            .record_component_active_instances(&self.component_id, -1);
+    }
+}
+
+/// Converts `duration` to nanoseconds, saturating at [`u64::MAX`].
+fn duration_ns(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use prometheus::Registry;
+
+    fn metrics() -> (Arc<HostMetrics>, Registry, SdkMeterProvider) {
+        let registry = Registry::new();
+        let exporter = opentelemetry_prometheus::exporter()
+            .with_registry(registry.clone())
+            .without_scope_info()
+            .without_target_info()
+            .build()
+            .expect("failed to build Prometheus exporter");
+        let provider = SdkMeterProvider::builder().with_reader(exporter).build();
+        let metrics = HostMetrics::new(
+            &provider.meter("test"),
+            "host".to_string(),
+            "lattice".to_string(),
+        );
+        (Arc::new(metrics), registry, provider)
+    }
+
+    /// Returns the value of the metric `name` with the label `key` set to `value`, which is the
+    /// number of observations for histograms
+    fn value(registry: &Registry, name: &str, (key, value): (&str, &str)) -> Option<f64> {
+        let family = registry
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == name)?;
+        let metric = family.get_metric().iter().find(|metric| {
+            metric
+                .get_label()
+                .iter()
+                .any(|label| label.get_name() == key && label.get_value() == value)
+        })?;
+        match family.get_field_type() {
+            prometheus::proto::MetricType::COUNTER => Some(metric.get_counter().get_value()),
+            prometheus::proto::MetricType::GAUGE => Some(metric.get_gauge().get_value()),
+            #[allow(clippy::cast_precision_loss)]
+            prometheus::proto::MetricType::HISTOGRAM => {
+                Some(metric.get_histogram().get_sample_count() as f64)
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn component_max_instances() {
+        const MAX: &str = "wasmcloud_host_component_max_instances";
+        const ID: (&str, &str) = ("component_id", "component");
+
+        let (metrics, registry, _provider) = metrics();
+        assert_eq!(value(&registry, MAX, ID), None);
+
+        // A component is scaled by instantiating it again and stopping the previous instance,
+        // which must leave the maximum of the new instance in place
+        metrics.record_component_max_instances("component", 2);
+        assert_eq!(value(&registry, MAX, ID), Some(2.));
+        metrics.record_component_max_instances("component", 4);
+        assert_eq!(value(&registry, MAX, ID), Some(4.));
+
+        // The maximum is only reset once the component is removed from the host
+        metrics.record_component_max_instances("component", 0);
+        assert_eq!(value(&registry, MAX, ID), Some(0.));
+    }
+
+    #[tokio::test]
+    async fn component_active_instances() {
+        const ACTIVE: &str = "wasmcloud_host_component_active_instances";
+        const ID: (&str, &str) = ("component_id", "component");
+
+        let (metrics, registry, _provider) = metrics();
+        let first = metrics.start_component_instance("component".into());
+        let second = metrics.start_component_instance("component".into());
+        assert_eq!(value(&registry, ACTIVE, ID), Some(2.));
+        drop(first);
+        assert_eq!(value(&registry, ACTIVE, ID), Some(1.));
+        drop(second);
+        assert_eq!(value(&registry, ACTIVE, ID), Some(0.));
+
+        // An invocation, which is aborted, e.g. because the component is stopped, must not leak
+        // an active instance
+        let active = metrics.start_component_instance("component".into());
+        let task = tokio::spawn(async move {
+            let _active = active;
+            std::future::pending::<()>().await;
+        });
+        assert_eq!(value(&registry, ACTIVE, ID), Some(1.));
+        task.abort();
+        assert!(task.await.is_err_and(|err| err.is_cancelled()));
+        assert_eq!(value(&registry, ACTIVE, ID), Some(0.));
+    }
+
+    #[test]
+    fn providers() {
+        const ID: (&str, &str) = ("provider_id", "provider");
+
+        let (metrics, registry, _provider) = metrics();
+        metrics.record_provider_processes("provider", 1);
+        metrics.record_provider_restart("provider");
+        metrics.record_provider_processes("provider", -1);
+        metrics.record_provider_processes("provider", 1);
+        assert_eq!(
+            value(&registry, "wasmcloud_host_provider_processes", ID),
+            Some(1.)
+        );
+        assert_eq!(
+            value(&registry, "wasmcloud_host_provider_restarts_total", ID),
+            Some(1.)
+        );
+    }
+
+    #[test]
+    fn policy_decisions() {
+        const KIND: (&str, &str) = ("kind", "performInvocation");
+
+        let (metrics, registry, _provider) = metrics();
+        metrics.record_policy_decision("performInvocation", Some(Duration::from_millis(1)), true);
+        // Cached decisions are not timed
+        metrics.record_policy_decision("performInvocation", None, false);
+        assert_eq!(
+            value(&registry, "wasmcloud_host_policy_decision_duration", KIND),
+            Some(1.)
+        );
+        assert_eq!(
+            value(&registry, "wasmcloud_host_policy_denials_total", KIND),
+            Some(1.)
+        );
+    }
+
+    #[test]
+    fn fetches() {
+        const HOST: (&str, &str) = ("host", "host");
+
+        let (metrics, registry, _provider) = metrics();
+        metrics.record_config_fetch(Duration::from_millis(1), Duration::from_millis(2));
+        metrics.record_oci_fetch("component", 42, Duration::from_millis(1));
+        metrics.record_oci_fetch("component", 8, Duration::from_millis(1));
+        metrics.record_ctl_request("component.scale".into());
+        assert_eq!(
+            value(&registry, "wasmcloud_host_config_fetch_duration", HOST),
+            Some(1.)
+        );
+        assert_eq!(
+            value(&registry, "wasmcloud_host_secrets_fetch_duration", HOST),
+            Some(1.)
+        );
+        assert_eq!(
+            value(
+                &registry,
+                "wasmcloud_host_oci_fetch_bytes_total",
+                ("kind", "component")
+            ),
+            Some(50.)
+        );
+        assert_eq!(
+            value(
+                &registry,
+                "wasmcloud_host_oci_fetch_duration",
+                ("kind", "component")
+            ),
+            Some(2.)
+        );
+        assert_eq!(
+            value(
+                &registry,
+                "wasmcloud_host_ctl_requests_total",
+                ("operation", "component.scale")
+            ),
+            Some(1.)
+        );
     }
 }
@@ -1,8 +1,11 @@
 // Adapted from
 // https://github.com/wasmCloud/wasmcloud-otp/blob/5f13500646d9e077afa1fca67a3fe9c8df5f3381/host_core/native/hostcore_wasmcloud_native/src/oci.rs
 
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
 /// Configuration options for OCI operations.
@@ -20,4 +23,57 @@ pub struct Config {
     pub oci_user: Option<String>,
     /// Password for the OCI registry specified by `oci_registry`.
     pub oci_password: Option<String>,
+    /// Paths to PEM-encoded public keys trusted to sign OCI artifacts
+    pub signature_trust_roots: Vec<PathBuf>,
+    /// Signature policy applied to OCI registries without a policy in `registry_signature_policies`
+    pub signature_policy: SignaturePolicy,
+    /// Signature policies of specific OCI registries, keyed by registry
+    pub registry_signature_policies: HashMap<String, SignaturePolicy>,
+    /// Directory, in which fetched OCI artifacts are cached. Defaults to a directory in the
+    /// temporary directory of the system
+    pub cache_dir: Option<PathBuf>,
+    /// Whether to only run OCI artifacts present in the cache, without contacting registries
+    pub offline: bool,
+    /// Size in bytes, above which cached OCI artifacts not referenced by running workloads are
+    /// evicted from the cache
+    pub cache_max_size: Option<u64>,
+}
+
+impl Config {
+    /// Returns the signature policy applied to OCI artifacts fetched from `registry`
+    #[must_use]
+    pub fn signature_policy(&self, registry: &str) -> SignaturePolicy {
+        self.registry_signature_policies
+            .get(registry)
+            .copied()
+            .unwrap_or(self.signature_policy)
+    }
+}
+
+/// Policy for verifying signatures of OCI artifacts before they are run
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// Refuse to run artifacts without a valid signature from a trusted key
+    Enforce,
+    /// Run artifacts without a valid signature from a trusted key, but log a warning
+    Warn,
+    /// Do not verify signatures
+    #[default]
+    Off,
+}
+
+impl FromStr for SignaturePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "enforce" => Ok(Self::Enforce),
+            "warn" => Ok(Self::Warn),
+            "off" => Ok(Self::Off),
+            _ => {
+                bail!("invalid signature policy `{s}`, expected one of `enforce`, `warn` or `off`")
+            }
+        }
+    }
 }
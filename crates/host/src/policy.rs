@@ -2,8 +2,9 @@ use core::time::Duration;
 
 use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
 use futures::{
@@ -13,11 +14,16 @@ use futures::{
 use serde::{Deserialize, Serialize};
 use tokio::spawn;
 use tokio::sync::RwLock;
-use tracing::{debug, error, instrument, trace, warn};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info, instrument, trace, warn};
 use ulid::Ulid;
 use uuid::Uuid;
 use wascap::jwt;
 
+use crate::{watch_file, HostMetrics};
+
+mod local;
+
 // NOTE: All requests will be v1 until the schema changes, at which point we can change the version
 // per-request type
 const POLICY_TYPE_VERSION: &str = "v1";
@@ -84,6 +90,83 @@ pub struct PerformInvocationRequest {
     pub target: ComponentInformation,
 }
 
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
+/// A request to put a link between a source and a target
+pub struct PutLinkRequest {
+    /// The unique identifier of the source component or provider
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    /// The unique identifier of the target component or provider
+    pub target: String,
+    /// The name of the link
+    pub name: String,
+    /// The WIT namespace of the linked interfaces
+    #[serde(rename = "witNamespace")]
+    pub wit_namespace: String,
+    /// The WIT package of the linked interfaces
+    #[serde(rename = "witPackage")]
+    pub wit_package: String,
+    /// The linked interfaces
+    pub interfaces: Vec<String>,
+    /// Names of the configuration provided to the source
+    #[serde(rename = "sourceConfig")]
+    pub source_config: Vec<String>,
+    /// Names of the configuration provided to the target
+    #[serde(rename = "targetConfig")]
+    pub target_config: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
+/// A request to delete the link of a source
+pub struct DeleteLinkRequest {
+    /// The unique identifier of the source component or provider
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    /// The name of the link
+    pub name: String,
+    /// The WIT namespace of the linked interfaces
+    #[serde(rename = "witNamespace")]
+    pub wit_namespace: String,
+    /// The WIT package of the linked interfaces
+    #[serde(rename = "witPackage")]
+    pub wit_package: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
+/// A request to put or delete named configuration
+pub struct ConfigRequest {
+    /// The name of the configuration
+    pub name: String,
+    /// The values being put, empty for deletions
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
+/// A request to put or delete a host label
+pub struct LabelRequest {
+    /// The key of the label
+    pub key: String,
+    /// The value being put, if any
+    pub value: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
+/// A request to update registry credentials. The credentials themselves are not included
+pub struct PutRegistriesRequest {
+    /// The registries, whose credentials are updated
+    pub registries: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
+/// A request to stop a host
+pub struct StopHostRequest {
+    /// The time in milliseconds the host may take to stop, if any
+    #[serde(rename = "timeoutMs")]
+    pub timeout_ms: Option<u64>,
+    /// Whether the host stops after draining its workloads
+    pub drain: bool,
+}
+
 /// Relevant information about the host that is receiving the invocation, or starting the component or provider
 #[derive(Clone, Debug, Serialize)]
 pub struct HostInfo {
@@ -98,7 +181,7 @@ pub struct HostInfo {
 }
 
 /// The action being requested
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub enum RequestKind {
     /// The host is checking whether it may invoke the target component
     #[serde(rename = "performInvocation")]
@@ -109,11 +192,59 @@ pub enum RequestKind {
     /// The host is checking whether it may start the target provider
     #[serde(rename = "startProvider")]
     StartProvider,
+    /// The host is checking whether it may put a link
+    #[serde(rename = "putLink")]
+    PutLink,
+    /// The host is checking whether it may delete a link
+    #[serde(rename = "deleteLink")]
+    DeleteLink,
+    /// The host is checking whether it may put named configuration
+    #[serde(rename = "putConfig")]
+    PutConfig,
+    /// The host is checking whether it may delete named configuration
+    #[serde(rename = "deleteConfig")]
+    DeleteConfig,
+    /// The host is checking whether it may return the revision history of named configuration
+    #[serde(rename = "getConfigHistory")]
+    GetConfigHistory,
+    /// The host is checking whether it may put one of its labels
+    #[serde(rename = "putLabel")]
+    PutLabel,
+    /// The host is checking whether it may delete one of its labels
+    #[serde(rename = "deleteLabel")]
+    DeleteLabel,
+    /// The host is checking whether it may update registry credentials
+    #[serde(rename = "putRegistries")]
+    PutRegistries,
+    /// The host is checking whether it may stop
+    #[serde(rename = "stopHost")]
+    StopHost,
     /// An unknown or unsupported request type
     #[serde(rename = "unknown")]
     Unknown,
 }
 
+impl RequestKind {
+    /// Returns the name of the kind, as used in policy requests
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::PerformInvocation => "performInvocation",
+            Self::StartComponent => "startComponent",
+            Self::StartProvider => "startProvider",
+            Self::PutLink => "putLink",
+            Self::DeleteLink => "deleteLink",
+            Self::PutConfig => "putConfig",
+            Self::DeleteConfig => "deleteConfig",
+            Self::GetConfigHistory => "getConfigHistory",
+            Self::PutLabel => "putLabel",
+            Self::DeleteLabel => "deleteLabel",
+            Self::PutRegistries => "putRegistries",
+            Self::StopHost => "stopHost",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Hash)]
 #[serde(untagged)]
 /// The body of a policy request, typed by the request kind
@@ -124,32 +255,81 @@ pub enum RequestBody {
     StartComponent(ComponentInformation),
     /// A request to start a provider on a host
     StartProvider(ProviderInformation),
+    /// A request to put a link
+    PutLink(PutLinkRequest),
+    /// A request to delete a link
+    DeleteLink(DeleteLinkRequest),
+    /// A request to put named configuration
+    PutConfig(ConfigRequest),
+    /// A request to delete named configuration
+    DeleteConfig(ConfigRequest),
+    /// A request to get the revision history, including past values, of named configuration
+    GetConfigHistory(ConfigRequest),
+    /// A request to put a host label
+    PutLabel(LabelRequest),
+    /// A request to delete a host label
+    DeleteLabel(LabelRequest),
+    /// A request to update registry credentials
+    PutRegistries(PutRegistriesRequest),
+    /// A request to stop a host
+    StopHost(StopHostRequest),
     /// Request body has an unknown type
     Unknown,
 }
 
-impl From<&RequestBody> for RequestKey {
-    fn from(val: &RequestBody) -> RequestKey {
-        match val {
-            RequestBody::StartComponent(ref req) => RequestKey {
+impl RequestBody {
+    /// Returns the kind of the request
+    #[must_use]
+    pub fn kind(&self) -> RequestKind {
+        match self {
+            Self::PerformInvocation(_) => RequestKind::PerformInvocation,
+            Self::StartComponent(_) => RequestKind::StartComponent,
+            Self::StartProvider(_) => RequestKind::StartProvider,
+            Self::PutLink(_) => RequestKind::PutLink,
+            Self::DeleteLink(_) => RequestKind::DeleteLink,
+            Self::PutConfig(_) => RequestKind::PutConfig,
+            Self::DeleteConfig(_) => RequestKind::DeleteConfig,
+            Self::GetConfigHistory(_) => RequestKind::GetConfigHistory,
+            Self::PutLabel(_) => RequestKind::PutLabel,
+            Self::DeleteLabel(_) => RequestKind::DeleteLabel,
+            Self::PutRegistries(_) => RequestKind::PutRegistries,
+            Self::StopHost(_) => RequestKind::StopHost,
+            Self::Unknown => RequestKind::Unknown,
+        }
+    }
+
+    /// Returns the key, under which the decision of the request is cached. Decisions of
+    /// control operations are not cached, since they are taken once per operation
+    fn cache_key(&self) -> Option<RequestKey> {
+        match self {
+            RequestBody::StartComponent(ref req) => Some(RequestKey {
                 kind: RequestKind::StartComponent,
                 cache_key: format!("{}_{}", req.component_id, req.image_ref),
-            },
-            RequestBody::StartProvider(ref req) => RequestKey {
+            }),
+            RequestBody::StartProvider(ref req) => Some(RequestKey {
                 kind: RequestKind::StartProvider,
                 cache_key: format!("{}_{}", req.provider_id, req.image_ref),
-            },
-            RequestBody::PerformInvocation(ref req) => RequestKey {
+            }),
+            RequestBody::PerformInvocation(ref req) => Some(RequestKey {
                 kind: RequestKind::PerformInvocation,
                 cache_key: format!(
                     "{}_{}_{}_{}",
                     req.target.component_id, req.target.image_ref, req.interface, req.function
                 ),
-            },
-            RequestBody::Unknown => RequestKey {
+            }),
+            RequestBody::Unknown => Some(RequestKey {
                 kind: RequestKind::Unknown,
                 cache_key: String::new(),
-            },
+            }),
+            RequestBody::PutLink(_)
+            | RequestBody::DeleteLink(_)
+            | RequestBody::PutConfig(_)
+            | RequestBody::DeleteConfig(_)
+            | RequestBody::GetConfigHistory(_)
+            | RequestBody::PutLabel(_)
+            | RequestBody::DeleteLabel(_)
+            | RequestBody::PutRegistries(_)
+            | RequestBody::StopHost(_) => None,
         }
     }
 }
@@ -233,34 +413,57 @@ pub struct Manager {
     nats: async_nats::Client,
     host_info: HostInfo,
     policy_topic: Option<String>,
+    /// Rules of the local policy, used to decide requests in-process instead of using a policy
+    /// server
+    local_policy: Option<RwLock<local::Policy>>,
     policy_timeout: Duration,
     decision_cache: Arc<RwLock<HashMap<RequestKey, Response>>>,
     request_to_key: Arc<RwLock<HashMap<String, RequestKey>>>,
-    /// An abort handle for the policy changes subscription
+    metrics: Arc<HostMetrics>,
+    /// An abort handle for the policy changes subscription or the local policy file watcher
     pub policy_changes: AbortHandle,
 }
 
 impl Manager {
-    /// Construct a new policy manager. Can fail if policy_changes_topic is set but we fail to subscribe to it
-    #[instrument(skip(nats))]
+    /// Construct a new policy manager. Can fail if policy_changes_topic is set but we fail to subscribe to it,
+    /// or if policy_file is set but we fail to load or watch it
+    #[instrument(skip(nats, metrics))]
     pub async fn new(
         nats: async_nats::Client,
         host_info: HostInfo,
         policy_topic: Option<String>,
         policy_timeout: Option<Duration>,
         policy_changes_topic: Option<String>,
+        policy_file: Option<PathBuf>,
+        metrics: Arc<HostMetrics>,
     ) -> anyhow::Result<Arc<Self>> {
         const DEFAULT_POLICY_TIMEOUT: Duration = Duration::from_secs(1);
 
+        anyhow::ensure!(
+            policy_file.is_none() || policy_topic.is_none(),
+            "a policy file and a policy topic cannot be used at the same time"
+        );
+
         let (policy_changes_abort, policy_changes_abort_reg) = AbortHandle::new_pair();
 
+        let local_policy = if let Some(path) = &policy_file {
+            let policy = local::Policy::load(path)
+                .await
+                .context("failed to load policy file")?;
+            Some(RwLock::new(policy))
+        } else {
+            None
+        };
+
         let manager = Manager {
             nats: nats.clone(),
             host_info,
             policy_topic,
+            local_policy,
             policy_timeout: policy_timeout.unwrap_or(DEFAULT_POLICY_TIMEOUT),
             decision_cache: Arc::default(),
             request_to_key: Arc::default(),
+            metrics,
             policy_changes: policy_changes_abort,
         };
         let manager = Arc::new(manager);
@@ -282,6 +485,27 @@ impl Manager {
                     }
                 })
             });
+        } else if let Some(path) = policy_file {
+            let (watcher, changes) = watch_file(&path).context("failed to watch policy file")?;
+            let _policy_file_changes = spawn({
+                let manager = Arc::clone(&manager);
+                Abortable::new(ReceiverStream::new(changes), policy_changes_abort_reg).for_each(
+                    move |()| {
+                        // Keep watching the file as long as the stream is not aborted
+                        let _watcher = &watcher;
+                        let manager = Arc::clone(&manager);
+                        let path = path.clone();
+                        async move {
+                            if let Err(e) = manager.reload_policy(&path).await {
+                                error!(
+                                    "failed to reload policy file, keeping current rules: {:#}",
+                                    e
+                                );
+                            }
+                        }
+                    },
+                )
+            });
         }
 
         Ok(manager)
@@ -353,31 +577,63 @@ impl Manager {
             .await
     }
 
-    /// Sends a policy request to the policy server and caches the response
+    /// Sends a policy request to the policy server, or decides it using the local policy, and
+    /// caches the response
     #[instrument(level = "trace", skip_all)]
     pub async fn evaluate_action(&self, request: RequestBody) -> anyhow::Result<Response> {
-        let Some(policy_topic) = self.policy_topic.clone() else {
-            // Ensure we short-circuit and allow the request if no policy topic is configured
+        if self.policy_topic.is_none() && self.local_policy.is_none() {
+            // Ensure we short-circuit and allow the request if no policy is configured
             return Ok(Response {
                 request_id: String::new(),
                 permitted: true,
                 message: None,
             });
-        };
+        }
 
-        let kind = match request {
-            RequestBody::StartComponent(_) => RequestKind::StartComponent,
-            RequestBody::StartProvider(_) => RequestKind::StartProvider,
-            RequestBody::PerformInvocation(_) => RequestKind::PerformInvocation,
-            RequestBody::Unknown => RequestKind::Unknown,
+        let kind = request.kind();
+        let cache_key = request.cache_key();
+        let cached = match &cache_key {
+            Some(cache_key) => self.decision_cache.read().await.get(cache_key).cloned(),
+            None => None,
         };
-        let cache_key = (&request).into();
-        if let Some(entry) = self.decision_cache.read().await.get(&cache_key) {
+        if let Some(entry) = cached {
             trace!(?cache_key, ?entry, "using cached policy decision");
-            return Ok(entry.clone());
+            self.metrics
+                .record_policy_decision(kind.as_str(), None, entry.permitted);
+            return Ok(entry);
         }
 
         let request_id = Uuid::from_u128(Ulid::new().into()).to_string();
+        if let Some(local_policy) = &self.local_policy {
+            let start_at = Instant::now();
+            // Hold the policy lock until the decision is cached, such that no decision taken
+            // using rules being replaced is cached after a reload
+            let local_policy = local_policy.read().await;
+            let (permitted, message) = local_policy.evaluate(&request, &self.host_info);
+            let decision = Response {
+                request_id,
+                permitted,
+                message,
+            };
+            trace!(?cache_key, ?decision, "decided policy request locally");
+            self.metrics.record_policy_decision(
+                kind.as_str(),
+                Some(start_at.elapsed()),
+                decision.permitted,
+            );
+            if let Some(cache_key) = cache_key {
+                self.decision_cache
+                    .write()
+                    .await
+                    .insert(cache_key, decision.clone());
+            }
+            return Ok(decision);
+        }
+
+        let policy_topic = self
+            .policy_topic
+            .clone()
+            .context("no policy topic configured")?;
         trace!(?cache_key, "requesting policy decision");
         let payload = serde_json::to_vec(&Request {
             request_id: request_id.clone(),
@@ -390,6 +646,7 @@ impl Manager {
         let request = async_nats::Request::new()
             .payload(payload.into())
             .timeout(Some(self.policy_timeout));
+        let start_at = Instant::now();
         let res = self
             .nats
             .send_request(policy_topic, request)
@@ -397,18 +654,44 @@ impl Manager {
             .context("policy request failed")?;
         let decision = serde_json::from_slice::<Response>(&res.payload)
             .context("failed to deserialize policy response")?;
-
-        self.decision_cache
-            .write()
-            .await
-            .insert(cache_key.clone(), decision.clone()); // cache policy decision
-        self.request_to_key
-            .write()
-            .await
-            .insert(request_id, cache_key); // cache request id -> decision key
+        self.metrics.record_policy_decision(
+            kind.as_str(),
+            Some(start_at.elapsed()),
+            decision.permitted,
+        );
+
+        if let Some(cache_key) = cache_key {
+            self.decision_cache
+                .write()
+                .await
+                .insert(cache_key.clone(), decision.clone()); // cache policy decision
+            self.request_to_key
+                .write()
+                .await
+                .insert(request_id, cache_key); // cache request id -> decision key
+        }
         Ok(decision)
     }
 
+    /// Reloads the local policy from `path` and discards all decisions taken using the previous
+    /// rules
+    #[instrument(skip(self))]
+    async fn reload_policy(&self, path: &Path) -> anyhow::Result<()> {
+        let Some(local_policy) = &self.local_policy else {
+            return Ok(());
+        };
+        let policy = local::Policy::load(path).await?;
+        let mut local_policy = local_policy.write().await;
+        if *local_policy == policy {
+            debug!("policy file did not change");
+            return Ok(());
+        }
+        *local_policy = policy;
+        self.decision_cache.write().await.clear();
+        info!(path = %path.display(), "reloaded policy file");
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     async fn override_decision(&self, msg: async_nats::Message) -> anyhow::Result<()> {
         let Response {
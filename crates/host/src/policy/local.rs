@@ -0,0 +1,378 @@
+//! Policy decisions evaluated in-process from rules loaded from a local file
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use tokio::fs;
+
+use super::{
+    ConfigRequest, DeleteLinkRequest, HostInfo, LabelRequest, PolicyClaims, PutLinkRequest,
+    RequestBody, RequestKind,
+};
+
+/// Decision taken by a [`Rule`] or by a [`Policy`] if no rule matches
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Decision {
+    Allow,
+    #[default]
+    Deny,
+}
+
+/// Policy rules loaded from a local file.
+///
+/// Rules are evaluated in order and the first rule matching a request decides it. Requests not
+/// matched by any rule are decided by `default`, which denies them unless configured otherwise.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Policy {
+    #[serde(default)]
+    default: Decision,
+    #[serde(default)]
+    rules: Vec<Rule>,
+}
+
+/// Rule of a [`Policy`].
+///
+/// All criteria present must match for the rule to match. Criteria holding a list match if any of
+/// the patterns match, where patterns may contain `*` wildcards matching any sequence of
+/// characters. Annotations and host labels must all be present with matching values.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    /// Name of the rule, used in denial messages
+    #[serde(default)]
+    name: Option<String>,
+    decision: Decision,
+    /// Message returned when the rule denies a request
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    kinds: Vec<RequestKind>,
+    /// IDs of the components or providers requests concern. For link requests these are source
+    /// IDs, for config requests config names and for label requests label keys
+    #[serde(default)]
+    ids: Vec<String>,
+    #[serde(default)]
+    image_refs: Vec<String>,
+    /// Issuers of the claims embedded in the component or provider
+    #[serde(default)]
+    issuers: Vec<String>,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+    #[serde(default)]
+    host_labels: HashMap<String, String>,
+}
+
+/// The parts of a policy request rules match on
+struct Subject<'a> {
+    kind: RequestKind,
+    id: &'a str,
+    image_ref: &'a str,
+    annotations: Option<&'a BTreeMap<String, String>>,
+    claims: Option<&'a PolicyClaims>,
+}
+
+impl<'a> From<&'a RequestBody> for Subject<'a> {
+    fn from(request: &'a RequestBody) -> Self {
+        let subject = Self {
+            kind: request.kind(),
+            id: "",
+            image_ref: "",
+            annotations: None,
+            claims: None,
+        };
+        match request {
+            RequestBody::StartComponent(component) => Self {
+                id: &component.component_id,
+                image_ref: &component.image_ref,
+                annotations: Some(&component.annotations),
+                claims: component.claims.as_ref(),
+                ..subject
+            },
+            RequestBody::StartProvider(provider) => Self {
+                id: &provider.provider_id,
+                image_ref: &provider.image_ref,
+                annotations: Some(&provider.annotations),
+                claims: provider.claims.as_ref(),
+                ..subject
+            },
+            RequestBody::PerformInvocation(invocation) => Self {
+                id: &invocation.target.component_id,
+                image_ref: &invocation.target.image_ref,
+                annotations: Some(&invocation.target.annotations),
+                claims: invocation.target.claims.as_ref(),
+                ..subject
+            },
+            RequestBody::PutLink(PutLinkRequest { source_id, .. })
+            | RequestBody::DeleteLink(DeleteLinkRequest { source_id, .. }) => Self {
+                id: source_id,
+                ..subject
+            },
+            RequestBody::PutConfig(ConfigRequest { name, .. })
+            | RequestBody::DeleteConfig(ConfigRequest { name, .. })
+            | RequestBody::GetConfigHistory(ConfigRequest { name, .. }) => Self {
+                id: name,
+                ..subject
+            },
+            RequestBody::PutLabel(LabelRequest { key, .. })
+            | RequestBody::DeleteLabel(LabelRequest { key, .. }) => Self { id: key, ..subject },
+            RequestBody::PutRegistries(_) | RequestBody::StopHost(_) | RequestBody::Unknown => {
+                subject
+            }
+        }
+    }
+}
+
+/// Returns whether `value` matches `pattern`, in which `*` matches any sequence of characters
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == value;
+    };
+    let Some(mut value) = value.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return value.ends_with(part);
+        }
+        let Some(i) = value.find(part) else {
+            return false;
+        };
+        value = &value[i + part.len()..];
+    }
+    true
+}
+
+/// Returns whether `patterns` is empty or any of them matches `value`
+fn any_matches(patterns: &[String], value: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|p| glob_matches(p, value))
+}
+
+impl Rule {
+    fn matches(&self, subject: &Subject<'_>, host: &HostInfo) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&subject.kind))
+            && any_matches(&self.ids, subject.id)
+            && any_matches(&self.image_refs, subject.image_ref)
+            && (self.issuers.is_empty()
+                || subject
+                    .claims
+                    .is_some_and(|claims| any_matches(&self.issuers, &claims.issuer)))
+            && self.annotations.iter().all(|(k, v)| {
+                subject
+                    .annotations
+                    .and_then(|annotations| annotations.get(k))
+                    .is_some_and(|value| glob_matches(v, value))
+            })
+            && self.host_labels.iter().all(|(k, v)| {
+                host.labels
+                    .get(k)
+                    .is_some_and(|value| glob_matches(v, value))
+            })
+    }
+}
+
+impl Policy {
+    /// Reads and parses the policy at `path`
+    pub(crate) async fn load(path: &Path) -> anyhow::Result<Self> {
+        let buf = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read policy `{}`", path.display()))?;
+        serde_yaml::from_slice(&buf)
+            .with_context(|| format!("failed to parse policy `{}`", path.display()))
+    }
+
+    /// Decides `request` made by `host` and returns whether it is permitted along with a message
+    /// explaining denials
+    pub(crate) fn evaluate(
+        &self,
+        request: &RequestBody,
+        host: &HostInfo,
+    ) -> (bool, Option<String>) {
+        let subject = Subject::from(request);
+        let Some((i, rule)) = self
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(&subject, host))
+        else {
+            return match self.default {
+                Decision::Allow => (true, None),
+                Decision::Deny => (false, Some("denied by default policy".into())),
+            };
+        };
+        match rule.decision {
+            Decision::Allow => (true, None),
+            Decision::Deny => {
+                let message = rule.message.clone().unwrap_or_else(|| match &rule.name {
+                    Some(name) => format!("denied by policy rule `{name}`"),
+                    None => format!("denied by policy rule {i}"),
+                });
+                (false, Some(message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::policy::{ComponentInformation, ProviderInformation};
+
+    const POLICY: &str = r#"
+default: allow
+rules:
+  - name: trusted-issuer
+    decision: allow
+    kinds: [startComponent]
+    issuers: [ACME*]
+  - name: untrusted-components
+    decision: deny
+    kinds: [startComponent]
+    message: component is not signed by a trusted issuer
+  - decision: deny
+    kinds: [startProvider]
+    image_refs: ["ghcr.io/*", "*:latest"]
+    host_labels:
+      env: prod*
+  - name: frozen
+    decision: deny
+    annotations:
+      frozen: "true"
+  - name: reserved-config
+    decision: deny
+    kinds: [putConfig, deleteConfig]
+    ids: ["wasmcloud-*"]
+  - name: immutable-hosts
+    decision: deny
+    kinds: [putLabel, deleteLabel, stopHost]
+    host_labels:
+      immutable: "true"
+"#;
+
+    fn component(issuer: Option<&str>) -> RequestBody {
+        RequestBody::StartComponent(ComponentInformation {
+            component_id: "http-hello".into(),
+            image_ref: "ghcr.io/wasmcloud/http-hello:0.1.0".into(),
+            max_instances: 1,
+            annotations: BTreeMap::default(),
+            claims: issuer.map(|issuer| PolicyClaims {
+                issuer: issuer.into(),
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn provider(image_ref: &str, annotations: &[(&str, &str)]) -> RequestBody {
+        RequestBody::StartProvider(ProviderInformation {
+            provider_id: "http-server".into(),
+            image_ref: image_ref.into(),
+            annotations: annotations
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            claims: None,
+        })
+    }
+
+    fn host(env: &str) -> HostInfo {
+        HostInfo {
+            public_key: "host".into(),
+            lattice: "default".into(),
+            labels: HashMap::from([("env".into(), env.into())]),
+        }
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_matches("foo", "foo"));
+        assert!(!glob_matches("foo", "foobar"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("foo*", "foobar"));
+        assert!(glob_matches("*bar", "foobar"));
+        assert!(glob_matches("f*o*r", "foobar"));
+        assert!(!glob_matches("f*x*r", "foobar"));
+        assert!(!glob_matches("*bar*bar", "foobar"));
+    }
+
+    #[test]
+    fn evaluate() -> anyhow::Result<()> {
+        let policy: Policy = serde_yaml::from_str(POLICY)?;
+
+        assert_eq!(
+            policy.evaluate(&component(Some("ACMECORP")), &host("dev")),
+            (true, None)
+        );
+        assert_eq!(
+            policy.evaluate(&component(Some("EVIL")), &host("dev")),
+            (
+                false,
+                Some("component is not signed by a trusted issuer".into())
+            )
+        );
+        assert!(!policy.evaluate(&component(None), &host("dev")).0);
+
+        let remote = provider("ghcr.io/wasmcloud/http-server:0.24.0", &[]);
+        assert_eq!(
+            policy.evaluate(&remote, &host("production")),
+            (false, Some("denied by policy rule 2".into()))
+        );
+        assert_eq!(policy.evaluate(&remote, &host("dev")), (true, None));
+        assert_eq!(
+            policy.evaluate(
+                &provider("localhost:5000/http-server:latest", &[]),
+                &host("prod")
+            ),
+            (false, Some("denied by policy rule 2".into()))
+        );
+        assert_eq!(
+            policy.evaluate(
+                &provider("file:///tmp/http-server.par.gz", &[("frozen", "true")]),
+                &host("dev")
+            ),
+            (false, Some("denied by policy rule `frozen`".into()))
+        );
+
+        let config = RequestBody::PutConfig(ConfigRequest {
+            name: "wasmcloud-secrets".into(),
+            values: BTreeMap::default(),
+        });
+        assert_eq!(
+            policy.evaluate(&config, &host("dev")),
+            (
+                false,
+                Some("denied by policy rule `reserved-config`".into())
+            )
+        );
+        let config = RequestBody::DeleteConfig(ConfigRequest {
+            name: "greeting".into(),
+            values: BTreeMap::default(),
+        });
+        assert_eq!(policy.evaluate(&config, &host("dev")), (true, None));
+
+        let mut immutable = host("dev");
+        immutable.labels.insert("immutable".into(), "true".into());
+        let label = RequestBody::PutLabel(LabelRequest {
+            key: "zone".into(),
+            value: Some("eu".into()),
+        });
+        assert_eq!(policy.evaluate(&label, &host("dev")), (true, None));
+        assert!(!policy.evaluate(&label, &immutable).0);
+        let link = RequestBody::DeleteLink(DeleteLinkRequest {
+            source_id: "http-server".into(),
+            ..Default::default()
+        });
+        assert_eq!(policy.evaluate(&link, &immutable), (true, None));
+
+        let policy = Policy::default();
+        assert_eq!(
+            policy.evaluate(&RequestBody::Unknown, &host("dev")),
+            (false, Some("denied by default policy".into()))
+        );
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{bail, ensure, Context as _};
-use async_nats::{jetstream::kv::Store, Client};
+use async_nats::Client;
 use futures::stream;
 use futures::stream::{StreamExt, TryStreamExt};
 use secrecy::Secret;
@@ -13,10 +13,12 @@ use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_secrets_client::Client as WasmcloudSecretsClient;
 use wasmcloud_secrets_types::{Secret as WasmcloudSecret, SecretConfig};
 
+use crate::store::KvStore;
+
 #[derive(Debug)]
 /// A manager for fetching secrets from a secret store, caching secrets clients for efficiency.
 pub struct Manager {
-    config_store: Store,
+    config_store: KvStore,
     /// The topic to use for configuring clients to fetch secrets from the secret store.
     secret_store_topic: Option<String>,
     nats_client: Client,
@@ -31,12 +33,12 @@ impl Manager {
     /// fetched by sending requests to the configured topic. If the provided secret_store_topic is None, this manager
     /// will always return an error if [`Self::fetch_secrets`] is called with a list of secrets.
     pub fn new(
-        config_store: &Store,
+        config_store: impl Into<KvStore>,
         secret_store_topic: Option<&String>,
         nats_client: &Client,
     ) -> Self {
         Self {
-            config_store: config_store.clone(),
+            config_store: config_store.into(),
             secret_store_topic: secret_store_topic.cloned(),
             nats_client: nats_client.clone(),
             backend_clients: Arc::new(RwLock::new(HashMap::new())),
@@ -0,0 +1,490 @@
+//! Verification of cosign-compatible signatures of OCI artifacts
+
+use core::fmt;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr as _;
+
+use anyhow::{bail, ensure, Context as _};
+use base64::Engine as _;
+use oci_client::Reference;
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, ED25519};
+use serde::Deserialize;
+use tokio::fs;
+use tracing::{debug, instrument, warn};
+use wasmcloud_core::{OciFetcher, OciSignature, RegistryConfig};
+
+use crate::oci::SignaturePolicy;
+use crate::{oci_fetcher, OciConfig, ResourceRef};
+
+/// DER-encoded `SubjectPublicKeyInfo` prefix of an uncompressed ECDSA P-256 public key
+const SPKI_ECDSA_P256_PREFIX: &[u8] = &[
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+
+/// DER-encoded `SubjectPublicKeyInfo` prefix of an Ed25519 public key
+const SPKI_ED25519_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Error returned when an OCI artifact fails signature verification under an enforcing policy
+#[derive(Debug)]
+pub struct SignatureVerificationError {
+    reference: String,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for SignatureVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "signature verification of `{}` failed: {:#}",
+            self.reference, self.error
+        )
+    }
+}
+
+impl std::error::Error for SignatureVerificationError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PublicKey {
+    EcdsaP256(Vec<u8>),
+    Ed25519(Vec<u8>),
+}
+
+impl PublicKey {
+    fn from_spki(spki: &[u8]) -> anyhow::Result<Self> {
+        if let Some(key) = spki.strip_prefix(SPKI_ECDSA_P256_PREFIX) {
+            Ok(Self::EcdsaP256(key.to_vec()))
+        } else if let Some(key) = spki.strip_prefix(SPKI_ED25519_PREFIX) {
+            Ok(Self::Ed25519(key.to_vec()))
+        } else {
+            bail!("unsupported public key, only ECDSA P-256 and Ed25519 keys are supported")
+        }
+    }
+
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        match self {
+            Self::EcdsaP256(key) => UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, key),
+            Self::Ed25519(key) => UnparsedPublicKey::new(&ED25519, key),
+        }
+        .verify(msg, sig)
+        .is_ok()
+    }
+}
+
+/// Public keys trusted to sign OCI artifacts
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TrustRoots(Vec<PublicKey>);
+
+impl TrustRoots {
+    /// Parses all PEM-encoded public keys in `pem`
+    pub(crate) fn from_pem(mut pem: &[u8]) -> anyhow::Result<Self> {
+        let keys = rustls_pemfile::public_keys(&mut pem)
+            .map(|spki| {
+                let spki = spki.context("failed to parse PEM")?;
+                PublicKey::from_spki(&spki)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        ensure!(!keys.is_empty(), "no public keys found");
+        Ok(Self(keys))
+    }
+
+    /// Loads the PEM-encoded public keys in the files at `paths`
+    pub(crate) async fn load(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut keys = Vec::with_capacity(paths.len());
+        for path in paths {
+            let pem = fs::read(path)
+                .await
+                .with_context(|| format!("failed to read `{}`", path.display()))?;
+            let Self(roots) = Self::from_pem(&pem)
+                .with_context(|| format!("failed to load trust roots from `{}`", path.display()))?;
+            keys.extend(roots);
+        }
+        Ok(Self(keys))
+    }
+
+    /// Verifies that `signature` was made by one of the trusted keys and refers to `digest`
+    fn verify(&self, signature: &OciSignature, digest: &str) -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Payload {
+            critical: Critical,
+        }
+        #[derive(Deserialize)]
+        struct Critical {
+            image: Image,
+        }
+        #[derive(Deserialize)]
+        struct Image {
+            #[serde(rename = "docker-manifest-digest")]
+            docker_manifest_digest: String,
+        }
+
+        let sig = base64::engine::general_purpose::STANDARD
+            .decode(&signature.signature)
+            .context("failed to decode signature")?;
+        ensure!(
+            self.0
+                .iter()
+                .any(|key| key.verify(&signature.payload, &sig)),
+            "signature was not made by a trusted key"
+        );
+        let Payload {
+            critical:
+                Critical {
+                    image:
+                        Image {
+                            docker_manifest_digest,
+                        },
+                },
+        } = serde_json::from_slice(&signature.payload)
+            .context("failed to decode signature payload")?;
+        ensure!(
+            docker_manifest_digest == digest,
+            "signature refers to digest `{docker_manifest_digest}`"
+        );
+        Ok(())
+    }
+}
+
+/// Verifies the signature of the OCI artifact under `oci_ref` and returns the reference pinned to
+/// the verified digest
+async fn verify(
+    fetcher: &OciFetcher,
+    trust_roots: &TrustRoots,
+    oci_ref: &str,
+) -> anyhow::Result<String> {
+    ensure!(
+        !trust_roots.0.is_empty(),
+        "no signature trust roots configured"
+    );
+    let digest = fetcher.fetch_digest(oci_ref).await?;
+    let signatures = fetcher
+        .fetch_signatures(oci_ref, &digest)
+        .await
+        .context("failed to fetch signatures")?;
+    ensure!(
+        !signatures.is_empty(),
+        "no signatures found for digest `{digest}`"
+    );
+    let mut res = Ok(());
+    for signature in &signatures {
+        res = trust_roots.verify(signature, &digest);
+        if res.is_ok() {
+            break;
+        }
+    }
+    res.with_context(|| format!("no valid signature found for digest `{digest}`"))?;
+    let img = Reference::from_str(&oci_ref.to_lowercase())?;
+    Ok(format!("{}/{}@{digest}", img.registry(), img.repository()))
+}
+
+/// Verifies the signature of the artifact under `reference` according to the signature policy of
+/// its registry and returns the reference to fetch the artifact from.
+///
+/// If the signature is verified, the returned reference is pinned to the verified digest, such
+/// that a tag moved after verification cannot be used to substitute the artifact.
+#[instrument(level = "debug", skip(oci_opts, trust_roots, registry_config))]
+pub(crate) async fn verify_signature(
+    reference: &str,
+    oci_opts: &OciConfig,
+    trust_roots: &TrustRoots,
+    registry_config: &HashMap<String, RegistryConfig>,
+) -> anyhow::Result<String> {
+    let ref oci_ref @ ResourceRef::Oci(img) = ResourceRef::try_from(reference)? else {
+        return Ok(reference.into());
+    };
+    let authority = oci_ref.authority();
+    let policy = oci_opts.signature_policy(authority.unwrap_or_default());
+    if policy == SignaturePolicy::Off {
+        return Ok(reference.into());
+    }
+    let fetcher = oci_fetcher(authority, oci_opts, registry_config);
+    match verify(&fetcher, trust_roots, img).await {
+        Ok(pinned) => {
+            debug!(pinned, "verified OCI artifact signature");
+            Ok(pinned)
+        }
+        Err(error) if policy == SignaturePolicy::Enforce => Err(SignatureVerificationError {
+            reference: reference.into(),
+            error,
+        }
+        .into()),
+        Err(error) => {
+            warn!(
+                ?error,
+                reference, "OCI artifact signature verification failed, continuing"
+            );
+            Ok(reference.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use axum::extract::State;
+    use axum::http::{header, StatusCode, Uri};
+    use axum::response::IntoResponse;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair as _, ECDSA_P256_SHA256_ASN1_SIGNING};
+    use serde_json::json;
+    use sha2::{Digest as _, Sha256};
+    use tokio::net::TcpListener;
+    use wasmcloud_core::RegistryType;
+
+    use super::*;
+
+    const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+    const INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+    const SIGNATURE_MEDIA_TYPE: &str = "application/vnd.dev.cosign.simplesigning.v1+json";
+
+    /// Stand-in for an OCI registry serving a fixed set of manifests and blobs, keyed by path
+    #[derive(Default)]
+    struct Registry(HashMap<String, (&'static str, Vec<u8>)>);
+
+    impl Registry {
+        fn blob(&mut self, repo: &str, media_type: &str, data: &[u8]) -> serde_json::Value {
+            let digest = sha256(data);
+            self.0.insert(
+                format!("/v2/{repo}/blobs/{digest}"),
+                ("application/octet-stream", data.to_vec()),
+            );
+            json!({ "mediaType": media_type, "digest": digest, "size": data.len() })
+        }
+
+        fn manifest(
+            &mut self,
+            repo: &str,
+            tag: Option<&str>,
+            layers: Vec<serde_json::Value>,
+        ) -> (String, usize) {
+            let config = self.blob(repo, "application/vnd.oci.image.config.v1+json", b"{}");
+            let manifest = serde_json::to_vec(&json!({
+                "schemaVersion": 2,
+                "mediaType": MANIFEST_MEDIA_TYPE,
+                "config": config,
+                "layers": layers,
+            }))
+            .expect("failed to encode manifest");
+            let digest = sha256(&manifest);
+            let size = manifest.len();
+            if let Some(tag) = tag {
+                self.0.insert(
+                    format!("/v2/{repo}/manifests/{tag}"),
+                    (MANIFEST_MEDIA_TYPE, manifest.clone()),
+                );
+            }
+            self.0.insert(
+                format!("/v2/{repo}/manifests/{digest}"),
+                (MANIFEST_MEDIA_TYPE, manifest),
+            );
+            (digest, size)
+        }
+
+        fn artifact(&mut self, repo: &str) -> String {
+            let layer = self.blob(repo, "application/wasm", repo.as_bytes());
+            self.manifest(repo, Some("0.1.0"), vec![layer]).0
+        }
+
+        fn signature(&mut self, repo: &str, key: &EcdsaKeyPair, digest: &str) -> serde_json::Value {
+            let payload = serde_json::to_vec(&json!({
+                "critical": {
+                    "identity": { "docker-reference": repo },
+                    "image": { "docker-manifest-digest": digest },
+                    "type": "cosign container image signature",
+                },
+                "optional": null,
+            }))
+            .expect("failed to encode payload");
+            let sig = key
+                .sign(&SystemRandom::new(), &payload)
+                .expect("failed to sign payload");
+            let mut layer = self.blob(repo, SIGNATURE_MEDIA_TYPE, &payload);
+            layer["annotations"] = json!({
+                "dev.cosignproject.cosign/signature":
+                    base64::engine::general_purpose::STANDARD.encode(sig),
+            });
+            layer
+        }
+
+        fn sign_tag(&mut self, repo: &str, key: &EcdsaKeyPair, digest: &str) {
+            let layer = self.signature(repo, key, digest);
+            let tag = format!("{}.sig", digest.replacen(':', "-", 1));
+            self.manifest(repo, Some(&tag), vec![layer]);
+        }
+
+        fn sign_referrer(&mut self, repo: &str, key: &EcdsaKeyPair, digest: &str) {
+            let layer = self.signature(repo, key, digest);
+            let (sig, size) = self.manifest(repo, None, vec![layer]);
+            let index = serde_json::to_vec(&json!({
+                "schemaVersion": 2,
+                "mediaType": INDEX_MEDIA_TYPE,
+                "manifests": [{ "mediaType": MANIFEST_MEDIA_TYPE, "digest": sig, "size": size }],
+            }))
+            .expect("failed to encode index");
+            self.0.insert(
+                format!("/v2/{repo}/referrers/{digest}"),
+                (INDEX_MEDIA_TYPE, index),
+            );
+        }
+    }
+
+    async fn serve(State(registry): State<Arc<Registry>>, uri: Uri) -> impl IntoResponse {
+        if uri.path() == "/v2/" {
+            return (StatusCode::OK, Vec::default()).into_response();
+        }
+        let Some((media_type, body)) = registry.0.get(uri.path()) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        (
+            [
+                (header::CONTENT_TYPE, (*media_type).to_string()),
+                (
+                    header::HeaderName::from_static("docker-content-digest"),
+                    sha256(body),
+                ),
+            ],
+            body.clone(),
+        )
+            .into_response()
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        format!("sha256:{}", hex::encode(Sha256::digest(data)))
+    }
+
+    fn key_pair() -> anyhow::Result<EcdsaKeyPair> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng)
+            .map_err(|e| anyhow!("failed to generate key: {e}"))?;
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+            .map_err(|e| anyhow!("failed to parse key: {e}"))
+    }
+
+    fn public_key_pem(key: &EcdsaKeyPair) -> String {
+        let spki = [SPKI_ECDSA_P256_PREFIX, key.public_key().as_ref()].concat();
+        format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64::engine::general_purpose::STANDARD.encode(spki)
+        )
+    }
+
+    #[test]
+    fn trust_roots() -> anyhow::Result<()> {
+        let (a, b) = (key_pair()?, key_pair()?);
+        let pem = public_key_pem(&a) + &public_key_pem(&b);
+        let roots = TrustRoots::from_pem(pem.as_bytes())?;
+        assert_eq!(
+            roots,
+            TrustRoots(vec![
+                PublicKey::EcdsaP256(a.public_key().as_ref().to_vec()),
+                PublicKey::EcdsaP256(b.public_key().as_ref().to_vec()),
+            ])
+        );
+        assert!(TrustRoots::from_pem(b"").is_err());
+        assert!(TrustRoots::from_pem(
+            b"-----BEGIN PUBLIC KEY-----\nMAA=\n-----END PUBLIC KEY-----\n"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn verify_registry_signatures() -> anyhow::Result<()> {
+        let (trusted, untrusted) = (key_pair()?, key_pair()?);
+
+        let mut registry = Registry::default();
+        let signed = registry.artifact("signed");
+        registry.sign_tag("signed", &trusted, &signed);
+        let referred = registry.artifact("referred");
+        registry.sign_referrer("referred", &trusted, &referred);
+        let forged = registry.artifact("forged");
+        registry.sign_tag("forged", &untrusted, &forged);
+        // signature of another artifact
+        registry.artifact("moved");
+        registry.sign_tag("moved", &trusted, &signed);
+        registry.artifact("unsigned");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        let router = axum::Router::new()
+            .fallback(serve)
+            .with_state(Arc::new(registry));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let trust_roots = TrustRoots::from_pem(public_key_pem(&trusted).as_bytes())?;
+        let registry_config = HashMap::from([(
+            addr.clone(),
+            RegistryConfig::builder()
+                .reg_type(RegistryType::Oci)
+                .allow_insecure(true)
+                .build()?,
+        )]);
+        let cache_dir =
+            std::env::temp_dir().join(format!("wasmcloud-signature-{}", ulid::Ulid::new()));
+        let mut oci_opts = OciConfig {
+            registry_signature_policies: HashMap::from([(addr.clone(), SignaturePolicy::Enforce)]),
+            cache_dir: Some(cache_dir.clone()),
+            ..Default::default()
+        };
+
+        for (repo, digest) in [("signed", &signed), ("referred", &referred)] {
+            let pinned = verify_signature(
+                &format!("{addr}/{repo}:0.1.0"),
+                &oci_opts,
+                &trust_roots,
+                &registry_config,
+            )
+            .await?;
+            assert_eq!(pinned, format!("{addr}/{repo}@{digest}"));
+        }
+        for repo in ["forged", "moved", "unsigned"] {
+            let Err(err) = verify_signature(
+                &format!("{addr}/{repo}:0.1.0"),
+                &oci_opts,
+                &trust_roots,
+                &registry_config,
+            )
+            .await
+            else {
+                panic!("verification of `{repo}` should fail");
+            };
+            assert!(err.downcast_ref::<SignatureVerificationError>().is_some());
+        }
+
+        // Digests and signatures are verified from the cache while offline
+        oci_opts.offline = true;
+        let pinned = verify_signature(
+            &format!("{addr}/signed:0.1.0"),
+            &oci_opts,
+            &trust_roots,
+            &registry_config,
+        )
+        .await?;
+        assert_eq!(pinned, format!("{addr}/signed@{signed}"));
+        oci_opts.offline = false;
+
+        oci_opts
+            .registry_signature_policies
+            .insert(addr.clone(), SignaturePolicy::Warn);
+        let reference = format!("{addr}/unsigned:0.1.0");
+        assert_eq!(
+            verify_signature(&reference, &oci_opts, &trust_roots, &registry_config).await?,
+            reference
+        );
+
+        let reference = "ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0";
+        assert_eq!(oci_opts.signature_policy("ghcr.io"), SignaturePolicy::Off);
+        assert_eq!(
+            verify_signature(reference, &oci_opts, &trust_roots, &registry_config).await?,
+            reference
+        );
+        fs::remove_dir_all(&cache_dir).await?;
+        Ok(())
+    }
+}
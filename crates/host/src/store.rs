@@ -0,0 +1,313 @@
+//! Key-value storage of lattice data and named config
+
+use core::fmt;
+use core::pin::Pin;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use async_nats::jetstream::kv::{Entry, Operation, Store, UpdateErrorKind};
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use time::OffsetDateTime;
+use tokio::sync::{broadcast, Mutex};
+
+/// Capacity of the channel notifying watchers of an in-memory store of changes
+const MEMORY_WATCH_CAPACITY: usize = 1024;
+
+/// Stream of the changes to entries of a [`KvStore`]
+pub type Watch = Pin<Box<dyn Stream<Item = anyhow::Result<Entry>> + Send>>;
+
+/// Key-value store holding lattice data or named config. This is a NATS JetStream bucket, unless
+/// the host runs without a control plane, in which case the data is kept in memory
+#[derive(Clone, Debug)]
+pub enum KvStore {
+    /// NATS JetStream key-value bucket
+    JetStream(Box<Store>),
+    /// In-memory store, which does not outlive the host
+    Memory(Arc<MemoryStore>),
+}
+
+impl From<Store> for KvStore {
+    fn from(store: Store) -> Self {
+        Self::JetStream(Box::new(store))
+    }
+}
+
+impl From<&Store> for KvStore {
+    fn from(store: &Store) -> Self {
+        Self::JetStream(Box::new(store.clone()))
+    }
+}
+
+impl KvStore {
+    /// Returns a new, empty in-memory store named `bucket`
+    #[must_use]
+    pub fn memory(bucket: impl Into<String>) -> Self {
+        Self::Memory(Arc::new(MemoryStore::new(bucket)))
+    }
+
+    /// Returns the value of `key`, if it exists
+    pub async fn get(&self, key: &str) -> anyhow::Result<Option<Bytes>> {
+        match self {
+            Self::JetStream(store) => store.get(key).await.map_err(Into::into),
+            Self::Memory(store) => Ok(store
+                .entry(key)
+                .await
+                .filter(|entry| entry.operation == Operation::Put)
+                .map(|entry| entry.value)),
+        }
+    }
+
+    /// Returns the last entry of `key`, which may be a deletion, if it has one
+    pub async fn entry(&self, key: &str) -> anyhow::Result<Option<Entry>> {
+        match self {
+            Self::JetStream(store) => store.entry(key).await.map_err(Into::into),
+            Self::Memory(store) => Ok(store.entry(key).await),
+        }
+    }
+
+    /// Sets the value of `key` and returns the revision of the entry
+    pub async fn put(&self, key: &str, value: Bytes) -> anyhow::Result<u64> {
+        match self {
+            Self::JetStream(store) => store.put(key, value).await.map_err(Into::into),
+            Self::Memory(store) => store
+                .write(key, value, Operation::Put, None)
+                .await
+                .context("failed to write entry"),
+        }
+    }
+
+    /// Sets the value of `key` if its last entry has revision `last_revision`, where `0` denotes
+    /// that `key` has no entry, and returns the revision of the entry.
+    /// Returns [`None`] if the last revision of `key` does not match
+    pub async fn update(
+        &self,
+        key: &str,
+        value: Bytes,
+        last_revision: u64,
+    ) -> anyhow::Result<Option<u64>> {
+        match self {
+            Self::JetStream(store) => match store.update(key, value, last_revision).await {
+                Ok(revision) => Ok(Some(revision)),
+                Err(err) if err.kind() == UpdateErrorKind::WrongLastRevision => Ok(None),
+                Err(err) => Err(err.into()),
+            },
+            Self::Memory(store) => Ok(store
+                .write(key, value, Operation::Put, Some(last_revision))
+                .await),
+        }
+    }
+
+    /// Removes `key` and its history
+    pub async fn purge(&self, key: &str) -> anyhow::Result<()> {
+        match self {
+            Self::JetStream(store) => store.purge(key).await.map_err(Into::into),
+            Self::Memory(store) => {
+                store.write(key, Bytes::new(), Operation::Purge, None).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns all keys, which have a value
+    pub async fn keys(&self) -> anyhow::Result<Vec<String>> {
+        match self {
+            Self::JetStream(store) => {
+                let keys = store.keys().await.context("failed to list keys")?;
+                keys.map_err(|err| anyhow!(err).context("failed to read key"))
+                    .try_collect()
+                    .await
+            }
+            Self::Memory(store) => Ok(store.keys().await),
+        }
+    }
+
+    /// Watches changes to `key` made from now on
+    pub async fn watch(&self, key: &str) -> anyhow::Result<Watch> {
+        match self {
+            Self::JetStream(store) => {
+                let watch = store.watch(key).await?;
+                Ok(Box::pin(watch.map_err(Into::into)))
+            }
+            Self::Memory(store) => {
+                let key = key.to_string();
+                Ok(Box::pin(store.watch().filter(move |entry| {
+                    let matches = entry.as_ref().map_or(true, |entry| entry.key == key);
+                    async move { matches }
+                })))
+            }
+        }
+    }
+
+    /// Watches changes to all keys made from now on
+    pub async fn watch_all(&self) -> anyhow::Result<Watch> {
+        match self {
+            Self::JetStream(store) => {
+                let watch = store.watch_all().await?;
+                Ok(Box::pin(watch.map_err(Into::into)))
+            }
+            Self::Memory(store) => Ok(Box::pin(store.watch())),
+        }
+    }
+
+    /// Checks that the store is available
+    pub async fn status(&self) -> anyhow::Result<()> {
+        match self {
+            Self::JetStream(store) => {
+                store.status().await?;
+                Ok(())
+            }
+            Self::Memory(..) => Ok(()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct MemoryEntries {
+    entries: HashMap<String, Entry>,
+    revision: u64,
+}
+
+/// Key-value store kept in memory, which mirrors the semantics of a NATS JetStream bucket without
+/// retaining history
+pub struct MemoryStore {
+    bucket: String,
+    entries: Mutex<MemoryEntries>,
+    changes: broadcast::Sender<Entry>,
+}
+
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("bucket", &self.bucket)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MemoryStore {
+    fn new(bucket: impl Into<String>) -> Self {
+        let (changes, _) = broadcast::channel(MEMORY_WATCH_CAPACITY);
+        Self {
+            bucket: bucket.into(),
+            entries: Mutex::default(),
+            changes,
+        }
+    }
+
+    async fn entry(&self, key: &str) -> Option<Entry> {
+        self.entries.lock().await.entries.get(key).cloned()
+    }
+
+    async fn keys(&self) -> Vec<String> {
+        self.entries
+            .lock()
+            .await
+            .entries
+            .values()
+            .filter(|entry| entry.operation == Operation::Put)
+            .map(|entry| entry.key.clone())
+            .collect()
+    }
+
+    /// Writes an entry of `key` and notifies watchers. If `last_revision` is set, the entry is
+    /// only written if the last entry of `key` has that revision
+    async fn write(
+        &self,
+        key: &str,
+        value: Bytes,
+        operation: Operation,
+        last_revision: Option<u64>,
+    ) -> Option<u64> {
+        let mut entries = self.entries.lock().await;
+        if let Some(last_revision) = last_revision {
+            let current = entries.entries.get(key).map_or(0, |entry| entry.revision);
+            if current != last_revision {
+                return None;
+            }
+        }
+        entries.revision = entries.revision.saturating_add(1);
+        let entry = Entry {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            value,
+            revision: entries.revision,
+            delta: 0,
+            created: OffsetDateTime::now_utc(),
+            operation,
+            seen_current: true,
+        };
+        entries.entries.insert(key.to_string(), entry.clone());
+        // Watchers are notified while holding the lock, such that they observe changes in order
+        let _ = self.changes.send(entry);
+        Some(entries.revision)
+    }
+
+    fn watch(&self) -> impl Stream<Item = anyhow::Result<Entry>> + Send + 'static {
+        stream::unfold(self.changes.subscribe(), |mut changes| async move {
+            match changes.recv().await {
+                Ok(entry) => Some((Ok(entry), changes)),
+                Err(broadcast::error::RecvError::Lagged(n)) => Some((
+                    Err(anyhow!("watcher lagged behind by {n} changes")),
+                    changes,
+                )),
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory() -> anyhow::Result<()> {
+        let store = KvStore::memory("test");
+        let mut watch = store.watch("foo").await?;
+        let mut watch_all = store.watch_all().await?;
+
+        assert_eq!(store.get("foo").await?, None);
+        let revision = store.put("foo", Bytes::from("bar")).await?;
+        store.put("baz", Bytes::from("qux")).await?;
+        assert_eq!(store.get("foo").await?, Some(Bytes::from("bar")));
+        let mut keys = store.keys().await?;
+        keys.sort();
+        assert_eq!(keys, ["baz", "foo"]);
+
+        assert_eq!(store.update("foo", Bytes::from("x"), 0).await?, None);
+        let revision = store.update("foo", Bytes::from("x"), revision).await?;
+        assert!(revision.is_some());
+        assert!(store.update("new", Bytes::from("y"), 0).await?.is_some());
+
+        store.purge("foo").await?;
+        assert_eq!(store.get("foo").await?, None);
+        let entry = store.entry("foo").await?.expect("purge not recorded");
+        assert_eq!(entry.operation, Operation::Purge);
+        assert!(!store.keys().await?.contains(&"foo".to_string()));
+
+        let ops: Vec<_> = watch
+            .by_ref()
+            .take(3)
+            .map(|entry| entry.map(|entry| (entry.key, entry.operation)))
+            .try_collect()
+            .await?;
+        assert_eq!(
+            ops,
+            [
+                ("foo".to_string(), Operation::Put),
+                ("foo".to_string(), Operation::Put),
+                ("foo".to_string(), Operation::Purge),
+            ]
+        );
+        let keys: Vec<_> = watch_all
+            .by_ref()
+            .take(5)
+            .map_ok(|entry| entry.key)
+            .try_collect()
+            .await?;
+        assert_eq!(keys, ["foo", "baz", "foo", "new", "foo"]);
+        Ok(())
+    }
+}
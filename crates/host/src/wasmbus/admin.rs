@@ -0,0 +1,249 @@
+//! HTTP admin endpoint of the host, exposing liveness, readiness, inventory and metrics
+
+use core::future::Future;
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use async_nats::connection::State;
+use axum::extract;
+use axum::http::{header, StatusCode};
+use axum::routing::get;
+use axum::{Json, Router};
+use prometheus::{Encoder as _, TextEncoder};
+use tokio::net::TcpListener;
+use tracing::{debug, error, instrument};
+use wasmcloud_control_interface::HostInventory;
+
+use super::Host;
+
+/// State served by the admin endpoint, which is implemented by [`Host`]
+pub(crate) trait AdminState: Send + Sync + 'static {
+    /// Returns the reasons for the host not being ready to accept workloads, which is empty if
+    /// the host is ready
+    fn readiness_failures(&self) -> impl Future<Output = Vec<String>> + Send;
+
+    /// Returns the inventory of the host
+    fn inventory(&self) -> impl Future<Output = HostInventory> + Send;
+
+    /// Returns the registry of the metrics exposed on `/metrics`, if enabled
+    fn prometheus_registry(&self) -> Option<&prometheus::Registry>;
+}
+
+/// Serves the admin endpoint of `host` on `listener`
+pub(crate) async fn serve<T: AdminState>(
+    host: Arc<T>,
+    listener: TcpListener,
+) -> anyhow::Result<()> {
+    let mut router = Router::new()
+        .route("/livez", get(livez))
+        .route("/readyz", get(readyz::<T>))
+        .route("/inventory", get(inventory::<T>));
+    if host.prometheus_registry().is_some() {
+        router = router.route("/metrics", get(metrics::<T>));
+    }
+    let router = router.with_state(host);
+    axum::serve(listener, router)
+        .await
+        .context("failed to serve HTTP admin endpoint")
+}
+
+async fn livez() -> &'static str {
+    "ok"
+}
+
+#[instrument(level = "trace", skip_all)]
+async fn readyz<T: AdminState>(
+    extract::State(host): extract::State<Arc<T>>,
+) -> (StatusCode, String) {
+    let failures = host.readiness_failures().await;
+    if failures.is_empty() {
+        (StatusCode::OK, "ok".into())
+    } else {
+        debug!(?failures, "host is not ready");
+        (StatusCode::SERVICE_UNAVAILABLE, failures.join("\n"))
+    }
+}
+
+#[instrument(level = "trace", skip_all)]
+async fn inventory<T: AdminState>(
+    extract::State(host): extract::State<Arc<T>>,
+) -> Json<HostInventory> {
+    Json(host.inventory().await)
+}
+
+#[instrument(level = "trace", skip_all)]
+async fn metrics<T: AdminState>(
+    extract::State(host): extract::State<Arc<T>>,
+) -> (StatusCode, [(header::HeaderName, String); 1], Vec<u8>) {
+    let encoder = TextEncoder::new();
+    let content_type = [(header::CONTENT_TYPE, encoder.format_type().to_string())];
+    let Some(registry) = host.prometheus_registry() else {
+        return (StatusCode::NOT_FOUND, content_type, Vec::default());
+    };
+    let mut buf = Vec::new();
+    if let Err(err) = encoder.encode(&registry.gather(), &mut buf) {
+        error!(?err, "failed to encode Prometheus metrics");
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            content_type,
+            Vec::default(),
+        );
+    }
+    (StatusCode::OK, content_type, buf)
+}
+
+impl AdminState for Host {
+    async fn readiness_failures(&self) -> Vec<String> {
+        let mut failures = Vec::new();
+        if !self.synced.load(Ordering::Relaxed) {
+            failures.push("lattice data bucket is not synchronized yet".into());
+        }
+        if self.is_draining() {
+            failures.push("host is draining".into());
+        }
+        if self.stop_rx.borrow().is_some() {
+            failures.push("host is stopping".into());
+        }
+        if self.ctl_nats.connection_state() != State::Connected {
+            failures.push("control interface NATS client is not connected".into());
+        }
+        if self.rpc_nats.connection_state() != State::Connected {
+            failures.push("RPC NATS client is not connected".into());
+        }
+        if let Err(err) = self.data.status().await {
+            failures.push(format!("lattice data bucket is not reachable: {err}"));
+        }
+        if let Err(err) = self.config_data.status().await {
+            failures.push(format!("config data bucket is not reachable: {err}"));
+        }
+        failures
+    }
+
+    async fn inventory(&self) -> HostInventory {
+        Host::inventory(self).await
+    }
+
+    fn prometheus_registry(&self) -> Option<&prometheus::Registry> {
+        self.host_config.prometheus_registry.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::SocketAddr;
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio::net::TcpStream;
+
+    struct TestState {
+        failures: Vec<String>,
+        inventory: HostInventory,
+    }
+
+    impl AdminState for TestState {
+        async fn readiness_failures(&self) -> Vec<String> {
+            self.failures.clone()
+        }
+
+        async fn inventory(&self) -> HostInventory {
+            self.inventory.clone()
+        }
+
+        fn prometheus_registry(&self) -> Option<&prometheus::Registry> {
+            None
+        }
+    }
+
+    /// Serves the admin endpoint of `state` and returns its address
+    async fn start(state: TestState) -> SocketAddr {
+        let listener = TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("failed to bind listener");
+        let addr = listener
+            .local_addr()
+            .expect("failed to get listener address");
+        tokio::spawn(serve(Arc::new(state), listener));
+        addr
+    }
+
+    /// Requests `path` from the admin endpoint at `addr` and returns the status and body
+    async fn get(addr: SocketAddr, path: &str) -> (StatusCode, String) {
+        let mut stream = TcpStream::connect(addr)
+            .await
+            .expect("failed to connect to admin endpoint");
+        stream
+            .write_all(
+                format!("GET {path} HTTP/1.1\r\nhost: {addr}\r\nconnection: close\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await
+            .expect("failed to send request");
+        let mut res = String::new();
+        stream
+            .read_to_string(&mut res)
+            .await
+            .expect("failed to read response");
+        let (head, body) = res.split_once("\r\n\r\n").expect("invalid response");
+        let status = head
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .expect("invalid status line");
+        (status, body.into())
+    }
+
+    fn inventory() -> HostInventory {
+        HostInventory::builder()
+            .host_id("host".into())
+            .friendly_name("friendly".into())
+            .version("1.0.0".into())
+            .uptime_human("1s".into())
+            .uptime_seconds(1)
+            .build()
+            .expect("failed to build inventory")
+    }
+
+    #[tokio::test]
+    async fn ready() {
+        let addr = start(TestState {
+            failures: Vec::default(),
+            inventory: inventory(),
+        })
+        .await;
+        assert_eq!(get(addr, "/livez").await, (StatusCode::OK, "ok".into()));
+        assert_eq!(get(addr, "/readyz").await, (StatusCode::OK, "ok".into()));
+
+        let (status, body) = get(addr, "/inventory").await;
+        assert_eq!(status, StatusCode::OK);
+        let body: HostInventory = serde_json::from_str(&body).expect("invalid inventory");
+        assert_eq!(body, inventory());
+
+        assert_eq!(get(addr, "/metrics").await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn not_ready() {
+        let addr = start(TestState {
+            failures: vec![
+                "host is stopping".into(),
+                "RPC NATS client is not connected".into(),
+            ],
+            inventory: inventory(),
+        })
+        .await;
+        // A host, which is not ready, is still alive
+        assert_eq!(get(addr, "/livez").await, (StatusCode::OK, "ok".into()));
+        assert_eq!(
+            get(addr, "/readyz").await,
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "host is stopping\nRPC NATS client is not connected".into()
+            )
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Garbage collection of cached OCI artifacts
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use tracing::{debug, info, instrument, warn};
+use wasmcloud_core::{oci_cache_dir, OciCache};
+
+use super::Host;
+use crate::oci_reference;
+
+/// Interval, at which cached OCI artifacts are garbage collected
+pub(crate) const OCI_CACHE_GC_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Period after its last use, during which a cached OCI artifact is never evicted. This keeps
+/// artifacts of workloads, which are still starting, in the cache.
+const OCI_CACHE_GC_GRACE: Duration = Duration::from_secs(600);
+
+impl Host {
+    /// Returns the cache of OCI artifacts of this host
+    async fn oci_cache(&self) -> anyhow::Result<OciCache> {
+        match &self.host_config.oci_opts.cache_dir {
+            Some(dir) => Ok(OciCache::new(dir)),
+            None => oci_cache_dir().await.map(OciCache::new),
+        }
+    }
+
+    /// Evicts cached OCI artifacts, which are not referenced by components or providers running
+    /// on this host, until the cache does not exceed `max_size` bytes
+    #[instrument(level = "debug", skip(self))]
+    pub(crate) async fn collect_oci_cache(&self, max_size: u64) -> anyhow::Result<()> {
+        let cache = self.oci_cache().await?;
+        let mut references: Vec<String> = self
+            .components
+            .read()
+            .await
+            .values()
+            .map(|component| component.image_reference.to_string())
+            .collect();
+        references.extend(
+            self.providers
+                .read()
+                .await
+                .values()
+                .map(|provider| provider.image_ref.clone()),
+        );
+        let mut referenced = HashSet::with_capacity(references.len());
+        for reference in &references {
+            let Some(oci_ref) = oci_reference(reference) else {
+                continue;
+            };
+            match cache.resolve(oci_ref).await {
+                Ok(Some(digest)) => {
+                    referenced.insert(digest);
+                }
+                Ok(None) => debug!(reference, "running workload not found in OCI cache"),
+                Err(err) => warn!(?err, reference, "failed to resolve cached OCI artifact"),
+            }
+        }
+        let evicted = cache
+            .gc(max_size, &referenced, OCI_CACHE_GC_GRACE)
+            .await
+            .context("failed to collect OCI cache garbage")?;
+        if !evicted.is_empty() {
+            info!(?evicted, "evicted OCI artifacts from cache");
+        }
+        Ok(())
+    }
+}
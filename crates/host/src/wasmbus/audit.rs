@@ -0,0 +1,204 @@
+//! Audit log of control interface requests
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Context as _};
+use async_nats::HeaderMap;
+use serde::Deserialize;
+use tokio::fs;
+use tokio::io::AsyncWriteExt as _;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, error, Instrument as _};
+use wasmcloud_core::{audit_stream_name, audit_subject, AuditRecord};
+
+use super::host_config::AuditLogSink;
+
+/// Header, in which the NATS server reports information about the client sending a request
+const REQUEST_INFO_HEADER: &str = "Nats-Request-Info";
+
+/// Maximum number of audit records waiting to be written, handling of control interface requests
+/// waits for the writer once exceeded
+const AUDIT_QUEUE_SIZE: usize = 1024;
+
+/// Writer of audit records to the configured [`AuditLogSink`]
+#[derive(Clone, Debug)]
+pub(crate) enum AuditLog {
+    File(Arc<Mutex<fs::File>>),
+    JetStream {
+        jetstream: async_nats::jetstream::Context,
+        lattice: Arc<str>,
+    },
+}
+
+/// Outcome reported by a serialized control interface response
+#[derive(Deserialize)]
+struct Outcome {
+    success: bool,
+    #[serde(default)]
+    message: String,
+}
+
+impl AuditLog {
+    /// Prepares `sink` for writing, opening the file or creating the JetStream stream if it does
+    /// not exist yet
+    pub(crate) async fn new(
+        sink: &AuditLogSink,
+        jetstream: &async_nats::jetstream::Context,
+        lattice: &Arc<str>,
+    ) -> anyhow::Result<Self> {
+        match sink {
+            AuditLogSink::File(path) => {
+                let file = open_append(path)
+                    .await
+                    .with_context(|| format!("failed to open `{}`", path.display()))?;
+                Ok(Self::File(Arc::new(Mutex::new(file))))
+            }
+            AuditLogSink::JetStream => {
+                let name = audit_stream_name(lattice);
+                jetstream
+                    .get_or_create_stream(async_nats::jetstream::stream::Config {
+                        name: name.clone(),
+                        subjects: vec![audit_subject(lattice, ">")],
+                        storage: async_nats::jetstream::stream::StorageType::File,
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .with_context(|| format!("failed to create stream `{name}`"))?;
+                Ok(Self::JetStream {
+                    jetstream: jetstream.clone(),
+                    lattice: Arc::clone(lattice),
+                })
+            }
+        }
+    }
+
+    /// Spawns a task writing the audit records sent on the returned channel in order, such that
+    /// replies to control interface requests do not wait for their records to be persisted.
+    /// The task exits once all senders are dropped.
+    pub(crate) fn spawn(self) -> mpsc::Sender<AuditRecord> {
+        let (tx, mut rx) = mpsc::channel::<AuditRecord>(AUDIT_QUEUE_SIZE);
+        tokio::spawn(
+            async move {
+                while let Some(record) = rx.recv().await {
+                    if let Err(err) = self.write(&record).await {
+                        error!(
+                            subject = record.subject,
+                            ?err,
+                            "failed to write audit record"
+                        );
+                    }
+                }
+                debug!("audit log writer done");
+            }
+            .in_current_span(),
+        );
+        tx
+    }
+
+    /// Writes `record`, returning once it is persisted
+    async fn write(&self, record: &AuditRecord) -> anyhow::Result<()> {
+        let mut payload = serde_json::to_vec(record).context("failed to serialize audit record")?;
+        match self {
+            Self::File(file) => {
+                payload.push(b'\n');
+                let mut file = file.lock().await;
+                file.write_all(&payload)
+                    .await
+                    .context("failed to write audit record")?;
+                file.sync_data().await.context("failed to sync audit log")
+            }
+            Self::JetStream { jetstream, lattice } => {
+                let operation = if record.operation.is_empty() {
+                    "unknown"
+                } else {
+                    &record.operation
+                };
+                jetstream
+                    .publish(audit_subject(lattice, operation), payload.into())
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .context("failed to publish audit record")?
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .context("audit record was not acknowledged")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn open_append(path: &Path) -> std::io::Result<fs::File> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).await?;
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
+
+/// Returns the current time in RFC 3339 format, as used in audit records
+pub(crate) fn audit_timestamp() -> String {
+    humantime::format_rfc3339_millis(SystemTime::now()).to_string()
+}
+
+/// Returns the NATS user, which sent a request with `headers`, as reported by the NATS server
+pub(crate) fn requesting_user(headers: Option<&HeaderMap>) -> Option<String> {
+    #[derive(Deserialize)]
+    struct RequestInfo {
+        #[serde(default)]
+        user: Option<String>,
+        #[serde(default)]
+        acc: Option<String>,
+    }
+
+    let info = headers?.get(REQUEST_INFO_HEADER)?;
+    let RequestInfo { user, acc } = serde_json::from_str(info.as_str()).ok()?;
+    user.filter(|user| !user.is_empty())
+        .or(acc.filter(|acc| !acc.is_empty()))
+}
+
+/// Returns whether the request succeeded and the message describing its outcome from a
+/// serialized control interface response. A response, which cannot be parsed, is reported as a
+/// failure
+pub(crate) fn response_outcome(response: &[u8]) -> (bool, String) {
+    match serde_json::from_slice::<Outcome>(response) {
+        Ok(Outcome { success, message }) => (success, message),
+        Err(_) => (false, "unparseable response".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_metadata() {
+        assert_eq!(requesting_user(None), None);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            REQUEST_INFO_HEADER,
+            r#"{"acc":"ACCOUNT","user":"UADMIN","rtt":"1ms"}"#,
+        );
+        assert_eq!(requesting_user(Some(&headers)).as_deref(), Some("UADMIN"));
+        headers.insert(REQUEST_INFO_HEADER, r#"{"acc":"ACCOUNT"}"#);
+        assert_eq!(requesting_user(Some(&headers)).as_deref(), Some("ACCOUNT"));
+
+        assert_eq!(
+            response_outcome(br#"{"success":false,"message":"policy denied"}"#),
+            (false, "policy denied".into())
+        );
+        assert_eq!(
+            response_outcome(br#"{"success":true}"#),
+            (true, String::default())
+        );
+        assert_eq!(
+            response_outcome(b"{}"),
+            (false, "unparseable response".into())
+        );
+    }
+}
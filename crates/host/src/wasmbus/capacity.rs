@@ -0,0 +1,82 @@
+//! Capacity of the host, which is reported in auction acknowledgements
+
+use tracing::warn;
+use wasmcloud_control_interface::HostCapacity;
+
+use super::Host;
+
+impl Host {
+    /// Returns the current capacity of the host
+    pub(crate) async fn capacity(&self) -> HostCapacity {
+        let (slots_used, memory_committed) = self.components.read().await.values().fold(
+            (0u64, 0u64),
+            |(slots, memory), component| {
+                let instances = u64::try_from(component.max_instances.get()).unwrap_or(u64::MAX);
+                let max_memory = component
+                    .max_memory
+                    .unwrap_or(self.host_config.max_linear_memory);
+                (
+                    slots.saturating_add(instances),
+                    memory.saturating_add(instances.saturating_mul(max_memory)),
+                )
+            },
+        );
+        let slots_used = u32::try_from(slots_used).unwrap_or(u32::MAX);
+        let providers = u32::try_from(self.providers.read().await.len()).unwrap_or(u32::MAX);
+        let mut capacity = HostCapacity::builder()
+            .component_slots_used(slots_used)
+            .component_slots_remaining(self.host_config.max_components.saturating_sub(slots_used))
+            .linear_memory_committed(memory_committed)
+            .providers(providers);
+        if let Some(cpu_load) = cpu_load().await {
+            capacity = capacity.cpu_load(cpu_load);
+        }
+        capacity.build().unwrap_or_else(|err| {
+            warn!(?err, "failed to build host capacity");
+            HostCapacity::default()
+        })
+    }
+
+    /// Returns whether the host should refuse to bid in auctions given its `capacity`
+    pub(crate) fn refuses_auction(&self, capacity: &HostCapacity) -> bool {
+        self.host_config
+            .auction_refusal_threshold
+            .is_some_and(|threshold| capacity.utilization() >= threshold)
+    }
+}
+
+/// Returns the load average of the host over the last minute relative to the number of CPUs, in
+/// percent
+async fn cpu_load() -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        let loadavg = tokio::fs::read_to_string("/proc/loadavg").await.ok()?;
+        let cpus = std::thread::available_parallelism().ok()?;
+        parse_cpu_load(&loadavg, cpus.get())
+    }
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+/// Parses the 1-minute load average from the contents of `/proc/loadavg` and returns it relative
+/// to `cpus`, in percent
+#[cfg_attr(not(target_os = "linux"), allow(unused))]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn parse_cpu_load(loadavg: &str, cpus: usize) -> Option<u32> {
+    let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+    let cpus = u32::try_from(cpus).ok().filter(|cpus| *cpus > 0)?;
+    Some((load * 100.0 / f64::from(cpus)).round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_average() {
+        assert_eq!(parse_cpu_load("3.00 2.50 1.75 2/1024 12345\n", 4), Some(75));
+        assert_eq!(parse_cpu_load("0.42 0.30 0.10 1/100 1\n", 1), Some(42));
+        assert_eq!(parse_cpu_load("", 4), None);
+        assert_eq!(parse_cpu_load("1.00", 0), None);
+    }
+}
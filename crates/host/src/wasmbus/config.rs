@@ -2,7 +2,7 @@
 use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 use anyhow::{bail, Context};
-use async_nats::jetstream::kv::{Operation, Store};
+use async_nats::jetstream::kv::Operation;
 use futures::{future::AbortHandle, stream::Abortable, TryStreamExt};
 use tokio::sync::{
     watch::{self, Receiver, Sender},
@@ -10,6 +10,8 @@ use tokio::sync::{
 };
 use tracing::{error, warn, Instrument};
 
+use crate::store::KvStore;
+
 type LockedConfig = Arc<RwLock<HashMap<String, String>>>;
 /// A cache of named config mapped to an existing receiver
 type WatchCache = Arc<RwLock<HashMap<String, Receiver<HashMap<String, String>>>>>;
@@ -192,7 +194,7 @@ impl ConfigBundle {
 /// A struct used for generating a config bundle given a list of named configs
 #[derive(Clone)]
 pub struct BundleGenerator {
-    store: Store,
+    store: KvStore,
     watch_cache: WatchCache,
     watch_handles: Arc<RwLock<AbortHandles>>,
 }
@@ -200,9 +202,9 @@ pub struct BundleGenerator {
 impl BundleGenerator {
     /// Create a new bundle generator
     #[must_use]
-    pub fn new(store: Store) -> Self {
+    pub fn new(store: impl Into<KvStore>) -> Self {
         Self {
-            store,
+            store: store.into(),
             watch_cache: Arc::default(),
             watch_handles: Arc::default(),
         }
@@ -268,7 +270,7 @@ impl BundleGenerator {
 }
 
 async fn watcher_loop(
-    store: Store,
+    store: KvStore,
     name: String,
     tx: watch::Sender<HashMap<String, String>>,
     done: tokio::sync::oneshot::Sender<anyhow::Result<()>>,
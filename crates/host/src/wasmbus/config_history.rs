@@ -0,0 +1,183 @@
+//! Revision history of named config
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context as _};
+use async_nats::jetstream::kv::Operation;
+use wasmcloud_control_interface::ConfigRevision;
+
+use crate::store::KvStore;
+
+/// Maximum number of revisions retained per named config
+const MAX_CONFIG_REVISIONS: usize = 64;
+
+/// Maximum number of attempts to record a revision while the history is concurrently updated
+const MAX_RECORD_ATTEMPTS: usize = 10;
+
+/// Revision history of named config, stored as a list of [`ConfigRevision`]s per config name.
+/// The history of a config is kept after the config is deleted, such that it can be restored
+#[derive(Clone, Debug)]
+pub(crate) struct ConfigHistory {
+    store: KvStore,
+}
+
+impl ConfigHistory {
+    pub(crate) fn new(store: KvStore) -> Self {
+        Self { store }
+    }
+
+    /// Returns the retained revisions of `name`, ordered from oldest to newest
+    pub(crate) async fn get(&self, name: &str) -> anyhow::Result<Vec<ConfigRevision>> {
+        let entry = self
+            .store
+            .entry(name)
+            .await
+            .context("failed to get config history")?;
+        match entry {
+            Some(entry) if entry.operation == Operation::Put => {
+                serde_json::from_slice(&entry.value).context("failed to parse config history")
+            }
+            _ => Ok(Vec::default()),
+        }
+    }
+
+    /// Records the revision of `name` written to the config store with revision `revision`,
+    /// changing its values to `values`, where `None` denotes a deletion. Revisions are ordered by
+    /// their number regardless of the order they are recorded in, and recording a revision again
+    /// has no effect
+    pub(crate) async fn record(
+        &self,
+        name: &str,
+        revision: u64,
+        values: Option<BTreeMap<String, String>>,
+        author: Option<String>,
+        rollback_of: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let mut builder = ConfigRevision::builder()
+            .revision(revision)
+            .timestamp(humantime::format_rfc3339_millis(SystemTime::now()).to_string());
+        if let Some(values) = values {
+            builder = builder.values(values);
+        }
+        if let Some(author) = author {
+            builder = builder.author(author);
+        }
+        if let Some(rollback_of) = rollback_of {
+            builder = builder.rollback_of(rollback_of);
+        }
+        let revision = builder
+            .build()
+            .map_err(|e| anyhow!(e).context("failed to build config revision"))?;
+        for _ in 0..MAX_RECORD_ATTEMPTS {
+            let entry = self
+                .store
+                .entry(name)
+                .await
+                .context("failed to get config history")?;
+            let (mut history, last) = match entry {
+                Some(entry) if entry.operation == Operation::Put => (
+                    serde_json::from_slice(&entry.value)
+                        .context("failed to parse config history")?,
+                    entry.revision,
+                ),
+                Some(entry) => (Vec::default(), entry.revision),
+                None => (Vec::default(), 0),
+            };
+            if !insert_revision(&mut history, revision.clone()) {
+                return Ok(());
+            }
+            let buf = serde_json::to_vec(&history).context("failed to encode config history")?;
+            let updated = self
+                .store
+                .update(name, buf.into(), last)
+                .await
+                .context("failed to store config history")?;
+            if updated.is_some() {
+                return Ok(());
+            }
+        }
+        bail!("config history was concurrently updated {MAX_RECORD_ATTEMPTS} times")
+    }
+}
+
+/// Inserts `revision` into `history` ordered by revision number, dropping the oldest revisions
+/// beyond the retained maximum. Returns `false` if `history` already contains the revision or it
+/// is older than all retained revisions of a full history
+fn insert_revision(history: &mut Vec<ConfigRevision>, revision: ConfigRevision) -> bool {
+    let Err(idx) = history.binary_search_by_key(&revision.revision(), ConfigRevision::revision)
+    else {
+        return false;
+    };
+    if idx == 0 && history.len() >= MAX_CONFIG_REVISIONS {
+        return false;
+    }
+    history.insert(idx, revision);
+    if let Some(excess) = history.len().checked_sub(MAX_CONFIG_REVISIONS) {
+        history.drain(..excess);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(revision: u64) -> anyhow::Result<ConfigRevision> {
+        ConfigRevision::builder()
+            .revision(revision)
+            .timestamp("2024-10-01T12:00:00.000Z".into())
+            .build()
+            .map_err(|e| anyhow!(e))
+    }
+
+    fn numbers(history: &[ConfigRevision]) -> Vec<u64> {
+        history.iter().map(ConfigRevision::revision).collect()
+    }
+
+    #[test]
+    fn ordering() -> anyhow::Result<()> {
+        let mut history = Vec::default();
+        assert!(insert_revision(&mut history, revision(5)?));
+        assert!(insert_revision(&mut history, revision(2)?));
+        assert!(insert_revision(&mut history, revision(9)?));
+        assert!(!insert_revision(&mut history, revision(5)?));
+        assert_eq!(numbers(&history), [2, 5, 9]);
+        Ok(())
+    }
+
+    #[test]
+    fn retention() -> anyhow::Result<()> {
+        let mut history = Vec::default();
+        for n in 1..=u64::try_from(MAX_CONFIG_REVISIONS)? + 3 {
+            assert!(insert_revision(&mut history, revision(n)?));
+        }
+        assert_eq!(history.len(), MAX_CONFIG_REVISIONS);
+        assert_eq!(history.first().map(ConfigRevision::revision), Some(4));
+        assert!(!insert_revision(&mut history, revision(2)?));
+        assert_eq!(history.first().map(ConfigRevision::revision), Some(4));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record() -> anyhow::Result<()> {
+        let history = ConfigHistory::new(KvStore::memory("history"));
+        let values = BTreeMap::from([("foo".to_string(), "bar".to_string())]);
+        history
+            .record("config", 7, None, Some("alice".into()), None)
+            .await?;
+        history
+            .record("config", 3, Some(values.clone()), None, None)
+            .await?;
+        history
+            .record("config", 7, Some(values.clone()), None, None)
+            .await?;
+        let revisions = history.get("config").await?;
+        assert_eq!(numbers(&revisions), [3, 7]);
+        assert_eq!(revisions[0].values(), Some(&values));
+        assert_eq!(revisions[1].values(), None);
+        assert_eq!(revisions[1].author(), Some("alice"));
+        assert!(history.get("missing").await?.is_empty());
+        Ok(())
+    }
+}
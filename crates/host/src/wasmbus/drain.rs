@@ -0,0 +1,194 @@
+//! Graceful draining of the host
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::join_all;
+use tokio::sync::watch;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, instrument, warn};
+
+use super::{event, Host};
+
+/// Tracks the number of invocations currently handled by the components of the host
+#[derive(Clone, Debug)]
+pub(crate) struct InFlight(Arc<watch::Sender<usize>>);
+
+impl Default for InFlight {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(0)))
+    }
+}
+
+impl InFlight {
+    /// Marks the start of an invocation, which is considered finished once the returned guard is
+    /// dropped
+    pub(crate) fn start(&self) -> InFlightGuard {
+        self.0.send_modify(|n| *n += 1);
+        InFlightGuard(Arc::clone(&self.0))
+    }
+
+    /// Returns the number of invocations in flight
+    pub(crate) fn count(&self) -> usize {
+        *self.0.borrow()
+    }
+
+    /// Waits until no invocations are in flight
+    async fn idle(&self) {
+        // The sender is owned by `self`, so this cannot fail
+        let _ = self.0.subscribe().wait_for(|n| *n == 0).await;
+    }
+
+    /// Waits until no invocations are in flight or `deadline` passes and returns whether no
+    /// invocations are in flight
+    async fn idle_until(&self, deadline: Option<Instant>) -> bool {
+        match deadline {
+            Some(deadline) => timeout_at(deadline, self.idle()).await.is_ok(),
+            None => {
+                self.idle().await;
+                true
+            }
+        }
+    }
+}
+
+/// Guard of an invocation in flight, see [`InFlight::start`]
+#[derive(Debug)]
+pub(crate) struct InFlightGuard(Arc<watch::Sender<usize>>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|n| *n = n.saturating_sub(1));
+    }
+}
+
+impl Host {
+    /// Drains the host and stops it afterwards.
+    ///
+    /// A draining host stops bidding on component and provider auctions, reports itself as not
+    /// ready and stops accepting new invocations. Invocations in flight are given until `timeout`
+    /// elapses to finish. Afterwards, providers are requested to shut down gracefully and given
+    /// the remainder of `timeout` to acknowledge, such that they can finish the requests they are
+    /// handling, after which the host is stopped regardless. Requests handled by providers are
+    /// not tracked by the host, so a provider, which acknowledges the shutdown before finishing
+    /// them, may still cut them off.
+    ///
+    /// Calling this on a host, which is already draining, has no effect.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn drain(&self, timeout: Option<Duration>) {
+        if self.draining.send_replace(true) {
+            debug!("host is already draining");
+            return;
+        }
+        let in_flight = self.in_flight.count();
+        info!(?timeout, in_flight, "draining host");
+        if let Err(err) = self
+            .publish_event("host_draining", event::host_draining(timeout, in_flight))
+            .await
+        {
+            warn!(?err, "failed to publish host draining event");
+        }
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        if !self.in_flight.idle_until(deadline).await {
+            warn!(
+                in_flight = self.in_flight.count(),
+                "drain timed out with invocations in flight"
+            );
+        }
+        self.shutdown_providers(deadline).await;
+        info!("host drained");
+        self.stop(None);
+    }
+
+    /// Requests all providers to shut down gracefully, waiting for each to acknowledge for up to
+    /// the provider shutdown delay, but no longer than until `deadline`
+    async fn shutdown_providers(&self, deadline: Option<Instant>) {
+        let host_id = self.host_key.public_key();
+        let delay = self.host_config.provider_shutdown_delay;
+        let timeout = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    warn!("drain timed out before providers were shut down");
+                    return;
+                }
+                Some(delay.map_or(remaining, |delay| delay.min(remaining)))
+            }
+            None => delay,
+        };
+        let providers = self.providers.read().await;
+        join_all(providers.iter().map(|(provider_id, provider)| {
+            // Make sure the provider is not restarted once it shuts down
+            provider.supervisor_stop.send_replace(true);
+            let host_id = host_id.as_str();
+            async move {
+                if let Err(err) = self
+                    .request_provider_shutdown(provider_id, host_id, timeout)
+                    .await
+                {
+                    warn!(?err, provider_id, "failed to request provider shutdown");
+                }
+            }
+        }))
+        .await;
+    }
+
+    /// Returns whether the host is draining
+    pub(crate) fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_flight() {
+        let in_flight = InFlight::default();
+        in_flight.idle().await;
+
+        let a = in_flight.start();
+        let b = in_flight.start();
+        assert_eq!(in_flight.count(), 2);
+        drop(a);
+        assert_eq!(in_flight.count(), 1);
+
+        let idle = tokio::spawn({
+            let in_flight = in_flight.clone();
+            async move { in_flight.idle().await }
+        });
+        drop(b);
+        idle.await.expect("failed to wait for idle");
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_until() {
+        let in_flight = InFlight::default();
+        assert!(in_flight.idle_until(None).await);
+
+        // An invocation, which does not finish in time, does not block the drain past the deadline
+        let stuck = in_flight.start();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        assert!(!in_flight.idle_until(Some(deadline)).await);
+        assert!(Instant::now() >= deadline);
+        assert_eq!(in_flight.count(), 1);
+        drop(stuck);
+
+        // An invocation, which finishes before the deadline, is awaited
+        let guard = in_flight.start();
+        let invocation = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            drop(guard);
+        });
+        let start = Instant::now();
+        assert!(
+            in_flight
+                .idle_until(Some(start + Duration::from_secs(10)))
+                .await
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+        invocation.await.expect("failed to handle invocation");
+    }
+}
@@ -0,0 +1,265 @@
+//! Outbound network policy of components, derived from annotations and named config
+
+use core::net::IpAddr;
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, ensure, Context as _};
+use wasmcloud_runtime::component::{EgressTarget, SocketAddrUse};
+
+/// Annotation and config key containing the outbound network allowlist of a component.
+///
+/// The value is a comma-separated list of `host[:port]` entries, where `host` is one of:
+/// - a hostname, e.g. `api.example.com`
+/// - a wildcard matching all subdomains, e.g. `*.example.com`
+/// - an IP address or a CIDR, e.g. `10.0.0.0/8` or `[2001:db8::]/32` (IPv6 with a port must be
+///   enclosed in brackets)
+///
+/// If not set, outgoing HTTP requests are not restricted.
+pub(crate) const EGRESS_KEY: &str = "wasmcloud.dev/egress";
+
+/// Annotation and config key, which allows the component to use `wasi:sockets` if set to `true`.
+/// Socket connections are subject to the allowlist specified by [`EGRESS_KEY`], if any.
+pub(crate) const ALLOW_SOCKETS_KEY: &str = "wasmcloud.dev/allow-sockets";
+
+/// Maximum number of `component_egress_denied` events published per second for a single
+/// component. Denials exceeding it are counted and reported with the next published event
+pub(crate) const MAX_EGRESS_DENIED_EVENTS: u32 = 10;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum EgressHost {
+    /// Exact hostname
+    Name(String),
+    /// Suffix of the hostname, including the leading `.`
+    Wildcard(String),
+    /// IP network
+    Cidr(IpAddr, u8),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct EgressRule {
+    host: EgressHost,
+    /// Allowed port, all ports are allowed if `None`
+    port: Option<u16>,
+}
+
+/// Returns whether `ip` is within the `net/len` network
+fn cidr_contains(net: IpAddr, len: u8, ip: IpAddr) -> bool {
+    match (net, ip.to_canonical()) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(len)).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(len)).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+impl EgressRule {
+    fn parse(rule: &str) -> anyhow::Result<Self> {
+        let (host, port) = if let Some(rule) = rule.strip_prefix('[') {
+            let Some((ip, rest)) = rule.split_once(']') else {
+                bail!("egress rule `[{rule}` is missing a closing bracket");
+            };
+            let (prefix, port) = rest.split_once(':').unwrap_or((rest, ""));
+            (format!("{ip}{prefix}"), port)
+        } else if rule.matches(':').count() > 1 {
+            // IPv6 address without a port
+            (rule.to_string(), "")
+        } else {
+            let (host, port) = rule.split_once(':').unwrap_or((rule, ""));
+            (host.to_string(), port)
+        };
+        ensure!(!host.is_empty(), "egress rule `{rule}` host is empty");
+        let port = if port.is_empty() {
+            None
+        } else {
+            let port = port
+                .parse()
+                .with_context(|| format!("egress rule `{rule}` port is invalid"))?;
+            Some(port)
+        };
+        let host = if let Some((ip, len)) = host.split_once('/') {
+            let ip: IpAddr = ip
+                .parse()
+                .with_context(|| format!("egress rule `{rule}` network address is invalid"))?;
+            let len = len
+                .parse()
+                .with_context(|| format!("egress rule `{rule}` prefix length is invalid"))?;
+            let max = if ip.is_ipv4() { 32 } else { 128 };
+            ensure!(
+                len <= max,
+                "egress rule `{rule}` prefix length exceeds {max}"
+            );
+            EgressHost::Cidr(ip, len)
+        } else if let Ok(ip) = host.parse::<IpAddr>() {
+            EgressHost::Cidr(ip, if ip.is_ipv4() { 32 } else { 128 })
+        } else if let Some(suffix) = host.strip_prefix('*') {
+            ensure!(
+                suffix.len() > 1 && suffix.starts_with('.'),
+                "egress rule `{rule}` wildcard must be in `*.domain` format"
+            );
+            EgressHost::Wildcard(suffix.to_ascii_lowercase())
+        } else {
+            EgressHost::Name(host.to_ascii_lowercase())
+        };
+        Ok(Self { host, port })
+    }
+
+    fn matches_host(&self, host: &str, port: u16) -> bool {
+        if self.port.is_some_and(|p| p != port) {
+            return false;
+        }
+        if let Ok(ip) = host.parse() {
+            return self.matches_ip(ip);
+        }
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        match &self.host {
+            EgressHost::Name(name) => *name == host,
+            EgressHost::Wildcard(suffix) => host.ends_with(suffix.as_str()),
+            EgressHost::Cidr(..) => false,
+        }
+    }
+
+    fn matches_ip(&self, ip: IpAddr) -> bool {
+        match self.host {
+            EgressHost::Cidr(net, len) => cidr_contains(net, len, ip),
+            EgressHost::Name(..) | EgressHost::Wildcard(..) => false,
+        }
+    }
+}
+
+/// Outbound network policy of a component
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EgressPolicy {
+    /// Allowed destinations, all destinations are allowed if `None`
+    rules: Option<Vec<EgressRule>>,
+    /// Whether `wasi:sockets` are allowed to be used
+    allow_sockets: bool,
+}
+
+impl EgressPolicy {
+    /// Parses the policy declared for a component through annotations and its named config.
+    /// Rules from both sources are combined, config takes precedence for [`ALLOW_SOCKETS_KEY`].
+    pub(crate) fn new(
+        annotations: &BTreeMap<String, String>,
+        config: &HashMap<String, String>,
+    ) -> anyhow::Result<Self> {
+        let mut rules = None;
+        for spec in [annotations.get(EGRESS_KEY), config.get(EGRESS_KEY)]
+            .into_iter()
+            .flatten()
+        {
+            let rules: &mut Vec<_> = rules.get_or_insert_with(Vec::default);
+            for rule in spec.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+                rules.push(EgressRule::parse(rule)?);
+            }
+        }
+        let allow_sockets = config
+            .get(ALLOW_SOCKETS_KEY)
+            .or_else(|| annotations.get(ALLOW_SOCKETS_KEY))
+            .map(|v| v.parse())
+            .transpose()
+            .with_context(|| format!("invalid `{ALLOW_SOCKETS_KEY}` value"))?
+            .unwrap_or_default();
+        Ok(Self {
+            rules,
+            allow_sockets,
+        })
+    }
+
+    /// Returns whether the policy permits access to `target`
+    pub(crate) fn permits(&self, target: &EgressTarget) -> bool {
+        match target {
+            EgressTarget::Http { host, port } => self
+                .rules
+                .as_ref()
+                .is_none_or(|rules| rules.iter().any(|r| r.matches_host(host, *port))),
+            EgressTarget::Socket { .. } if !self.allow_sockets => false,
+            // Binding local addresses is not egress
+            EgressTarget::Socket {
+                usage: SocketAddrUse::TcpBind | SocketAddrUse::UdpBind,
+                ..
+            } => true,
+            EgressTarget::Socket { addr, .. } => self.rules.as_ref().is_none_or(|rules| {
+                rules
+                    .iter()
+                    .any(|r| r.port.is_none_or(|p| p == addr.port()) && r.matches_ip(addr.ip()))
+            }),
+        }
+    }
+}
+
+/// Returns the protocol, host and port of `target` for reporting
+pub(crate) fn describe_target(target: &EgressTarget) -> (&'static str, String, u16) {
+    match target {
+        EgressTarget::Http { host, port } => ("http", host.clone(), *port),
+        EgressTarget::Socket {
+            addr,
+            usage: SocketAddrUse::TcpBind | SocketAddrUse::TcpConnect,
+        } => ("tcp", addr.ip().to_string(), addr.port()),
+        EgressTarget::Socket { addr, .. } => ("udp", addr.ip().to_string(), addr.port()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http(host: &str, port: u16) -> EgressTarget {
+        EgressTarget::Http {
+            host: host.into(),
+            port,
+        }
+    }
+
+    fn tcp(addr: &str) -> EgressTarget {
+        EgressTarget::Socket {
+            addr: addr.parse().expect("invalid socket address"),
+            usage: SocketAddrUse::TcpConnect,
+        }
+    }
+
+    #[test]
+    fn egress_policy() {
+        let policy = EgressPolicy::default();
+        assert!(policy.permits(&http("example.com", 443)));
+        assert!(!policy.permits(&tcp("10.0.0.1:5432")));
+
+        let annotations = BTreeMap::from([(
+            EGRESS_KEY.to_string(),
+            "api.example.com:443, *.internal, 10.0.0.0/8:5432".to_string(),
+        )]);
+        let config = HashMap::from([
+            (EGRESS_KEY.to_string(), "[fd00::]/8:443,::1".to_string()),
+            (ALLOW_SOCKETS_KEY.to_string(), "true".to_string()),
+        ]);
+        let policy = EgressPolicy::new(&annotations, &config).expect("failed to parse policy");
+        assert!(policy.permits(&http("API.example.com", 443)));
+        assert!(!policy.permits(&http("api.example.com", 80)));
+        assert!(!policy.permits(&http("example.com", 443)));
+        assert!(policy.permits(&http("db.svc.internal", 8080)));
+        assert!(!policy.permits(&http("internal", 8080)));
+        assert!(policy.permits(&http("10.1.2.3", 5432)));
+        assert!(policy.permits(&http("fd12::1", 443)));
+        assert!(policy.permits(&tcp("10.1.2.3:5432")));
+        assert!(!policy.permits(&tcp("10.1.2.3:5433")));
+        assert!(!policy.permits(&tcp("192.168.0.1:5432")));
+        assert!(policy.permits(&tcp("[::ffff:10.0.0.1]:5432")));
+        assert!(policy.permits(&tcp("[::1]:1234")));
+
+        for rule in [
+            "*",
+            "*example.com",
+            "10.0.0.0/33",
+            "example.com:http",
+            "[::1",
+        ] {
+            let annotations = BTreeMap::from([(EGRESS_KEY.to_string(), rule.to_string())]);
+            assert!(EgressPolicy::new(&annotations, &HashMap::default()).is_err());
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! `wasi:cli` arguments and environment variables of components, derived from named config
+
+use std::collections::HashMap;
+
+use wasmcloud_runtime::component::CliEnvironment;
+
+/// Prefix of config keys, which are made available to components as environment variables.
+///
+/// For example, config key `wasmcloud.dev/env.LOG_LEVEL` with value `debug` results in
+/// environment variable `LOG_LEVEL=debug`.
+pub(crate) const ENV_PREFIX: &str = "wasmcloud.dev/env.";
+
+/// Config key containing whitespace-separated arguments passed to components after `argv[0]`
+pub(crate) const ARGS_KEY: &str = "wasmcloud.dev/args";
+
+/// Derives the [`CliEnvironment`] of a component from its merged config
+pub(crate) fn cli_environment(config: &HashMap<String, String>) -> CliEnvironment {
+    let args = config
+        .get(ARGS_KEY)
+        .map(|args| args.split_whitespace().map(String::from).collect())
+        .unwrap_or_default();
+    let mut env: Vec<_> = config
+        .iter()
+        .filter_map(|(k, v)| {
+            let k = k.strip_prefix(ENV_PREFIX)?;
+            (!k.is_empty()).then(|| (k.to_string(), v.clone()))
+        })
+        .collect();
+    env.sort_unstable();
+    CliEnvironment { args, env }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_environment_from_config() {
+        let config = HashMap::from([
+            ("wasmcloud.dev/env.LOG_LEVEL".into(), "debug".into()),
+            ("wasmcloud.dev/env.A".into(), "b".into()),
+            ("wasmcloud.dev/env.".into(), "ignored".into()),
+            ("wasmcloud.dev/args".into(), " --port  8080 -v".into()),
+            ("LOG_LEVEL".into(), "info".into()),
+        ]);
+        assert_eq!(
+            cli_environment(&config),
+            CliEnvironment {
+                args: vec!["--port".into(), "8080".into(), "-v".into()],
+                env: vec![
+                    ("A".into(), "b".into()),
+                    ("LOG_LEVEL".into(), "debug".into())
+                ],
+            }
+        );
+        assert_eq!(cli_environment(&HashMap::new()), CliEnvironment::default());
+    }
+}
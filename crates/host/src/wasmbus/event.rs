@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 use anyhow::Context;
 use cloudevents::{EventBuilder, EventBuilderV10};
@@ -10,6 +11,8 @@ use uuid::Uuid;
 use wascap::jwt;
 use wasmcloud_control_interface::Link;
 
+use crate::SignatureVerificationError;
+
 fn format_component_claims(claims: &jwt::Claims<jwt::Component>) -> serde_json::Value {
     let issuer = &claims.issuer;
     let not_before_human = "TODO";
@@ -39,6 +42,7 @@ pub fn component_scaled(
     annotations: &BTreeMap<String, String>,
     host_id: impl AsRef<str>,
     max_instances: impl Into<usize>,
+    max_memory: Option<u64>,
     image_ref: impl AsRef<str>,
     component_id: impl AsRef<str>,
 ) -> serde_json::Value {
@@ -50,6 +54,7 @@ pub fn component_scaled(
             "host_id": host_id.as_ref(),
             "image_ref": image_ref.as_ref(),
             "max_instances": max_instances.into(),
+            "max_memory": max_memory,
             "component_id": component_id.as_ref(),
         })
     } else {
@@ -58,6 +63,7 @@ pub fn component_scaled(
             "host_id": host_id.as_ref(),
             "image_ref": image_ref.as_ref(),
             "max_instances": max_instances.into(),
+            "max_memory": max_memory,
             "component_id": component_id.as_ref(),
         })
     }
@@ -187,6 +193,34 @@ pub fn provider_started(
     }
 }
 
+pub fn component_signature_verification_failed(
+    host_id: impl AsRef<str>,
+    image_ref: impl AsRef<str>,
+    component_id: impl AsRef<str>,
+    error: &SignatureVerificationError,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "image_ref": image_ref.as_ref(),
+        "component_id": component_id.as_ref(),
+        "error": error.to_string(),
+    })
+}
+
+pub fn provider_signature_verification_failed(
+    host_id: impl AsRef<str>,
+    provider_ref: impl AsRef<str>,
+    provider_id: impl AsRef<str>,
+    error: &SignatureVerificationError,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "provider_ref": provider_ref.as_ref(),
+        "provider_id": provider_id.as_ref(),
+        "error": error.to_string(),
+    })
+}
+
 pub fn provider_start_failed(
     provider_ref: impl AsRef<str>,
     provider_id: impl AsRef<str>,
@@ -206,12 +240,14 @@ pub fn provider_stopped(
     host_id: impl AsRef<str>,
     provider_id: impl AsRef<str>,
     reason: impl AsRef<str>,
+    limit_exceeded: Option<&str>,
 ) -> serde_json::Value {
     json!({
         "host_id": host_id.as_ref(),
         "provider_id": provider_id.as_ref(),
         "annotations": annotations,
         "reason": reason.as_ref(),
+        "limit_exceeded": limit_exceeded,
         // TODO(#1548): remove these fields when we don't depend on them
         "instance_id": provider_id.as_ref(),
         "public_key": provider_id.as_ref(),
@@ -219,6 +255,34 @@ pub fn provider_stopped(
     })
 }
 
+pub fn provider_restarted(
+    host_id: impl AsRef<str>,
+    provider_id: impl AsRef<str>,
+    restarts: u32,
+    reason: impl AsRef<str>,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "provider_id": provider_id.as_ref(),
+        "restarts": restarts,
+        "reason": reason.as_ref(),
+    })
+}
+
+pub fn provider_crash_looping(
+    host_id: impl AsRef<str>,
+    provider_id: impl AsRef<str>,
+    restarts: u32,
+    reason: impl AsRef<str>,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "provider_id": provider_id.as_ref(),
+        "restarts": restarts,
+        "reason": reason.as_ref(),
+    })
+}
+
 pub fn provider_health_check(
     host_id: impl AsRef<str>,
     provider_id: impl AsRef<str>,
@@ -241,6 +305,31 @@ pub fn config_deleted(config_name: impl AsRef<str>) -> serde_json::Value {
     })
 }
 
+pub fn component_egress_denied(
+    host_id: impl AsRef<str>,
+    component_id: impl AsRef<str>,
+    protocol: &str,
+    host: impl AsRef<str>,
+    port: u16,
+    suppressed: u64,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "component_id": component_id.as_ref(),
+        "protocol": protocol,
+        "host": host.as_ref(),
+        "port": port,
+        "suppressed": suppressed,
+    })
+}
+
+pub fn host_draining(timeout: Option<Duration>, in_flight: usize) -> serde_json::Value {
+    json!({
+        "timeout_ms": timeout.map(|timeout| timeout.as_millis()),
+        "in_flight_invocations": in_flight,
+    })
+}
+
 pub fn labels_changed(
     host_id: impl AsRef<str>,
     labels: impl Into<HashMap<String, String>>,
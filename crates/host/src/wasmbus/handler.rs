@@ -1,27 +1,33 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context as _};
 use async_trait::async_trait;
 use bytes::Bytes;
+use cloudevents::EventBuilderV10;
 use secrecy::Secret;
 use tokio::sync::RwLock;
-use tracing::{error, instrument, warn};
+use tokio::time::timeout;
+use tracing::{debug, error, info, instrument, trace, warn};
 use wasmcloud_runtime::capability;
 use wasmcloud_runtime::capability::logging::logging;
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_runtime::capability::{secrets, CallTargetInterface};
 use wasmcloud_runtime::component::{
-    Bus, Bus1_0_0, Config, InvocationErrorIntrospect, InvocationErrorKind, Logging,
-    ReplacedInstanceTarget, Secrets,
+    is_out_of_fuel, Bus, Bus1_0_0, Config, Egress, EgressTarget, InvocationErrorIntrospect,
+    InvocationErrorKind, Logging, ReplacedInstanceTarget, Secrets, StdioStream,
 };
 use wasmcloud_tracing::context::TraceContextInjector;
 use wrpc_transport::InvokeExt as _;
 
 use super::config::ConfigBundle;
+use super::egress::{describe_target, EgressPolicy};
+use super::event;
 use super::injector_to_headers;
+use super::local::{self, InvocationStream, LocalComponents};
+use super::output::OutputRateLimiter;
 
 #[derive(Clone, Debug)]
 pub struct Handler {
@@ -38,6 +44,8 @@ pub struct Handler {
     pub lattice: Arc<str>,
     /// The identifier of the component that this handler is associated with
     pub component_id: Arc<str>,
+    /// The identifier of the host running the component
+    pub host_id: Arc<str>,
     /// The current link targets. `instance` -> `link-name`
     /// Instance specification does not include a version
     pub targets: Arc<RwLock<HashMap<Box<str>, Arc<str>>>>,
@@ -58,6 +66,23 @@ pub struct Handler {
     pub instance_links: Arc<RwLock<HashMap<Box<str>, HashMap<Box<str>, Box<str>>>>>,
 
     pub invocation_timeout: Duration,
+
+    /// Rate limiter of component `stdout` and `stderr` output, shared by all instances of the
+    /// component
+    pub output_limiter: Arc<OutputRateLimiter>,
+
+    /// Outbound network policy of the component
+    pub egress_policy: Arc<RwLock<EgressPolicy>>,
+    /// Rate limiter of `component_egress_denied` events, shared by all instances of the component
+    pub egress_denied_limiter: Arc<OutputRateLimiter>,
+    /// NATS client used to publish events
+    pub ctl_nats: async_nats::Client,
+    /// Builder of published events
+    pub event_builder: EventBuilderV10,
+
+    /// Components running on this host, which are invoked directly instead of over NATS.
+    /// Local invocations are disabled if `None`
+    pub local_components: Option<LocalComponents>,
 }
 
 impl Handler {
@@ -70,10 +95,17 @@ impl Handler {
             secrets: self.secrets.clone(),
             lattice: self.lattice.clone(),
             component_id: self.component_id.clone(),
+            host_id: self.host_id.clone(),
             targets: Arc::default(),
             trace_ctx: Arc::default(),
             instance_links: self.instance_links.clone(),
             invocation_timeout: self.invocation_timeout,
+            output_limiter: self.output_limiter.clone(),
+            egress_policy: Arc::default(),
+            egress_denied_limiter: self.egress_denied_limiter.clone(),
+            ctl_nats: self.ctl_nats.clone(),
+            event_builder: self.event_builder.clone(),
+            local_components: self.local_components.clone(),
         }
     }
 }
@@ -155,8 +187,14 @@ impl Bus for Handler {
 
 impl wrpc_transport::Invoke for Handler {
     type Context = Option<ReplacedInstanceTarget>;
-    type Outgoing = <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Outgoing;
-    type Incoming = <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Incoming;
+    type Outgoing = InvocationStream<
+        <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Outgoing,
+        wrpc_transport::frame::Outgoing,
+    >;
+    type Incoming = InvocationStream<
+        <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Incoming,
+        wrpc_transport::frame::Incoming,
+    >;
 
     async fn invoke<P>(
         &self,
@@ -221,15 +259,50 @@ impl wrpc_transport::Invoke for Handler {
         let mut headers = injector_to_headers(&TraceContextInjector::default_with_span());
         headers.insert("source-id", &*self.component_id);
         headers.insert("link-name", link_name);
+        if let Some(local_components) = &self.local_components {
+            let endpoint = local_components.read().await.get(&**id).cloned();
+            if let Some(endpoint) = endpoint {
+                // Like for invocations over NATS, the timeout bounds the whole invocation, including
+                // waiting for the component to accept it
+                let local_paths = paths.as_ref();
+                let local_invocation = async {
+                    let permit = endpoint.reserve().await.ok()?;
+                    trace!(target_id = ?id, "invoking component running on this host");
+                    Some(
+                        local::invoke(
+                            permit,
+                            headers.clone(),
+                            instance,
+                            func,
+                            params.clone(),
+                            local_paths,
+                        )
+                        .await,
+                    )
+                };
+                match timeout(self.invocation_timeout, local_invocation).await {
+                    Ok(Some(res)) => {
+                        let (tx, rx) = res?;
+                        return Ok((InvocationStream::Local(tx), InvocationStream::Local(rx)));
+                    }
+                    Ok(None) => {
+                        debug!(target_id = ?id, "local component stopped, invoking over NATS");
+                    }
+                    Err(_) => bail!("invocation of local component `{id}` timed out"),
+                }
+            }
+        }
         let nats = wrpc_transport_nats::Client::new(
             Arc::clone(&self.nats),
             format!("{}.{id}", &self.lattice),
             None,
         )
         .await?;
-        nats.timeout(self.invocation_timeout)
+        let (tx, rx) = nats
+            .timeout(self.invocation_timeout)
             .invoke(Some(headers), instance, func, params, paths)
-            .await
+            .await?;
+        Ok((InvocationStream::Nats(tx), InvocationStream::Nats(rx)))
     }
 }
 
@@ -262,6 +335,48 @@ impl Config for Handler {
     }
 }
 
+#[async_trait]
+impl Egress for Handler {
+    #[instrument(level = "debug", skip(self))]
+    async fn check_egress(&self, target: &EgressTarget) -> bool {
+        if self.egress_policy.read().await.permits(target) {
+            return true;
+        }
+        // A component retrying denied requests in a loop must not flood the lattice with events
+        let Some(suppressed) = self.egress_denied_limiter.acquire(Instant::now()) else {
+            return false;
+        };
+        let (protocol, host, port) = describe_target(target);
+        warn!(
+            component_id = ?self.component_id,
+            protocol,
+            host,
+            port,
+            suppressed,
+            "outbound network access denied by egress policy"
+        );
+        if let Err(err) = event::publish(
+            &self.event_builder,
+            &self.ctl_nats,
+            &self.lattice,
+            "component_egress_denied",
+            event::component_egress_denied(
+                &self.host_id,
+                &self.component_id,
+                protocol,
+                host,
+                port,
+                suppressed,
+            ),
+        )
+        .await
+        {
+            warn!(?err, "failed to publish egress denied event");
+        }
+        false
+    }
+}
+
 #[async_trait]
 impl Logging for Handler {
     #[instrument(level = "trace", skip(self))]
@@ -329,6 +444,47 @@ impl Logging for Handler {
         };
         Ok(())
     }
+
+    fn log_output(&self, stream: StdioStream, line: &str) {
+        let Some(dropped) = self.output_limiter.acquire(Instant::now()) else {
+            return;
+        };
+        let span = tracing::info_span!(
+            "component_output",
+            component_id = %self.component_id,
+            host_id = %self.host_id,
+        );
+        // Output is written while the component is being invoked, associate it with the trace
+        // context of the invocation. Similar to `invoke`, this must never block.
+        if let Ok(trace_context) = self.trace_ctx.try_read() {
+            if !trace_context.is_empty() {
+                span.in_scope(|| wasmcloud_tracing::context::attach_span_context(&trace_context));
+            }
+        }
+        let _enter = span.enter();
+        if dropped > 0 {
+            warn!(
+                component_id = %self.component_id,
+                host_id = %self.host_id,
+                dropped,
+                "component output rate limit exceeded, dropped lines"
+            );
+        }
+        match stream {
+            StdioStream::Stdout => info!(
+                component_id = %self.component_id,
+                host_id = %self.host_id,
+                stream = %stream,
+                "{line}"
+            ),
+            StdioStream::Stderr => warn!(
+                component_id = %self.component_id,
+                host_id = %self.host_id,
+                stream = %stream,
+                "{line}"
+            ),
+        }
+    }
 }
 
 #[async_trait]
@@ -366,6 +522,9 @@ impl Secrets for Handler {
 
 impl InvocationErrorIntrospect for Handler {
     fn invocation_error_kind(&self, err: &anyhow::Error) -> InvocationErrorKind {
+        if is_out_of_fuel(err) {
+            return InvocationErrorKind::OutOfFuel;
+        }
         if let Some(err) = err.root_cause().downcast_ref::<std::io::Error>() {
             if err.kind() == std::io::ErrorKind::NotConnected {
                 return InvocationErrorKind::NotFound;
@@ -1,12 +1,18 @@
 use crate::OciConfig;
 
 use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::bail;
 use nkeys::KeyPair;
 use url::Url;
 use wasmcloud_core::{logging::Level as LogLevel, OtelConfig};
+pub use wasmcloud_runtime::cache::DEFAULT_COMPONENT_CACHE_MAX_SIZE;
 use wasmcloud_runtime::{MAX_COMPONENTS, MAX_COMPONENT_SIZE, MAX_LINEAR_MEMORY};
 
 /// wasmCloud Host configuration
@@ -47,6 +53,9 @@ pub struct Host {
     pub oci_opts: OciConfig,
     /// Whether to allow loading component or provider components from the filesystem
     pub allow_file_load: bool,
+    /// Host directories, within which components are allowed to have directories preopened via
+    /// `wasi:filesystem`. If empty, components cannot access the host filesystem
+    pub allowed_preopen_paths: Vec<PathBuf>,
     /// Whether or not structured logging is enabled
     pub enable_structured_logging: bool,
     /// Log level to pass to capability providers to use. Should be parsed from a [`tracing::Level`]
@@ -64,14 +73,163 @@ pub struct Host {
     pub version: String,
     /// The maximum execution time for a component instance
     pub max_execution_time: Duration,
-    /// The maximum linear memory that a component instance can allocate
+    /// The maximum linear memory that a component instance can allocate. Individual components can
+    /// be restricted further using the `wasmcloud.dev/max-memory` annotation
     pub max_linear_memory: u64,
     /// The maximum size of a component binary that can be loaded
     pub max_component_size: u64,
     /// The maximum number of components that can be run simultaneously
     pub max_components: u32,
+    /// Whether to meter fuel consumed by component invocations. Per-component fuel budgets
+    /// can only be enforced if this is enabled
+    pub fuel_metering: bool,
+    /// Whether WASI preview 1 core modules are allowed to be started, in which case they are
+    /// adapted to components when loaded. Adapted components are stored in the component cache,
+    /// if configured. Defaults to `true`
+    pub allow_core_modules: bool,
+    /// Whether core modules exporting a `_start` function are adapted using the WASI command
+    /// adapter instead of the reactor adapter, which is used for all core modules otherwise.
+    /// Defaults to `false`
+    pub adapt_command_modules: bool,
     /// The interval at which the Host will send heartbeats
     pub heartbeat_interval: Option<Duration>,
+    /// Directory used to cache precompiled components. If not set, components are compiled on
+    /// every start
+    pub component_cache_dir: Option<PathBuf>,
+    /// The maximum size of the precompiled component cache on disk, in bytes
+    pub component_cache_max_size: u64,
+    /// The maximum number of lines of component `stdout` and `stderr` output logged per second
+    /// for each component. Excess lines are dropped. `0` disables the limit
+    pub max_component_output_lines: u32,
+    /// Whether invocations of components running on this host are dispatched directly, instead
+    /// of going through NATS
+    pub local_component_invocations: bool,
+    /// Destination of recordings of invocations of components annotated with
+    /// `wasmcloud.dev/record-invocations: "true"`. If not set, invocations are not recorded
+    pub invocation_recordings: Option<InvocationRecordingSink>,
+    /// Default restart policy of capability provider processes, which defaults to never restarting
+    /// them. Individual providers can override it using the `wasmcloud.dev/restart-policy`
+    /// annotation
+    pub provider_restart_policy: ProviderRestartPolicy,
+    /// The maximum number of consecutive restarts of a capability provider, after which it is
+    /// considered to be crash looping and is not restarted anymore. Individual providers can
+    /// override it using the `wasmcloud.dev/max-restarts` annotation
+    pub provider_max_restarts: u32,
+    /// Delay before the first restart of an exited capability provider, which is doubled on every
+    /// consecutive restart
+    pub provider_restart_backoff: Duration,
+    /// The maximum delay between consecutive restarts of a capability provider
+    pub provider_max_restart_backoff: Duration,
+    /// Default OS-level resource limits of capability provider processes. Individual providers can
+    /// override them using the `wasmcloud.dev/max-memory`, `wasmcloud.dev/cpu-weight`,
+    /// `wasmcloud.dev/max-open-files` and `wasmcloud.dev/max-processes` annotations
+    pub provider_limits: ProviderLimits,
+    /// If set, each capability provider process is placed in a dedicated cgroup v2 created in this
+    /// directory, which must be a cgroup v2 directory writable by the host without any processes
+    /// of its own. If not set, limits are enforced using rlimits only
+    pub provider_cgroup_root: Option<PathBuf>,
+    /// If set, the host serves `/livez`, `/readyz` and `/inventory` HTTP endpoints on this address
+    pub http_admin: Option<SocketAddr>,
+    /// If set, metrics collected in this registry are served in the Prometheus text format on the
+    /// `/metrics` path of the HTTP admin endpoint
+    pub prometheus_registry: Option<prometheus::Registry>,
+    /// If set, the host applies the components, providers, links and named config described by
+    /// the manifest at this path and reconciles them whenever the file changes.
+    /// Such a host does not connect to the control plane and only requires NATS for provider RPC.
+    /// Data otherwise stored in the JetStream buckets of the lattice, such as links and named
+    /// config, is kept in memory only and lost when the host stops, and control interface events
+    /// are published on the RPC connection
+    pub manifest: Option<PathBuf>,
+    /// Whether the host records the components and providers it runs in the lattice data bucket
+    /// and brings them back on startup. This requires a stable host key and has no effect for a
+    /// host running a [`manifest`](Self::manifest)
+    pub rehydrate_workloads: bool,
+    /// If set, the host does not bid in auctions once its utilization in percent reaches this
+    /// threshold. Utilization is the higher of the share of used component slots and the CPU load
+    pub auction_refusal_threshold: Option<u32>,
+    /// Destination of the audit log recording every control interface request handled by the
+    /// host and its outcome. If not set, requests are not audited
+    pub audit_log: Option<AuditLogSink>,
+}
+
+/// OS-level resource limits of a capability provider process. Limits are only enforced on Linux
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ProviderLimits {
+    /// The maximum amount of memory in bytes. Enforced using `memory.max` of the provider cgroup,
+    /// if [`Host::provider_cgroup_root`] is set, or using `RLIMIT_AS` otherwise, which limits the
+    /// virtual address space rather than the resident memory of the process
+    pub max_memory: Option<u64>,
+    /// The relative share of CPU time in the range `1..=10000`, where `100` is the default share of
+    /// a process. Enforced using `cpu.weight` of the provider cgroup and ignored if
+    /// [`Host::provider_cgroup_root`] is not set
+    pub cpu_weight: Option<u64>,
+    /// The maximum number of file descriptors the provider can open. Enforced using `RLIMIT_NOFILE`
+    pub max_open_files: Option<u64>,
+    /// The maximum number of processes and threads. Enforced using `pids.max` of the provider
+    /// cgroup, if [`Host::provider_cgroup_root`] is set, or using `RLIMIT_NPROC` otherwise, which
+    /// counts all processes and threads of the user running the host, not only those of the
+    /// provider
+    pub max_processes: Option<u64>,
+}
+
+/// Policy determining whether the process of a capability provider is restarted once it exits.
+/// Unless the policy is [`Never`](Self::Never), a provider, which exits and is not restarted, is
+/// removed from the host and a `provider_stopped` event is published
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ProviderRestartPolicy {
+    /// The provider is never restarted and remains in the inventory of the host after it exits,
+    /// until it is stopped
+    #[default]
+    Never,
+    /// The provider is restarted if it exits with a non-zero exit code or is killed by a signal
+    OnFailure,
+    /// The provider is restarted whenever it exits, unless it was stopped by the host
+    Always,
+}
+
+impl fmt::Display for ProviderRestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Never => write!(f, "never"),
+            Self::OnFailure => write!(f, "on-failure"),
+            Self::Always => write!(f, "always"),
+        }
+    }
+}
+
+impl FromStr for ProviderRestartPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(Self::Never),
+            "on-failure" => Ok(Self::OnFailure),
+            "always" => Ok(Self::Always),
+            _ => bail!(
+                "unknown provider restart policy `{s}`, expected one of `never`, `on-failure` or `always`"
+            ),
+        }
+    }
+}
+
+/// Destination of component invocation recordings
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvocationRecordingSink {
+    /// Each recording is written as a JSON file into a subdirectory named after the component ID
+    Directory(PathBuf),
+    /// Recordings are published as JSON on `wasmcloud.{lattice}.recordings.{component_id}` and
+    /// retained for a day in the `RECORDINGS_{lattice}` JetStream stream
+    JetStream,
+}
+
+/// Destination of the audit log of control interface requests
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditLogSink {
+    /// Each audit record is appended as a line of JSON to this file
+    File(PathBuf),
+    /// Audit records are published as JSON on `wasmcloud.{lattice}.audit.{operation}` and
+    /// retained in the `AUDIT_{lattice}` JetStream stream
+    JetStream,
 }
 
 /// Configuration for wasmCloud policy service
@@ -83,6 +241,9 @@ pub struct PolicyService {
     pub policy_changes_topic: Option<String>,
     /// The timeout for policy requests
     pub policy_timeout_ms: Option<Duration>,
+    /// If provided, policy requests are decided in-process using the rules in this file instead
+    /// of a policy server. The file is reloaded whenever it changes
+    pub policy_file: Option<PathBuf>,
 }
 
 impl Default for Host {
@@ -107,6 +268,7 @@ impl Default for Host {
             provider_shutdown_delay: None,
             oci_opts: OciConfig::default(),
             allow_file_load: false,
+            allowed_preopen_paths: Vec::default(),
             enable_structured_logging: false,
             log_level: LogLevel::Info,
             config_service_enabled: false,
@@ -120,7 +282,27 @@ impl Default for Host {
             // 50 MB
             max_component_size: MAX_COMPONENT_SIZE,
             max_components: MAX_COMPONENTS,
+            fuel_metering: false,
+            allow_core_modules: true,
+            adapt_command_modules: false,
             heartbeat_interval: None,
+            component_cache_dir: None,
+            component_cache_max_size: DEFAULT_COMPONENT_CACHE_MAX_SIZE,
+            max_component_output_lines: 100,
+            local_component_invocations: true,
+            invocation_recordings: None,
+            provider_restart_policy: ProviderRestartPolicy::default(),
+            provider_max_restarts: 5,
+            provider_restart_backoff: Duration::from_secs(1),
+            provider_max_restart_backoff: Duration::from_secs(60),
+            provider_limits: ProviderLimits::default(),
+            provider_cgroup_root: None,
+            http_admin: None,
+            prometheus_registry: None,
+            manifest: None,
+            rehydrate_workloads: false,
+            auction_refusal_threshold: None,
+            audit_log: None,
         }
     }
 }
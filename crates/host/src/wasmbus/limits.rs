@@ -0,0 +1,275 @@
+//! OS-level resource limits of capability provider processes
+
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context as _};
+#[cfg(target_os = "linux")]
+use tokio::fs;
+use tokio::process;
+use tracing::warn;
+
+use super::host_config::ProviderLimits;
+use super::{Annotations, HostConfig, MAX_MEMORY_ANNOTATION};
+
+/// Annotation used to override the host default CPU weight of a provider
+pub(crate) const CPU_WEIGHT_ANNOTATION: &str = "wasmcloud.dev/cpu-weight";
+
+/// Annotation used to override the host default maximum number of open files of a provider
+pub(crate) const MAX_OPEN_FILES_ANNOTATION: &str = "wasmcloud.dev/max-open-files";
+
+/// Annotation used to override the host default maximum number of processes of a provider
+pub(crate) const MAX_PROCESSES_ANNOTATION: &str = "wasmcloud.dev/max-processes";
+
+/// Returns the value of a numeric limit annotation, if set
+fn parse_annotation(annotations: &Annotations, name: &str) -> anyhow::Result<Option<u64>> {
+    annotations
+        .get(name)
+        .map(|v| v.parse())
+        .transpose()
+        .with_context(|| format!("invalid `{name}` annotation"))
+}
+
+impl ProviderLimits {
+    /// Returns the limits of a provider started with `annotations`, falling back to `self` for
+    /// limits not set through annotations
+    pub(crate) fn with_annotations(self, annotations: &Annotations) -> anyhow::Result<Self> {
+        let limits = Self {
+            max_memory: parse_annotation(annotations, MAX_MEMORY_ANNOTATION)?.or(self.max_memory),
+            cpu_weight: parse_annotation(annotations, CPU_WEIGHT_ANNOTATION)?.or(self.cpu_weight),
+            max_open_files: parse_annotation(annotations, MAX_OPEN_FILES_ANNOTATION)?
+                .or(self.max_open_files),
+            max_processes: parse_annotation(annotations, MAX_PROCESSES_ANNOTATION)?
+                .or(self.max_processes),
+        };
+        if let Some(cpu_weight) = limits.cpu_weight {
+            ensure!(
+                (1..=10_000).contains(&cpu_weight),
+                "CPU weight of {cpu_weight} is not in the range of 1 to 10000"
+            );
+        }
+        Ok(limits)
+    }
+
+    /// Returns whether no limits are set
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Enforces [`ProviderLimits`] on the processes of a single provider. On Linux, the provider may be
+/// placed in a dedicated cgroup, which is removed once this is dropped
+#[derive(Debug)]
+pub(crate) struct ProviderLimiter {
+    #[cfg_attr(not(target_os = "linux"), allow(unused))]
+    limits: ProviderLimits,
+    /// Path of the cgroup of the provider
+    #[cfg(target_os = "linux")]
+    cgroup: Option<PathBuf>,
+}
+
+impl ProviderLimiter {
+    /// Returns a limiter of the provider identified by `provider_id` started with `annotations`,
+    /// creating the provider cgroup if configured
+    pub(crate) async fn new(
+        config: &HostConfig,
+        provider_id: &str,
+        annotations: &Annotations,
+    ) -> anyhow::Result<Self> {
+        let limits = config.provider_limits.with_annotations(annotations)?;
+        #[cfg(target_os = "linux")]
+        {
+            let cgroup = match config.provider_cgroup_root.as_deref() {
+                Some(root) if !limits.is_empty() => {
+                    Some(create_cgroup(root, provider_id, &limits).await?)
+                }
+                _ => None,
+            };
+            Ok(Self { limits, cgroup })
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            if !limits.is_empty() {
+                warn!(
+                    provider_id,
+                    "provider resource limits are only supported on Linux and will not be enforced"
+                );
+            }
+            Ok(Self { limits })
+        }
+    }
+
+    /// Configures `cmd` such that the spawned provider process is subject to the limits
+    pub(crate) fn apply(&self, cmd: &mut process::Command) -> anyhow::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::fs::OpenOptions;
+            use std::os::fd::AsRawFd as _;
+
+            use nix::sys::resource::{setrlimit, Resource};
+
+            // Open `cgroup.procs` before forking, such that the child process can move itself
+            // into the cgroup without allocating
+            let procs = self
+                .cgroup
+                .as_ref()
+                .map(|cgroup| {
+                    OpenOptions::new()
+                        .write(true)
+                        .open(cgroup.join("cgroup.procs"))
+                        .context("failed to open `cgroup.procs` of provider cgroup")
+                })
+                .transpose()?;
+            let mut rlimits = Vec::with_capacity(3);
+            if let Some(n) = self.limits.max_open_files {
+                rlimits.push((Resource::RLIMIT_NOFILE, n));
+            }
+            if procs.is_none() {
+                if let Some(n) = self.limits.max_memory {
+                    rlimits.push((Resource::RLIMIT_AS, n));
+                }
+                if let Some(n) = self.limits.max_processes {
+                    rlimits.push((Resource::RLIMIT_NPROC, n));
+                }
+                if self.limits.cpu_weight.is_some() {
+                    warn!("provider CPU weight can only be enforced with a cgroup root configured");
+                }
+            }
+            if procs.is_none() && rlimits.is_empty() {
+                return Ok(());
+            }
+            // SAFETY: the closure only performs async-signal-safe system calls
+            unsafe {
+                cmd.pre_exec(move || {
+                    if let Some(procs) = &procs {
+                        nix::unistd::write(procs.as_raw_fd(), b"0")?;
+                    }
+                    for (resource, n) in &rlimits {
+                        setrlimit(*resource, *n, *n)?;
+                    }
+                    Ok(())
+                });
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = cmd;
+        Ok(())
+    }
+
+    /// Returns the number of processes of the provider killed due to exceeding the memory limit
+    pub(crate) async fn oom_kills(&self) -> u64 {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            match fs::read_to_string(cgroup.join("memory.events")).await {
+                Ok(events) => return parse_oom_kills(&events),
+                Err(err) => warn!(?err, "failed to read `memory.events` of provider cgroup"),
+            }
+        }
+        0
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for ProviderLimiter {
+    fn drop(&mut self) {
+        if let Some(cgroup) = &self.cgroup {
+            if let Err(err) = std::fs::remove_dir(cgroup) {
+                warn!(?err, cgroup = %cgroup.display(), "failed to remove provider cgroup");
+            }
+        }
+    }
+}
+
+/// Creates the cgroup of the provider identified by `provider_id` in `root` and configures it
+/// according to `limits`
+#[cfg(target_os = "linux")]
+async fn create_cgroup(
+    root: &Path,
+    provider_id: &str,
+    limits: &ProviderLimits,
+) -> anyhow::Result<PathBuf> {
+    fs::write(root.join("cgroup.subtree_control"), "+memory +cpu +pids")
+        .await
+        .with_context(|| {
+            format!(
+                "failed to enable controllers in provider cgroup root `{}`",
+                root.display()
+            )
+        })?;
+    let name = provider_id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+    let cgroup = root.join(format!("provider-{name}"));
+    fs::create_dir_all(&cgroup)
+        .await
+        .with_context(|| format!("failed to create provider cgroup `{}`", cgroup.display()))?;
+    for (file, limit) in [
+        ("memory.max", limits.max_memory),
+        ("cpu.weight", limits.cpu_weight),
+        ("pids.max", limits.max_processes),
+    ] {
+        let limit = limit.map_or_else(|| "max".to_string(), |n| n.to_string());
+        fs::write(cgroup.join(file), limit)
+            .await
+            .with_context(|| format!("failed to write `{file}` of provider cgroup"))?;
+    }
+    Ok(cgroup)
+}
+
+/// Parses the `oom_kill` counter from the contents of a cgroup `memory.events` file
+#[cfg(target_os = "linux")]
+fn parse_oom_kills(events: &str) -> u64 {
+    events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_limits() -> anyhow::Result<()> {
+        let defaults = ProviderLimits {
+            max_memory: Some(1024),
+            max_open_files: Some(64),
+            ..Default::default()
+        };
+        let limits = defaults.with_annotations(&Annotations::from([
+            (MAX_MEMORY_ANNOTATION.into(), "2048".into()),
+            (CPU_WEIGHT_ANNOTATION.into(), "50".into()),
+        ]))?;
+        assert_eq!(
+            limits,
+            ProviderLimits {
+                max_memory: Some(2048),
+                cpu_weight: Some(50),
+                max_open_files: Some(64),
+                max_processes: None,
+            }
+        );
+        assert!(defaults
+            .with_annotations(&Annotations::from([(
+                CPU_WEIGHT_ANNOTATION.into(),
+                "0".into()
+            )]))
+            .is_err());
+        assert!(defaults
+            .with_annotations(&Annotations::from([(
+                MAX_PROCESSES_ANNOTATION.into(),
+                "many".into()
+            )]))
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn oom_kills() {
+        assert_eq!(
+            parse_oom_kills("low 0\nhigh 0\nmax 3\noom 2\noom_kill 2\noom_group_kill 0\n"),
+            2
+        );
+        assert_eq!(parse_oom_kills(""), 0);
+    }
+}
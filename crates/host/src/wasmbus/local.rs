@@ -0,0 +1,230 @@
+//! Short-circuiting of invocations of components running on the same host.
+//!
+//! Instead of going through NATS, local invocations are sent over in-memory streams using the
+//! framed wRPC transport and are accepted by the same [`wrpc_transport::Serve`] implementation
+//! used for remote invocations, so policy checks, tracing, metrics and instance limits apply
+//! uniformly.
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use async_nats::HeaderMap;
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf, ReadHalf, WriteHalf};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use wrpc_transport::frame::{self, Accept};
+use wrpc_transport::Index;
+
+/// Size of the in-memory buffer of each local invocation stream
+const LOCAL_INVOCATION_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Maximum number of local invocations waiting to be accepted by a component
+pub(crate) const LOCAL_INVOCATION_QUEUE_SIZE: usize = 64;
+
+/// Invocation of a local component, consisting of the wRPC context and the server end of an
+/// in-memory stream
+pub(crate) type LocalInvocation = (Option<HeaderMap>, DuplexStream);
+
+/// Endpoints of components running on this host keyed by component ID
+pub(crate) type LocalComponents = Arc<RwLock<HashMap<Arc<str>, mpsc::Sender<LocalInvocation>>>>;
+
+/// wRPC server accepting local invocations of a single component
+pub(crate) type LocalServer =
+    frame::Server<Option<HeaderMap>, ReadHalf<DuplexStream>, WriteHalf<DuplexStream>>;
+
+/// [`Accept`] implementation yielding local invocations of a single component
+pub(crate) struct LocalListener(Mutex<mpsc::Receiver<LocalInvocation>>);
+
+impl LocalListener {
+    pub(crate) fn new(invocations: mpsc::Receiver<LocalInvocation>) -> Self {
+        Self(Mutex::new(invocations))
+    }
+}
+
+impl Accept for &LocalListener {
+    type Context = Option<HeaderMap>;
+    type Outgoing = WriteHalf<DuplexStream>;
+    type Incoming = ReadHalf<DuplexStream>;
+
+    async fn accept(&self) -> io::Result<(Self::Context, Self::Outgoing, Self::Incoming)> {
+        let (cx, stream) = self
+            .0
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "local invoker closed"))?;
+        let (rx, tx) = tokio::io::split(stream);
+        Ok((cx, tx, rx))
+    }
+}
+
+/// Invokes `func` on `instance` of a local component, using a reserved slot of its endpoint
+pub(crate) async fn invoke<P>(
+    permit: mpsc::Permit<'_, LocalInvocation>,
+    headers: HeaderMap,
+    instance: &str,
+    func: &str,
+    params: Bytes,
+    paths: impl AsRef<[P]> + Send,
+) -> anyhow::Result<(frame::Outgoing, frame::Incoming)>
+where
+    P: AsRef<[Option<usize>]> + Send + Sync,
+{
+    let (client, server) = tokio::io::duplex(LOCAL_INVOCATION_BUFFER_SIZE);
+    permit.send((Some(headers), server));
+    let (rx, tx) = tokio::io::split(client);
+    frame::invoke(tx, rx, instance, func, params, paths).await
+}
+
+/// wRPC byte stream, which is either transmitted over NATS or in-memory for local invocations
+pub enum InvocationStream<N, L> {
+    /// Stream of a NATS invocation
+    Nats(N),
+    /// Stream of a local invocation
+    Local(L),
+}
+
+impl<N, L> Index<Self> for InvocationStream<N, L>
+where
+    N: Index<N>,
+    L: Index<L>,
+{
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        match self {
+            Self::Nats(stream) => stream.index(path).map(Self::Nats),
+            Self::Local(stream) => stream.index(path).map(Self::Local),
+        }
+    }
+}
+
+impl<N, L> AsyncRead for InvocationStream<N, L>
+where
+    N: AsyncRead + Unpin,
+    L: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Nats(stream) => Pin::new(stream).poll_read(cx, buf),
+            Self::Local(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<N, L> AsyncWrite for InvocationStream<N, L>
+where
+    N: AsyncWrite + Unpin,
+    L: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Nats(stream) => Pin::new(stream).poll_write(cx, buf),
+            Self::Local(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Nats(stream) => Pin::new(stream).poll_flush(cx),
+            Self::Local(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Nats(stream) => Pin::new(stream).poll_shutdown(cx),
+            Self::Local(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Nats(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            Self::Local(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Self::Nats(stream) => stream.is_write_vectored(),
+            Self::Local(stream) => stream.is_write_vectored(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt as _;
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use wrpc_transport::Serve as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn local_invocation_roundtrip() -> anyhow::Result<()> {
+        let srv = LocalServer::default();
+        let mut invocations = srv
+            .serve("wasmcloud:test/echo", "echo", Vec::default())
+            .await?;
+        let (tx, rx) = mpsc::channel(1);
+        let listener = LocalListener::new(rx);
+        let mut headers = HeaderMap::new();
+        headers.insert("source-id", "caller");
+
+        let permit = tx.reserve().await?;
+        let (invoked, accepted) = tokio::join!(
+            invoke::<&[Option<usize>]>(
+                permit,
+                headers,
+                "wasmcloud:test/echo",
+                "echo",
+                Bytes::from("ping"),
+                [],
+            ),
+            srv.accept(&listener),
+        );
+        let (client_tx, mut client_rx) = invoked?;
+        accepted.map_err(|err| anyhow::anyhow!("{err}"))?;
+        // Framed streams are closed on drop
+        drop(client_tx);
+
+        let (cx, mut server_tx, mut server_rx) =
+            invocations.next().await.expect("invocation stream ended")?;
+        assert_eq!(
+            cx.and_then(|cx| cx.get("source-id").map(ToString::to_string)),
+            Some("caller".into())
+        );
+        let mut params = Vec::new();
+        server_rx.read_to_end(&mut params).await?;
+        assert_eq!(params, b"ping");
+        server_tx.write_all(b"pong").await?;
+        drop(server_tx);
+
+        let mut results = Vec::new();
+        client_rx.read_to_end(&mut results).await?;
+        assert_eq!(results, b"pong");
+
+        drop(tx);
+        assert!(matches!(
+            srv.accept(&listener).await,
+            Err(frame::AcceptError::IO(err)) if err.kind() == io::ErrorKind::BrokenPipe
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+//! Local manifest describing the workloads of a host, which is applied without a control plane
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use serde::Deserialize;
+use tokio::fs;
+use tracing::{debug, error, info, instrument, warn};
+use wasmcloud_control_interface::{
+    CtlResponse, DeleteInterfaceLinkDefinitionRequest, Link, ScaleComponentCommand,
+    StartProviderCommand, StopProviderCommand,
+};
+
+use super::{Annotations, Host};
+use crate::watch_file;
+
+/// Workloads of a host described by a local manifest file
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Manifest {
+    /// Named configuration, keyed by name
+    #[serde(default)]
+    config: BTreeMap<String, HashMap<String, String>>,
+    #[serde(default)]
+    components: Vec<ManifestComponent>,
+    #[serde(default)]
+    providers: Vec<ManifestProvider>,
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+/// Component described by a [`Manifest`]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct ManifestComponent {
+    id: String,
+    image: String,
+    #[serde(default = "default_max_instances")]
+    max_instances: u32,
+    #[serde(default)]
+    annotations: Annotations,
+    #[serde(default)]
+    config: Vec<String>,
+}
+
+fn default_max_instances() -> u32 {
+    1
+}
+
+/// Capability provider described by a [`Manifest`]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct ManifestProvider {
+    id: String,
+    image: String,
+    #[serde(default)]
+    annotations: Annotations,
+    #[serde(default)]
+    config: Vec<String>,
+}
+
+/// Single step of reconciling a host from one [`Manifest`] to another
+#[derive(Debug, PartialEq)]
+enum Change<'a> {
+    PutConfig(&'a str, &'a HashMap<String, String>),
+    DeleteConfig(&'a str),
+    ScaleComponent(&'a ManifestComponent),
+    StopComponent(&'a ManifestComponent),
+    StartProvider(&'a ManifestProvider),
+    StopProvider(&'a str),
+    PutLink(&'a Link),
+    DeleteLink(&'a Link),
+}
+
+impl Manifest {
+    /// Reads and parses the manifest at `path`
+    pub(crate) async fn load(path: &Path) -> anyhow::Result<Self> {
+        let buf = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read manifest `{}`", path.display()))?;
+        serde_yaml::from_slice(&buf)
+            .with_context(|| format!("failed to parse manifest `{}`", path.display()))
+    }
+
+    /// Returns the changes required to reconcile a host running the workloads of `self` to the
+    /// workloads of `next`, in the order they should be applied
+    fn changes<'a>(&'a self, next: &'a Self) -> Vec<Change<'a>> {
+        let mut changes = Vec::new();
+        // Config is put first and deleted last, such that it is available to all workloads
+        // referencing it
+        for (name, values) in &next.config {
+            if self.config.get(name) != Some(values) {
+                changes.push(Change::PutConfig(name, values));
+            }
+        }
+        for prev in &self.providers {
+            if !next.providers.contains(prev) {
+                changes.push(Change::StopProvider(&prev.id));
+            }
+        }
+        for prev in &self.components {
+            if !next.components.iter().any(|c| c.id == prev.id) {
+                changes.push(Change::StopComponent(prev));
+            }
+        }
+        // Links, which changed, are deleted before being put again, since a link cannot be
+        // replaced by one with the same source, name and interface but a different target
+        for prev in &self.links {
+            if !next.links.contains(prev) {
+                changes.push(Change::DeleteLink(prev));
+            }
+        }
+        for component in &next.components {
+            if !self.components.contains(component) {
+                changes.push(Change::ScaleComponent(component));
+            }
+        }
+        for provider in &next.providers {
+            if !self.providers.contains(provider) {
+                changes.push(Change::StartProvider(provider));
+            }
+        }
+        for link in &next.links {
+            if !self.links.contains(link) {
+                changes.push(Change::PutLink(link));
+            }
+        }
+        for name in self.config.keys() {
+            if !next.config.contains_key(name) {
+                changes.push(Change::DeleteConfig(name));
+            }
+        }
+        changes
+    }
+}
+
+impl Host {
+    /// Reconciles the host from the workloads of `prev` to the workloads of `next`
+    #[instrument(level = "debug", skip_all)]
+    async fn apply_manifest(self: &Arc<Self>, prev: &Manifest, next: &Manifest) {
+        let host_id = self.host_key.public_key();
+        for change in prev.changes(next) {
+            debug!(?change, "applying manifest change");
+            let res = match change {
+                Change::PutConfig(name, values) => match serde_json::to_vec(values) {
+                    Ok(data) => self.handle_config_put(name, data.into(), None).await,
+                    Err(err) => Err(anyhow!(err).context("failed to encode config")),
+                },
+                Change::DeleteConfig(name) => self.handle_config_delete(name, None).await,
+                Change::ScaleComponent(component) => {
+                    self.scale_manifest_component(&host_id, component, component.max_instances)
+                        .await
+                }
+                Change::StopComponent(component) => {
+                    self.scale_manifest_component(&host_id, component, 0).await
+                }
+                // Providers, which changed, are stopped before being started again
+                Change::StartProvider(provider) => {
+                    self.start_manifest_provider(&host_id, provider).await
+                }
+                Change::StopProvider(provider_id) => {
+                    self.stop_manifest_provider(&host_id, provider_id).await;
+                    Ok(CtlResponse::success(String::default()))
+                }
+                Change::PutLink(link) => match serde_json::to_vec(link) {
+                    Ok(payload) => self.handle_link_put(payload).await,
+                    Err(err) => Err(anyhow!(err).context("failed to encode link")),
+                },
+                Change::DeleteLink(link) => {
+                    match serde_json::to_vec(
+                        &DeleteInterfaceLinkDefinitionRequest::from_source_and_link_metadata(
+                            link.source_id(),
+                            link.name(),
+                            link.wit_namespace(),
+                            link.wit_package(),
+                        ),
+                    ) {
+                        Ok(payload) => self.handle_link_del(payload).await,
+                        Err(err) => Err(anyhow!(err).context("failed to encode link deletion")),
+                    }
+                }
+            };
+            match res {
+                Ok(res) if !res.succeeded() => {
+                    warn!(
+                        ?change,
+                        message = res.message(),
+                        "failed to apply manifest change"
+                    );
+                }
+                Ok(..) => {}
+                Err(err) => warn!(?change, ?err, "failed to apply manifest change"),
+            }
+        }
+    }
+
+    async fn scale_manifest_component(
+        self: &Arc<Self>,
+        host_id: &str,
+        component: &ManifestComponent,
+        max_instances: u32,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let cmd = ScaleComponentCommand::builder()
+            .component_ref(&component.image)
+            .component_id(&component.id)
+            .annotations(component.annotations.clone())
+            .max_instances(max_instances)
+            .host_id(host_id)
+            .config(component.config.clone())
+            .allow_update(true)
+            .build()
+            .map_err(|e| anyhow!("failed to build component scale command: {e}"))?;
+        let payload =
+            serde_json::to_vec(&cmd).context("failed to encode component scale command")?;
+        Arc::clone(self)
+            .handle_scale_component(payload, host_id)
+            .await
+    }
+
+    async fn start_manifest_provider(
+        self: &Arc<Self>,
+        host_id: &str,
+        provider: &ManifestProvider,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let cmd = StartProviderCommand::builder()
+            .provider_ref(&provider.image)
+            .provider_id(&provider.id)
+            .annotations(provider.annotations.clone())
+            .host_id(host_id)
+            .config(provider.config.clone())
+            .build()
+            .map_err(|e| anyhow!("failed to build provider start command: {e}"))?;
+        let payload =
+            serde_json::to_vec(&cmd).context("failed to encode provider start command")?;
+        Arc::clone(self)
+            .handle_start_provider(payload, host_id)
+            .await
+    }
+
+    async fn stop_manifest_provider(&self, host_id: &str, provider_id: &str) {
+        let res = async {
+            let cmd = StopProviderCommand::builder()
+                .provider_id(provider_id)
+                .host_id(host_id)
+                .build()
+                .map_err(|e| anyhow!("failed to build provider stop command: {e}"))?;
+            let payload =
+                serde_json::to_vec(&cmd).context("failed to encode provider stop command")?;
+            self.handle_stop_provider(payload, host_id).await
+        }
+        .await;
+        match res {
+            Ok(res) if !res.succeeded() => {
+                warn!(
+                    provider_id,
+                    message = res.message(),
+                    "failed to stop provider"
+                );
+            }
+            Ok(..) => {}
+            Err(err) => warn!(provider_id, ?err, "failed to stop provider"),
+        }
+    }
+
+    /// Applies `manifest` read from `path` and reconciles the host whenever the file changes
+    #[instrument(level = "debug", skip(self, manifest))]
+    pub(crate) async fn watch_manifest(
+        self: Arc<Self>,
+        path: PathBuf,
+        manifest: Manifest,
+    ) -> anyhow::Result<()> {
+        let (_watcher, mut changes_rx) = watch_file(&path).context("failed to watch manifest")?;
+
+        info!(path = %path.display(), "applying manifest");
+        self.apply_manifest(&Manifest::default(), &manifest).await;
+        let mut applied = manifest;
+        while changes_rx.recv().await.is_some() {
+            match Manifest::load(&path).await {
+                Ok(manifest) if manifest == applied => {
+                    debug!("manifest did not change");
+                }
+                Ok(manifest) => {
+                    info!(path = %path.display(), "reconciling changed manifest");
+                    self.apply_manifest(&applied, &manifest).await;
+                    applied = manifest;
+                }
+                Err(err) => error!(?err, "failed to reload manifest, keeping current workloads"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+config:
+  greeting:
+    message: hello
+components:
+  - id: http-hello
+    image: file:///tmp/http_hello.wasm
+    max_instances: 10
+    config: [greeting]
+providers:
+  - id: http-server
+    image: ghcr.io/wasmcloud/http-server:0.24.0
+links:
+  - source_id: http-server
+    target: http-hello
+    wit_namespace: wasi
+    wit_package: http
+    interfaces: [incoming-handler]
+"#;
+
+    #[test]
+    fn changes() -> anyhow::Result<()> {
+        let manifest: Manifest = serde_yaml::from_str(MANIFEST)?;
+        assert_eq!(manifest.components[0].max_instances, 10);
+        assert_eq!(manifest.links[0].name(), "default");
+
+        let empty = Manifest::default();
+        assert_eq!(
+            empty.changes(&manifest),
+            [
+                Change::PutConfig("greeting", &manifest.config["greeting"]),
+                Change::ScaleComponent(&manifest.components[0]),
+                Change::StartProvider(&manifest.providers[0]),
+                Change::PutLink(&manifest.links[0]),
+            ]
+        );
+        assert!(manifest.changes(&manifest).is_empty());
+
+        let mut next = manifest.clone();
+        next.components[0].max_instances = 5;
+        next.providers.clear();
+        next.links.clear();
+        next.config.clear();
+        assert_eq!(
+            manifest.changes(&next),
+            [
+                Change::StopProvider("http-server"),
+                Change::DeleteLink(&manifest.links[0]),
+                Change::ScaleComponent(&next.components[0]),
+                Change::DeleteConfig("greeting"),
+            ]
+        );
+        assert_eq!(
+            manifest.changes(&empty),
+            [
+                Change::StopProvider("http-server"),
+                Change::StopComponent(&manifest.components[0]),
+                Change::DeleteLink(&manifest.links[0]),
+                Change::DeleteConfig("greeting"),
+            ]
+        );
+
+        let retargeted: Manifest =
+            serde_yaml::from_str(&MANIFEST.replace("target: http-hello", "target: http-other"))?;
+        assert_eq!(
+            manifest.changes(&retargeted),
+            [
+                Change::DeleteLink(&manifest.links[0]),
+                Change::PutLink(&retargeted.links[0]),
+            ]
+        );
+        assert!(serde_yaml::from_str::<Manifest>("actors: []").is_err());
+        Ok(())
+    }
+}
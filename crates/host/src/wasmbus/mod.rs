@@ -1,22 +1,20 @@
 use std::collections::btree_map::Entry as BTreeMapEntry;
 use std::collections::hash_map::{self, Entry};
 use std::collections::{BTreeMap, HashMap};
-use std::env;
 use std::env::consts::{ARCH, FAMILY, OS};
 use std::future::Future;
+use std::io;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
 use std::pin::Pin;
-use std::process::Stdio;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
 use anyhow::{anyhow, bail, ensure, Context as _};
 use async_nats::jetstream::kv::{Entry as KvEntry, Operation, Store};
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine;
 use bytes::{BufMut, Bytes, BytesMut};
 use cloudevents::{EventBuilder, EventBuilderV10};
 use futures::future::Either;
@@ -26,40 +24,67 @@ use nkeys::{KeyPair, KeyPairType, XKey};
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tokio::io::{AsyncWrite, AsyncWriteExt};
-use tokio::sync::{broadcast, mpsc, watch, RwLock, Semaphore};
+use tokio::io::AsyncWrite;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, watch, Mutex, RwLock, Semaphore};
 use tokio::task::{JoinHandle, JoinSet};
-use tokio::time::{interval_at, Instant};
-use tokio::{process, select, spawn};
+use tokio::time::{interval, interval_at, Instant};
+use tokio::{fs, select, spawn};
 use tokio_stream::wrappers::IntervalStream;
 use tracing::{debug, error, info, instrument, trace, warn, Instrument as _};
 use uuid::Uuid;
 use wascap::{jwt, prelude::ClaimsBuilder};
 use wasmcloud_control_interface::{
-    ComponentAuctionAck, ComponentAuctionRequest, ComponentDescription, CtlResponse,
-    DeleteInterfaceLinkDefinitionRequest, HostInventory, HostLabel, Link, ProviderAuctionAck,
-    ProviderAuctionRequest, ProviderDescription, RegistryCredential, ScaleComponentCommand,
-    StartProviderCommand, StopHostCommand, StopProviderCommand, UpdateComponentCommand,
+    ComponentAuctionAck, ComponentAuctionRequest, ComponentDescription, ConfigRollbackRequest,
+    CtlResponse, DeleteInterfaceLinkDefinitionRequest, HostInventory, HostLabel, Link,
+    ProviderAuctionAck, ProviderAuctionRequest, ProviderDescription, RegistryCredential,
+    ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
+    UpdateComponentCommand,
 };
 use wasmcloud_core::{
-    provider_config_update_subject, ComponentId, HealthCheckResponse, HostData, OtelConfig,
-    CTL_API_VERSION_1,
+    format_config_violations, provider_config_update_subject, sanitize_audit_payload,
+    validate_config as validate_config_schema, validate_config_properties, AuditRecord,
+    ComponentId, HealthCheckResponse, HostData, OtelConfig, CTL_API_VERSION_1,
 };
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_runtime::component::WrpcServeEvent;
-use wasmcloud_runtime::Runtime;
+use wasmcloud_runtime::{ComponentCache, Runtime};
 use wasmcloud_secrets_types::SECRET_PREFIX;
 use wasmcloud_tracing::context::TraceContextInjector;
 use wasmcloud_tracing::{global, KeyValue};
+use wrpc_transport::frame::AcceptError;
 
+use crate::policy::{
+    ConfigRequest, DeleteLinkRequest, LabelRequest, PutLinkRequest, PutRegistriesRequest,
+    RequestBody, StopHostRequest,
+};
 use crate::registry::RegistryCredentialExt;
+use crate::signature::{verify_signature, TrustRoots};
+use crate::store::KvStore;
 use crate::{
-    fetch_component, HostMetrics, OciConfig, PolicyHostInfo, PolicyManager, PolicyResponse,
-    RegistryAuth, RegistryConfig, RegistryType, SecretsManager,
+    fetch_component, is_oci_reference, HostMetrics, OciConfig, PolicyHostInfo, PolicyManager,
+    PolicyResponse, RegistryAuth, RegistryConfig, RegistryType, SecretsManager,
+    SignatureVerificationError,
 };
 
+mod admin;
+mod artifacts;
+mod audit;
+mod capacity;
+mod config_history;
+mod drain;
+mod egress;
+mod environment;
 mod event;
 mod handler;
+mod limits;
+mod local;
+mod manifest;
+mod output;
+mod preopens;
+mod recording;
+mod rehydrate;
+mod supervisor;
 
 pub mod config;
 /// wasmCloud host configuration
@@ -67,12 +92,34 @@ pub mod host_config;
 
 pub use self::host_config::Host as HostConfig;
 
+use self::artifacts::OCI_CACHE_GC_INTERVAL;
+use self::audit::{audit_timestamp, requesting_user, response_outcome, AuditLog};
 use self::config::{BundleGenerator, ConfigBundle};
+use self::config_history::ConfigHistory;
+use self::drain::InFlight;
+use self::egress::{EgressPolicy, MAX_EGRESS_DENIED_EVENTS};
 use self::handler::Handler;
+use self::limits::ProviderLimiter;
+use self::local::{
+    InvocationStream, LocalComponents, LocalInvocation, LocalListener, LocalServer,
+    LOCAL_INVOCATION_QUEUE_SIZE,
+};
+use self::manifest::Manifest;
+use self::output::OutputRateLimiter;
+use self::recording::{RecordingWriter, RECORD_INVOCATIONS_ANNOTATION};
+use self::rehydrate::Workloads;
+use self::supervisor::{spawn_provider_process, ProviderSupervisor, RestartPolicy};
 
 const MAX_INVOCATION_CHANNEL_SIZE: usize = 5000;
 const MIN_INVOCATION_CHANNEL_SIZE: usize = 256;
 
+/// Annotation used to set the fuel budget of a single component invocation
+const MAX_FUEL_ANNOTATION: &str = "wasmcloud.dev/max-fuel";
+
+/// Annotation used to set the maximum amount of linear memory, in bytes, of each component instance
+/// or the maximum amount of memory, in bytes, of a provider process
+const MAX_MEMORY_ANNOTATION: &str = "wasmcloud.dev/max-memory";
+
 #[derive(Debug)]
 struct Queue {
     all_streams: SelectAll<async_nats::Subscriber>,
@@ -190,9 +237,14 @@ struct Component {
     id: Arc<str>,
     handler: Handler,
     exports: JoinHandle<()>,
+    /// Endpoint used to invoke this component from other components running on this host
+    local_invocations: mpsc::Sender<LocalInvocation>,
     annotations: Annotations,
     /// Maximum number of instances of this component that can be running at once
     max_instances: NonZeroUsize,
+    /// Maximum amount of linear memory each instance of this component can use, if restricted
+    /// beyond the host limit
+    max_memory: Option<u64>,
     image_reference: Arc<str>,
 }
 
@@ -207,6 +259,7 @@ impl Deref for Component {
 #[derive(Clone)]
 struct WrpcServer {
     nats: wrpc_transport_nats::Client,
+    local: Arc<LocalServer>,
     claims: Option<Arc<jwt::Claims<jwt::Component>>>,
     id: Arc<str>,
     image_reference: Arc<str>,
@@ -218,8 +271,14 @@ struct WrpcServer {
 
 impl wrpc_transport::Serve for WrpcServer {
     type Context = (Instant, Vec<KeyValue>);
-    type Outgoing = <wrpc_transport_nats::Client as wrpc_transport::Serve>::Outgoing;
-    type Incoming = <wrpc_transport_nats::Client as wrpc_transport::Serve>::Incoming;
+    type Outgoing = InvocationStream<
+        <wrpc_transport_nats::Client as wrpc_transport::Serve>::Outgoing,
+        wrpc_transport::frame::Outgoing,
+    >;
+    type Incoming = InvocationStream<
+        <wrpc_transport_nats::Client as wrpc_transport::Serve>::Incoming,
+        wrpc_transport::frame::Incoming,
+    >;
 
     #[instrument(
         level = "info",
@@ -239,7 +298,18 @@ impl wrpc_transport::Serve for WrpcServer {
             + 'static,
     > {
         debug!("serving invocations");
-        let invocations = self.nats.serve(instance, func, paths).await?;
+        let paths = paths.into();
+        let nats = self
+            .nats
+            .serve(instance, func, Arc::clone(&paths))
+            .await?
+            .map_ok(|(cx, tx, rx)| (cx, InvocationStream::Nats(tx), InvocationStream::Nats(rx)));
+        let local = self
+            .local
+            .serve(instance, func, paths)
+            .await?
+            .map_ok(|(cx, tx, rx)| (cx, InvocationStream::Local(tx), InvocationStream::Local(rx)));
+        let invocations = stream::select(nats, local);
 
         let func: Arc<str> = Arc::from(func);
         let instance: Arc<str> = Arc::from(instance);
@@ -343,8 +413,12 @@ struct Provider {
     /// Task that continuously forwards configuration updates to the provider
     config_update_task: JoinHandle<()>,
     /// Config bundle for the aggregated configuration being watched by the provider
-    #[allow(unused)]
     config: Arc<RwLock<ConfigBundle>>,
+    /// Stops the supervision of the provider process when set or dropped, such that the provider
+    /// is not restarted once it exits
+    supervisor_stop: watch::Sender<bool>,
+    /// Number of times the provider process was restarted
+    restarts: Arc<AtomicU32>,
 }
 
 impl Drop for Provider {
@@ -367,14 +441,17 @@ pub struct Host {
     secrets_xkey: Arc<XKey>,
     labels: RwLock<BTreeMap<String, String>>,
     ctl_topic_prefix: String,
-    /// NATS client to use for control interface subscriptions and jetstream queries
+    /// NATS client to use for control interface subscriptions and jetstream queries. Hosts applying
+    /// a local manifest do not connect to a control plane and use the RPC client instead
     ctl_nats: async_nats::Client,
     /// NATS client to use for RPC calls
     rpc_nats: Arc<async_nats::Client>,
-    data: Store,
+    data: KvStore,
     /// Task to watch for changes in the LATTICEDATA store
     data_watch: AbortHandle,
-    config_data: Store,
+    config_data: KvStore,
+    /// Revision history of the named config in `config_data`
+    config_history: ConfigHistory,
     config_generator: BundleGenerator,
     policy_manager: Arc<PolicyManager>,
     secrets_manager: Arc<SecretsManager>,
@@ -392,6 +469,23 @@ pub struct Host {
     provider_claims: Arc<RwLock<HashMap<String, jwt::Claims<jwt::CapabilityProvider>>>>,
     metrics: Arc<HostMetrics>,
     max_execution_time: Duration,
+    /// Components running on this host, which can be invoked without going through NATS
+    local_components: LocalComponents,
+    /// Writer of component invocation recordings, if a destination is configured
+    recording_writer: Option<RecordingWriter>,
+    /// Sender of records to the writer of the audit log of control interface requests, if a
+    /// destination is configured
+    audit_log: Option<mpsc::Sender<AuditRecord>>,
+    /// Whether the initial contents of the lattice data bucket were processed
+    synced: AtomicBool,
+    /// Whether the host is draining
+    draining: watch::Sender<bool>,
+    /// Invocations currently handled by components of this host
+    in_flight: InFlight,
+    /// Workloads this host intends to run, recorded if workload rehydration is enabled
+    workloads: Mutex<Workloads>,
+    /// Public keys trusted to sign OCI artifacts
+    trust_roots: TrustRoots,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -404,6 +498,9 @@ pub struct ComponentSpecification {
     url: String,
     /// All outbound links from this component to other components, used for routing when calling a component `import`
     links: Vec<Link>,
+    /// JSON schema of the config of the component, declared in its claims
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config_schema: Option<serde_json::Value>,
     ////
     // Possible additions in the future, left in as comments to facilitate discussion
     ////
@@ -421,6 +518,7 @@ impl ComponentSpecification {
         Self {
             url: url.as_ref().to_string(),
             links: Vec::new(),
+            config_schema: None,
         }
     }
 }
@@ -754,8 +852,13 @@ impl Host {
             "version": config.version,
         });
 
+        // Hosts applying a local manifest are not controlled via the control interface, so they
+        // neither connect to the control plane nor subscribe to control interface topics
         let ((ctl_nats, queue), rpc_nats) = try_join!(
             async {
+                if config.manifest.is_some() {
+                    return Ok((None, None));
+                }
                 debug!(
                     ctl_nats_url = config.ctl_nats_url.as_str(),
                     "connecting to NATS control server"
@@ -778,7 +881,7 @@ impl Host {
                 .await
                 .context("failed to initialize queue")?;
                 ctl_nats.flush().await.context("failed to flush")?;
-                Ok((ctl_nats, queue))
+                Ok((Some(ctl_nats), Some(queue)))
             },
             async {
                 debug!(
@@ -809,25 +912,68 @@ impl Host {
 
         let (stop_tx, stop_rx) = watch::channel(None);
 
-        let (runtime, _epoch) = Runtime::builder()
+        let mut runtime = Runtime::builder()
             .max_execution_time(config.max_execution_time)
             .max_linear_memory(config.max_linear_memory)
             .max_components(config.max_components)
             .max_component_size(config.max_component_size)
-            .build()
-            .context("failed to build runtime")?;
+            .adapt_core_modules(config.allow_core_modules)
+            .adapt_command_modules(config.adapt_command_modules);
+        if config.fuel_metering {
+            runtime = runtime.fuel_metering();
+        }
+        if let Some(dir) = &config.component_cache_dir {
+            runtime = runtime.component_cache(
+                ComponentCache::new(dir).max_size(config.component_cache_max_size),
+            );
+        }
+        let (runtime, _epoch) = runtime.build().context("failed to build runtime")?;
         let event_builder = EventBuilderV10::new().source(host_key.public_key());
 
+        let ctl_nats = ctl_nats.unwrap_or_else(|| rpc_nats.clone());
         let ctl_jetstream = if let Some(domain) = config.js_domain.as_ref() {
             async_nats::jetstream::with_domain(ctl_nats.clone(), domain)
         } else {
             async_nats::jetstream::new(ctl_nats.clone())
         };
         let bucket = format!("LATTICEDATA_{}", config.lattice);
-        let data = create_bucket(&ctl_jetstream, &bucket).await?;
-
         let config_bucket = format!("CONFIGDATA_{}", config.lattice);
-        let config_data = create_bucket(&ctl_jetstream, &config_bucket).await?;
+        let config_history_bucket = format!("CONFIGHISTORY_{}", config.lattice);
+        // Links and config of hosts applying a local manifest are only kept in memory
+        let (data, config_data, config_history) = if config.manifest.is_some() {
+            (
+                KvStore::memory(bucket),
+                KvStore::memory(config_bucket),
+                KvStore::memory(config_history_bucket),
+            )
+        } else {
+            (
+                create_bucket(&ctl_jetstream, &bucket).await?.into(),
+                create_bucket(&ctl_jetstream, &config_bucket).await?.into(),
+                create_bucket(&ctl_jetstream, &config_history_bucket)
+                    .await?
+                    .into(),
+            )
+        };
+        let config_history = ConfigHistory::new(config_history);
+
+        let recording_writer = if let Some(sink) = &config.invocation_recordings {
+            let writer = RecordingWriter::new(sink, &ctl_jetstream, &config.lattice)
+                .await
+                .context("failed to configure invocation recordings")?;
+            Some(writer)
+        } else {
+            None
+        };
+
+        let audit_log = if let Some(sink) = &config.audit_log {
+            let log = AuditLog::new(sink, &ctl_jetstream, &config.lattice)
+                .await
+                .context("failed to configure audit log")?;
+            Some(log.spawn())
+        } else {
+            None
+        };
 
         let (queue_abort, queue_abort_reg) = AbortHandle::new_pair();
         let (heartbeat_abort, heartbeat_abort_reg) = AbortHandle::new_pair();
@@ -842,6 +988,21 @@ impl Host {
         let registry_config = RwLock::new(supplemental_config.registry_config.unwrap_or_default());
         merge_registry_config(&registry_config, config.oci_opts.clone()).await;
 
+        let meter = global::meter_with_version(
+            "wasmcloud-host",
+            Some(config.version.clone()),
+            None::<&str>,
+            Some(vec![
+                KeyValue::new("host.id", host_key.public_key()),
+                KeyValue::new("host.version", config.version.clone()),
+            ]),
+        );
+        let metrics = Arc::new(HostMetrics::new(
+            &meter,
+            host_key.public_key(),
+            config.lattice.to_string(),
+        ));
+
         let policy_manager = PolicyManager::new(
             ctl_nats.clone(),
             PolicyHostInfo {
@@ -852,6 +1013,8 @@ impl Host {
             config.policy_service_config.policy_topic.clone(),
             config.policy_service_config.policy_timeout_ms,
             config.policy_service_config.policy_changes_topic.clone(),
+            config.policy_service_config.policy_file.clone(),
+            Arc::clone(&metrics),
         )
         .await?;
 
@@ -867,26 +1030,37 @@ impl Host {
         );
 
         let secrets_manager = Arc::new(SecretsManager::new(
-            &config_data,
+            config_data.clone(),
             config.secrets_topic_prefix.as_ref(),
             &ctl_nats,
         ));
 
-        let meter = global::meter_with_version(
-            "wasmcloud-host",
-            Some(config.version.clone()),
-            None::<&str>,
-            Some(vec![
-                KeyValue::new("host.id", host_key.public_key()),
-                KeyValue::new("host.version", config.version.clone()),
-            ]),
-        );
-        let metrics = HostMetrics::new(&meter, host_key.public_key(), config.lattice.to_string());
-
         let config_generator = BundleGenerator::new(config_data.clone());
 
         let max_execution_time_ms = config.max_execution_time;
 
+        let trust_roots = TrustRoots::load(&config.oci_opts.signature_trust_roots)
+            .await
+            .context("failed to load signature trust roots")?;
+
+        let manifest = if let Some(path) = &config.manifest {
+            let manifest = Manifest::load(path)
+                .await
+                .context("failed to load manifest")?;
+            Some((path.clone(), manifest))
+        } else {
+            None
+        };
+
+        let http_admin = if let Some(addr) = config.http_admin {
+            let listener = TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("failed to bind HTTP admin endpoint on `{addr}`"))?;
+            Some(listener)
+        } else {
+            None
+        };
+
         let host = Host {
             components: RwLock::default(),
             event_builder,
@@ -903,6 +1077,7 @@ impl Host {
             data: data.clone(),
             data_watch: data_watch_abort.clone(),
             config_data: config_data.clone(),
+            config_history,
             config_generator,
             policy_manager,
             secrets_manager,
@@ -916,43 +1091,66 @@ impl Host {
             links: RwLock::default(),
             component_claims: Arc::default(),
             provider_claims: Arc::default(),
-            metrics: Arc::new(metrics),
+            metrics,
             max_execution_time: max_execution_time_ms,
+            local_components: LocalComponents::default(),
+            recording_writer,
+            audit_log,
+            synced: AtomicBool::new(false),
+            draining: watch::Sender::new(false),
+            in_flight: InFlight::default(),
+            workloads: Mutex::default(),
+            trust_roots,
         };
 
         let host = Arc::new(host);
-        let queue = spawn({
-            let host = Arc::clone(&host);
-            async move {
-                let mut queue = Abortable::new(queue, queue_abort_reg);
-                queue
-                    .by_ref()
-                    .for_each_concurrent(None, {
-                        let host = Arc::clone(&host);
-                        move |msg| {
+        let (http_admin_abort, http_admin_abort_reg) = AbortHandle::new_pair();
+        let http_admin = http_admin.map(|listener| {
+            spawn({
+                let host = Arc::clone(&host);
+                async move {
+                    info!(addr = ?listener.local_addr(), "serving HTTP admin endpoint");
+                    match Abortable::new(admin::serve(host, listener), http_admin_abort_reg).await {
+                        Ok(Err(err)) => error!(?err, "HTTP admin endpoint unexpectedly stopped"),
+                        Ok(Ok(())) | Err(_) => info!("HTTP admin endpoint gracefully stopped"),
+                    }
+                }
+            })
+        });
+        let queue = queue.map(|queue| {
+            spawn({
+                let host = Arc::clone(&host);
+                async move {
+                    let mut queue = Abortable::new(queue, queue_abort_reg);
+                    queue
+                        .by_ref()
+                        .for_each_concurrent(None, {
                             let host = Arc::clone(&host);
-                            async move { host.handle_ctl_message(msg).await }
-                        }
-                    })
-                    .await;
-                let deadline = { *host.stop_rx.borrow() };
-                host.stop_tx.send_replace(deadline);
-                if queue.is_aborted() {
-                    info!("control interface queue task gracefully stopped");
-                } else {
-                    error!("control interface queue task unexpectedly stopped");
+                            move |msg| {
+                                let host = Arc::clone(&host);
+                                async move { host.handle_ctl_message(msg).await }
+                            }
+                        })
+                        .await;
+                    let deadline = { *host.stop_rx.borrow() };
+                    host.stop_tx.send_replace(deadline);
+                    if queue.is_aborted() {
+                        info!("control interface queue task gracefully stopped");
+                    } else {
+                        error!("control interface queue task unexpectedly stopped");
+                    }
                 }
-            }
+            })
         });
 
+        // Watch the lattice data before processing existing entries, such that no changes are missed
+        let data_watch = data
+            .watch_all()
+            .await
+            .context("failed to watch lattice data bucket")?;
         let data_watch: JoinHandle<anyhow::Result<_>> = spawn({
-            let data = data.clone();
             let host = Arc::clone(&host);
             async move {
-                let data_watch = data
-                    .watch_all()
-                    .await
-                    .context("failed to watch lattice data bucket")?;
                 let mut data_watch = Abortable::new(data_watch, data_watch_abort_reg);
                 data_watch
                     .by_ref()
@@ -1021,15 +1219,18 @@ impl Host {
         });
 
         // Process existing data without emitting events
-        data.keys()
+        let keys = data
+            .keys()
             .await
-            .context("failed to read keys of lattice data bucket")?
-            .map_err(|e| anyhow!(e).context("failed to read lattice data stream"))
-            .try_filter_map(|key| async {
-                data.entry(key)
+            .context("failed to read keys of lattice data bucket")?;
+        let data = &data;
+        stream::iter(keys)
+            .then(|key| async move {
+                data.entry(&key)
                     .await
                     .context("failed to get entry in lattice data bucket")
             })
+            .try_filter_map(|entry| async { Ok(entry) })
             .for_each(|entry| async {
                 match entry {
                     Ok(entry) => host.process_entry(entry, false).await,
@@ -1037,6 +1238,44 @@ impl Host {
                 }
             })
             .await;
+        if host.host_config.rehydrate_workloads {
+            if let Err(err) = host.rehydrate_workloads().await {
+                error!(?err, "failed to rehydrate workloads");
+            }
+        }
+        host.synced.store(true, Ordering::Relaxed);
+
+        let (manifest_abort, manifest_abort_reg) = AbortHandle::new_pair();
+        let manifest = manifest.map(|(path, manifest)| {
+            spawn({
+                let host = Arc::clone(&host);
+                async move {
+                    match Abortable::new(host.watch_manifest(path, manifest), manifest_abort_reg)
+                        .await
+                    {
+                        Ok(Err(err)) => error!(?err, "manifest watcher unexpectedly stopped"),
+                        Ok(Ok(())) | Err(_) => info!("manifest watcher gracefully stopped"),
+                    }
+                }
+            })
+        });
+
+        let (oci_gc_abort, oci_gc_abort_reg) = AbortHandle::new_pair();
+        let oci_gc = host.host_config.oci_opts.cache_max_size.map(|max_size| {
+            spawn({
+                let host = Arc::clone(&host);
+                async move {
+                    let ticks = IntervalStream::new(interval(OCI_CACHE_GC_INTERVAL));
+                    let mut ticks = Abortable::new(ticks, oci_gc_abort_reg);
+                    while ticks.next().await.is_some() {
+                        if let Err(err) = host.collect_oci_cache(max_size).await {
+                            error!(?err, "failed to collect OCI cache garbage");
+                        }
+                    }
+                    info!("OCI cache garbage collector gracefully stopped");
+                }
+            })
+        });
 
         host.publish_event("host_started", start_evt)
             .await
@@ -1050,8 +1289,29 @@ impl Host {
             heartbeat_abort.abort();
             queue_abort.abort();
             data_watch_abort.abort();
+            http_admin_abort.abort();
+            manifest_abort.abort();
+            oci_gc_abort.abort();
             host.policy_manager.policy_changes.abort();
-            let _ = try_join!(queue, data_watch, heartbeat).context("failed to await tasks")?;
+            let _ = try_join!(data_watch, heartbeat).context("failed to await tasks")?;
+            if let Some(queue) = queue {
+                queue
+                    .await
+                    .context("failed to await control interface queue")?;
+            }
+            if let Some(http_admin) = http_admin {
+                http_admin
+                    .await
+                    .context("failed to await HTTP admin endpoint")?;
+            }
+            if let Some(manifest) = manifest {
+                manifest.await.context("failed to await manifest watcher")?;
+            }
+            if let Some(oci_gc) = oci_gc {
+                oci_gc
+                    .await
+                    .context("failed to await OCI cache garbage collector")?;
+            }
             host.publish_event(
                 "host_stopped",
                 json!({
@@ -1111,6 +1371,9 @@ impl Host {
                 {
                     description = description.name(name);
                 };
+                if let Some(max_memory) = component.max_memory {
+                    description = description.max_memory(max_memory);
+                }
 
                 Some(
                     description
@@ -1133,6 +1396,7 @@ impl Host {
                         annotations,
                         claims_token,
                         image_ref,
+                        restarts,
                         ..
                     },
                 )| {
@@ -1160,6 +1424,7 @@ impl Host {
                                 .and_then(|jwt::CapabilityProvider { rev, .. }| *rev)
                                 .unwrap_or_default(),
                         )
+                        .restarts(restarts.load(Ordering::Relaxed))
                         .build()
                         .expect("failed to build provider description")
                 },
@@ -1219,6 +1484,52 @@ impl Host {
         let max_execution_time = self.max_execution_time;
         component.set_max_execution_time(max_execution_time);
 
+        let max_fuel = component_max_fuel(&id, annotations, self.host_config.fuel_metering)?;
+        component.set_max_fuel(max_fuel);
+
+        let max_memory = self.component_max_memory(annotations)?;
+        component.set_max_memory(max_memory);
+
+        let record_invocations = annotations
+            .get(RECORD_INVOCATIONS_ANNOTATION)
+            .map(|record| record.parse())
+            .transpose()
+            .with_context(|| format!("invalid `{RECORD_INVOCATIONS_ANNOTATION}` annotation"))?
+            .unwrap_or_default();
+        let recordings = match (record_invocations, &self.recording_writer) {
+            (true, Some(writer)) => Some(writer.spawn(Arc::clone(&id))),
+            (true, None) => {
+                warn!(
+                    component_id = ?id,
+                    "invocation recording requested for component, but no recording destination is configured on this host"
+                );
+                None
+            }
+            (false, _) => None,
+        };
+        component.set_invocation_recording(recordings);
+
+        let (preopens, cli_environment) = {
+            let config_data = handler.config_data.read().await;
+            let config = config_data.get_config().await;
+            let preopens = preopens::component_preopens(
+                annotations,
+                &config,
+                &self.host_config.allowed_preopen_paths,
+            )
+            .context("failed to configure preopened directories")?;
+            let egress_policy = EgressPolicy::new(annotations, &config)
+                .context("failed to configure egress policy")?;
+            *handler.egress_policy.write().await = egress_policy;
+            (preopens, environment::cli_environment(&config))
+        };
+        component.set_preopens(preopens);
+        let (cli_environment_tx, cli_environment_rx) = watch::channel(cli_environment);
+        component.set_cli_environment(cli_environment_rx);
+        let mut config_bundle = handler.config_data.read().await.clone();
+        let egress_annotations = annotations.clone();
+        let egress_policy = Arc::clone(&handler.egress_policy);
+
         let (events_tx, mut events_rx) = mpsc::channel(
             max_instances
                 .get()
@@ -1231,10 +1542,14 @@ impl Host {
             Some(prefix),
         )
         .await?;
+        let local = Arc::new(LocalServer::default());
+        let (local_invocations, local_invocations_rx) = mpsc::channel(LOCAL_INVOCATION_QUEUE_SIZE);
+        let local_listener = LocalListener::new(local_invocations_rx);
         let exports = component
             .serve_wrpc(
                 &WrpcServer {
                     nats,
+                    local: Arc::clone(&local),
                     claims: component.claims().cloned().map(Arc::new),
                     id: Arc::clone(&id),
                     image_reference: Arc::clone(&image_reference),
@@ -1251,6 +1566,20 @@ impl Host {
             usize::from(max_instances).min(Semaphore::MAX_PERMITS),
         ));
         let metrics = Arc::clone(&self.metrics);
+        metrics.record_component_max_instances(
+            &id,
+            max_instances.get().try_into().unwrap_or(u64::MAX),
+        );
+        let instance_metrics = Arc::clone(&self.metrics);
+        let instance_id = Arc::clone(&id);
+        let in_flight = self.in_flight.clone();
+        let mut draining = self.draining.subscribe();
+        if self.host_config.local_component_invocations {
+            self.local_components
+                .write()
+                .await
+                .insert(Arc::clone(&id), local_invocations.clone());
+        }
         Ok(Arc::new(Component {
             component,
             id,
@@ -1263,15 +1592,25 @@ impl Host {
                             let mut exports = stream::select_all(exports);
                             loop {
                                 let permits = Arc::clone(&permits);
+                                let metrics = Arc::clone(&instance_metrics);
+                                let id = Arc::clone(&instance_id);
                                 select! {
+                                    biased;
+                                    _ = draining.wait_for(|draining| *draining).map_ok(|_| ()) => {
+                                        debug!("host is draining, stop accepting invocations");
+                                        break;
+                                    }
                                     Some(fut) = exports.next() => {
                                         match fut {
                                             Ok(fut) => {
+                                                let in_flight = in_flight.start();
                                                 debug!("accepted invocation, acquiring permit");
                                                 let permit = permits.acquire_owned().await;
                                                 tasks.spawn(async move {
+                                                    let _in_flight = in_flight;
                                                     let _permit = permit;
                                                     debug!("handling invocation");
+                                                    let _active = metrics.start_component_instance(id);
                                                     match fut.await {
                                                         Ok(()) => {
                                                             debug!("successfully handled invocation");
@@ -1296,6 +1635,12 @@ impl Host {
                                     }
                                 }
                             }
+                            drop(exports);
+                            while let Some(res) = tasks.join_next().await {
+                                if let Err(err) = res {
+                                    error!(?err, "export serving task failed");
+                                }
+                            }
                         },
                         async move {
                             while let Some(evt) = events_rx.recv().await {
@@ -1303,35 +1648,96 @@ impl Host {
                                     WrpcServeEvent::HttpIncomingHandlerHandleReturned {
                                         context: (start_at, ref attributes),
                                         success,
+                                        fuel_consumed,
                                     }
                                     | WrpcServeEvent::MessagingHandlerHandleMessageReturned {
                                         context: (start_at, ref attributes),
                                         success,
+                                        fuel_consumed,
                                     }
                                     | WrpcServeEvent::DynamicExportReturned {
                                         context: (start_at, ref attributes),
                                         success,
+                                        fuel_consumed,
                                     } => metrics.record_component_invocation(
                                         u64::try_from(start_at.elapsed().as_nanos())
                                             .unwrap_or_default(),
                                         attributes,
                                         !success,
+                                        fuel_consumed,
                                     ),
                                 }
                             }
                             debug!("serving event stream is done");
                         },
+                        async move {
+                            // Keep `wasi:cli` environment of new instances and the egress policy
+                            // up to date with config
+                            loop {
+                                match config_bundle.changed().await {
+                                    Ok(config) => {
+                                        cli_environment_tx
+                                            .send_replace(environment::cli_environment(&config));
+                                        match EgressPolicy::new(&egress_annotations, &config) {
+                                            Ok(policy) => *egress_policy.write().await = policy,
+                                            Err(err) => warn!(
+                                                ?err,
+                                                "invalid egress policy in updated config, keeping current policy"
+                                            ),
+                                        }
+                                    }
+                                    Err(err) => {
+                                        warn!(?err, "failed to watch component config for changes");
+                                        return;
+                                    }
+                                }
+                            }
+                        },
+                        async move {
+                            // Dispatch invocations from components running on this host
+                            loop {
+                                match local.accept(&local_listener).await {
+                                    Ok(()) => {}
+                                    Err(AcceptError::IO(err))
+                                        if err.kind() == io::ErrorKind::BrokenPipe =>
+                                    {
+                                        return;
+                                    }
+                                    Err(err) => warn!(?err, "failed to accept local invocation"),
+                                }
+                            }
+                        },
                     );
                     debug!("export serving task done");
                 }
                 .in_current_span(),
             ),
+            local_invocations,
             annotations: annotations.clone(),
             max_instances,
+            max_memory,
             image_reference,
         }))
     }
 
+    /// Returns the linear memory limit of a component requested through annotations, which must
+    /// not exceed the limit of this host
+    fn component_max_memory(&self, annotations: &Annotations) -> anyhow::Result<Option<u64>> {
+        let max_memory = annotations
+            .get(MAX_MEMORY_ANNOTATION)
+            .map(|max_memory| max_memory.parse())
+            .transpose()
+            .with_context(|| format!("invalid `{MAX_MEMORY_ANNOTATION}` annotation"))?;
+        if let Some(max_memory) = max_memory {
+            ensure!(
+                max_memory <= self.host_config.max_linear_memory,
+                "requested component memory limit of {max_memory} bytes exceeds the host limit of {} bytes",
+                self.host_config.max_linear_memory
+            );
+        }
+        Ok(max_memory)
+    }
+
     #[allow(clippy::too_many_arguments)]
     #[instrument(level = "debug", skip_all)]
     async fn start_component<'a>(
@@ -1354,10 +1760,14 @@ impl Host {
                 .context("failed to store claims")?;
         }
 
-        let component_spec = self
+        let mut component_spec = self
             .get_component_spec(&component_id)
             .await?
             .unwrap_or_else(|| ComponentSpecification::new(&component_ref));
+        component_spec.config_schema = claims
+            .as_ref()
+            .and_then(|claims| claims.metadata.as_ref())
+            .and_then(|metadata| metadata.config_schema.clone());
         self.store_component_spec(&component_id, &component_spec)
             .await?;
 
@@ -1367,11 +1777,23 @@ impl Host {
             config_data: Arc::new(RwLock::new(config)),
             lattice: Arc::clone(&self.host_config.lattice),
             component_id: Arc::clone(&component_id),
+            host_id: Arc::from(self.host_key.public_key()),
             secrets: Arc::new(RwLock::new(secrets)),
             targets: Arc::default(),
             trace_ctx: Arc::default(),
             instance_links: Arc::new(RwLock::new(component_import_links(&component_spec.links))),
             invocation_timeout: Duration::from_secs(10), // TODO: Make this configurable
+            output_limiter: Arc::new(OutputRateLimiter::new(
+                self.host_config.max_component_output_lines,
+            )),
+            egress_policy: Arc::default(),
+            egress_denied_limiter: Arc::new(OutputRateLimiter::new(MAX_EGRESS_DENIED_EVENTS)),
+            ctl_nats: self.ctl_nats.clone(),
+            event_builder: self.event_builder.clone(),
+            local_components: self
+                .host_config
+                .local_component_invocations
+                .then(|| Arc::clone(&self.local_components)),
         };
         let component = wasmcloud_runtime::Component::new(&self.runtime, &wasm)?;
         let component = self
@@ -1394,6 +1816,7 @@ impl Host {
                 annotations,
                 self.host_key.public_key(),
                 max_instances,
+                component.max_memory,
                 &component_ref,
                 &component_id,
             ),
@@ -1408,6 +1831,13 @@ impl Host {
         trace!(component_id = %component.id, "stopping component");
 
         component.exports.abort();
+        let mut local_components = self.local_components.write().await;
+        if local_components
+            .get(&component.id)
+            .is_some_and(|endpoint| endpoint.same_channel(&component.local_invocations))
+        {
+            local_components.remove(&component.id);
+        }
 
         Ok(())
     }
@@ -1429,6 +1859,14 @@ impl Host {
             ?constraints,
             "handling auction for component"
         );
+        if !self.synced.load(Ordering::Relaxed) {
+            debug!("host is not synchronized yet, skipping component auction");
+            return Ok(None);
+        }
+        if self.is_draining() {
+            debug!("host is draining, skipping component auction");
+            return Ok(None);
+        }
 
         let host_labels = self.labels.read().await;
         let constraints_satisfied = constraints
@@ -1437,18 +1875,27 @@ impl Host {
         let component_id_running = self.components.read().await.contains_key(component_id);
 
         // This host can run the component if all constraints are satisfied and the component is not already running
-        if constraints_satisfied && !component_id_running {
-            Ok(Some(CtlResponse::ok(
-                ComponentAuctionAck::from_component_host_and_constraints(
-                    component_ref,
-                    component_id,
-                    &self.host_key.public_key(),
-                    constraints.clone(),
-                ),
-            )))
-        } else {
-            Ok(None)
+        if !constraints_satisfied || component_id_running {
+            return Ok(None);
+        }
+        let capacity = self.capacity().await;
+        if self.refuses_auction(&capacity) {
+            debug!(
+                ?capacity,
+                "host utilization exceeds threshold, skipping component auction"
+            );
+            return Ok(None);
         }
+        Ok(Some(CtlResponse::ok(
+            ComponentAuctionAck::builder()
+                .component_ref(component_ref.into())
+                .component_id(component_id.into())
+                .host_id(self.host_key.public_key())
+                .constraints(constraints.clone())
+                .capacity(capacity)
+                .build()
+                .map_err(|e| anyhow!("failed to build component auction ack: {e}"))?,
+        )))
     }
 
     #[instrument(level = "debug", skip_all)]
@@ -1468,39 +1915,70 @@ impl Host {
             ?constraints,
             "handling auction for provider"
         );
+        if !self.synced.load(Ordering::Relaxed) {
+            debug!("host is not synchronized yet, skipping provider auction");
+            return Ok(None);
+        }
+        if self.is_draining() {
+            debug!("host is draining, skipping provider auction");
+            return Ok(None);
+        }
 
         let host_labels = self.labels.read().await;
         let constraints_satisfied = constraints
             .iter()
             .all(|(k, v)| host_labels.get(k).is_some_and(|hv| hv == v));
-        let providers = self.providers.read().await;
-        let provider_running = providers.contains_key(provider_id);
-        if constraints_satisfied && !provider_running {
-            Ok(Some(CtlResponse::ok(
-                ProviderAuctionAck::builder()
-                    .provider_ref(provider_ref.into())
-                    .provider_id(provider_id.into())
-                    .constraints(constraints.clone())
-                    .host_id(self.host_key.public_key())
-                    .build()
-                    .map_err(|e| anyhow!("failed to build provider auction ack: {e}"))?,
-            )))
-        } else {
-            Ok(None)
+        let provider_running = self.providers.read().await.contains_key(provider_id);
+        if !constraints_satisfied || provider_running {
+            return Ok(None);
+        }
+        let capacity = self.capacity().await;
+        if self.refuses_auction(&capacity) {
+            debug!(
+                ?capacity,
+                "host utilization exceeds threshold, skipping provider auction"
+            );
+            return Ok(None);
         }
+        Ok(Some(CtlResponse::ok(
+            ProviderAuctionAck::builder()
+                .provider_ref(provider_ref.into())
+                .provider_id(provider_id.into())
+                .constraints(constraints.clone())
+                .host_id(self.host_key.public_key())
+                .capacity(capacity)
+                .build()
+                .map_err(|e| anyhow!("failed to build provider auction ack: {e}"))?,
+        )))
     }
 
     #[instrument(level = "trace", skip_all)]
     async fn fetch_component(&self, component_ref: &str) -> anyhow::Result<Vec<u8>> {
         let registry_config = self.registry_config.read().await;
-        fetch_component(
+        let start_at = Instant::now();
+        let component_ref = verify_signature(
             component_ref,
+            &self.host_config.oci_opts,
+            &self.trust_roots,
+            &registry_config,
+        )
+        .await?;
+        let component = fetch_component(
+            &component_ref,
             self.host_config.allow_file_load,
-            &self.host_config.oci_opts.additional_ca_paths,
+            &self.host_config.oci_opts,
             &registry_config,
         )
         .await
-        .context("failed to fetch component")
+        .context("failed to fetch component")?;
+        if is_oci_reference(&component_ref) {
+            self.metrics.record_oci_fetch(
+                "component",
+                component.len().try_into().unwrap_or(u64::MAX),
+                start_at.elapsed(),
+            );
+        }
+        Ok(component)
     }
 
     #[instrument(level = "trace", skip_all)]
@@ -1513,12 +1991,22 @@ impl Host {
         Ok(())
     }
 
-    #[instrument(level = "debug", skip_all)]
-    async fn handle_stop_host(
+    /// Stops the host, allowing it to shut down until `deadline`
+    fn stop(&self, deadline: Option<Instant>) {
+        self.heartbeat.abort();
+        self.data_watch.abort();
+        self.queue.abort();
+        self.policy_manager.policy_changes.abort();
+        self.stop_tx.send_replace(deadline);
+    }
+
+    /// Returns the timeout in milliseconds of a [`StopHostCommand`] received on behalf of
+    /// `transport_host_id`, validating that it targets this host
+    fn parse_stop_host_command(
         &self,
         payload: impl AsRef<[u8]>,
         transport_host_id: &str,
-    ) -> anyhow::Result<CtlResponse<()>> {
+    ) -> anyhow::Result<Option<u64>> {
         // Allow an empty payload to be used for stopping hosts
         let timeout = if payload.as_ref().is_empty() {
             None
@@ -1544,21 +2032,79 @@ impl Host {
             transport_host_id == self.host_key.public_key(),
             "invalid host_id [{transport_host_id}]"
         );
+        Ok(timeout)
+    }
+
+    /// Evaluates the control operation `request` using the policy manager and returns a failed
+    /// response carrying the denial message, if the operation is denied
+    async fn policy_denial(&self, request: RequestBody) -> anyhow::Result<Option<CtlResponse<()>>> {
+        let kind = request.kind();
+        let PolicyResponse {
+            permitted, message, ..
+        } = self.policy_manager.evaluate_action(request).await?;
+        if permitted {
+            return Ok(None);
+        }
+        let message = match message {
+            Some(message) => format!("policy denied {} request: {message}", kind.as_str()),
+            None => format!("policy denied {} request", kind.as_str()),
+        };
+        warn!(message, "control operation denied by policy");
+        Ok(Some(CtlResponse::error(&message)))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_stop_host(
+        &self,
+        payload: impl AsRef<[u8]>,
+        transport_host_id: &str,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let timeout = self.parse_stop_host_command(payload, transport_host_id)?;
+        if let Some(res) = self
+            .policy_denial(RequestBody::StopHost(StopHostRequest {
+                timeout_ms: timeout,
+                drain: false,
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
 
         info!(?timeout, "handling stop host");
 
-        self.heartbeat.abort();
-        self.data_watch.abort();
-        self.queue.abort();
-        self.policy_manager.policy_changes.abort();
         let deadline =
             timeout.and_then(|timeout| Instant::now().checked_add(Duration::from_millis(timeout)));
-        self.stop_tx.send_replace(deadline);
+        self.stop(deadline);
         Ok(CtlResponse::<()>::success(
             "successfully handled stop host".into(),
         ))
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_drain_host(
+        self: Arc<Self>,
+        payload: impl AsRef<[u8]>,
+        transport_host_id: &str,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let timeout = self.parse_stop_host_command(payload, transport_host_id)?;
+        if let Some(res) = self
+            .policy_denial(RequestBody::StopHost(StopHostRequest {
+                timeout_ms: timeout,
+                drain: true,
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
+
+        info!(?timeout, "handling drain host");
+
+        spawn(async move { self.drain(timeout.map(Duration::from_millis)).await });
+        Ok(CtlResponse::<()>::success(
+            "successfully handled drain host".into(),
+        ))
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_scale_component(
         self: Arc<Self>,
@@ -1567,6 +2113,19 @@ impl Host {
     ) -> anyhow::Result<CtlResponse<()>> {
         let cmd = serde_json::from_slice::<ScaleComponentCommand>(payload.as_ref())
             .context("failed to deserialize component scale command")?;
+        let (res, _task) = self.scale_component(&cmd, host_id).await;
+        Ok(res)
+    }
+
+    /// Scales a component according to `cmd` in a spawned task, which is returned along with the
+    /// response to the command if the component is scaling. The command is recorded in the host
+    /// workloads once the component is scaled successfully
+    #[instrument(level = "debug", skip_all)]
+    async fn scale_component(
+        self: Arc<Self>,
+        cmd: &ScaleComponentCommand,
+        host_id: &str,
+    ) -> (CtlResponse<()>, Option<JoinHandle<()>>) {
         let component_ref = cmd.component_ref();
         let component_id = cmd.component_id();
         let annotations = cmd.annotations();
@@ -1580,11 +2139,17 @@ impl Host {
         );
 
         let host_id = host_id.to_string();
-        let annotations: Annotations = annotations
+        let mut annotations: Annotations = annotations
             .cloned()
             .unwrap_or_default()
             .into_iter()
             .collect();
+        if let Some(max_fuel) = cmd.max_fuel() {
+            annotations.insert(MAX_FUEL_ANNOTATION.into(), max_fuel.to_string());
+        }
+        if let Some(max_memory) = cmd.max_memory() {
+            annotations.insert(MAX_MEMORY_ANNOTATION.into(), max_memory.to_string());
+        }
 
         // Basic validation to ensure that the component is running and that the image reference matches
         // If it doesn't match, we can still successfully scale, but we won't be updating the image reference
@@ -1622,12 +2187,26 @@ impl Host {
             _ => String::with_capacity(0),
         };
 
+        if max_instances > 0 {
+            if let Err(e) = self
+                .validate_config_against_cached_schema(component_id, component_ref, &config)
+                .await
+            {
+                error!(component_ref, component_id, err = ?e, "failed to scale component");
+                return (CtlResponse::error(&format!("{e:#}")), None);
+            }
+        }
+
         let component_id = Arc::from(component_id);
         let component_ref = Arc::from(component_ref);
+        let cmd = cmd.clone();
         // Spawn a task to perform the scaling and possibly an update of the component afterwards
-        spawn(async move {
-            // Fetch the component from the reference
-            let component_and_claims =
+        let task = spawn(async move {
+            // Fetch the component from the reference, unless it is scaled to zero and therefore
+            // only stopped
+            let component_and_claims = if max_instances == 0 {
+                Ok((Vec::default(), Ok(None)))
+            } else {
                 self.fetch_component(&component_ref)
                     .await
                     .map(|component_bytes| {
@@ -1636,10 +2215,27 @@ impl Host {
                         let claims_token =
                             wasmcloud_runtime::component::claims_token(&component_bytes);
                         (component_bytes, claims_token)
-                    });
+                    })
+            };
             let (wasm, claims_token) = match component_and_claims {
                 Ok((wasm, Ok(claims_token))) => (wasm, claims_token),
                 Err(e) | Ok((_, Err(e))) => {
+                    if let Some(err) = e.downcast_ref::<SignatureVerificationError>() {
+                        if let Err(e) = self
+                            .publish_event(
+                                "component_signature_verification_failed",
+                                event::component_signature_verification_failed(
+                                    &host_id,
+                                    &component_ref,
+                                    &component_id,
+                                    err,
+                                ),
+                            )
+                            .await
+                        {
+                            error!(%component_ref, %component_id, err = ?e, "failed to publish component signature verification failed event");
+                        }
+                    }
                     if let Err(e) = self
                         .publish_event(
                             "component_scale_failed",
@@ -1694,6 +2290,7 @@ impl Host {
                 }
                 return;
             }
+            self.record_component_scale(&cmd).await;
 
             if perform_post_update {
                 if let Err(e) = self
@@ -1710,7 +2307,7 @@ impl Host {
             }
         });
 
-        Ok(CtlResponse::<()>::success(message))
+        (CtlResponse::<()>::success(message), Some(task))
     }
 
     #[instrument(level = "debug", skip_all)]
@@ -1730,7 +2327,23 @@ impl Host {
     ) -> anyhow::Result<()> {
         trace!(?component_ref, max_instances, "scale component task");
 
-        let claims = claims_token.map(|c| c.claims.clone());
+        let claims = match claims_token {
+            Some(claims_token) => Some(claims_token.claims.clone()),
+            // Components scaled to zero are not fetched, so the claims of the running component
+            // are used instead
+            None if max_instances == 0 => self
+                .components
+                .read()
+                .await
+                .get(&*component_id)
+                .and_then(|component| component.claims().cloned()),
+            None => None,
+        };
+        let config_schema = claims
+            .as_ref()
+            .and_then(|claims| claims.metadata.as_ref())
+            .and_then(|metadata| metadata.config_schema.as_ref());
+        let max_memory = self.component_max_memory(annotations)?;
         match self
             .policy_manager
             .evaluate_start_component(
@@ -1769,11 +2382,16 @@ impl Host {
                 annotations,
                 host_id,
                 0_usize,
+                max_memory,
                 &component_ref,
                 &component_id,
             ),
             // No component is running and we requested to scale to some amount, start with specified max
             (hash_map::Entry::Vacant(entry), Some(max)) => {
+                if let Some(schema) = config_schema {
+                    self.validate_config_against_schema(&component_id, schema, &config)
+                        .await?;
+                }
                 let (config, secrets) = self
                     .fetch_config_and_secrets(
                         &config,
@@ -1800,6 +2418,7 @@ impl Host {
                     annotations,
                     host_id,
                     max,
+                    max_memory,
                     &component_ref,
                     &component_id,
                 )
@@ -1810,6 +2429,8 @@ impl Host {
                 self.stop_component(&component, host_id)
                     .await
                     .context("failed to stop component in response to scale to zero")?;
+                self.metrics
+                    .record_component_max_instances(&component.id, 0);
 
                 info!(?component_ref, "component stopped");
                 event::component_scaled(
@@ -1817,6 +2438,7 @@ impl Host {
                     &component.annotations,
                     host_id,
                     0_usize,
+                    component.max_memory,
                     &component.image_reference,
                     &component.id,
                 )
@@ -1834,15 +2456,31 @@ impl Host {
                     &component.annotations,
                     host_id,
                     max,
+                    max_memory,
                     &component.image_reference,
                     &component.id,
                 );
 
-                // Modify scale only if the requested max differs from the current max or if the configuration has changed
-                if component.max_instances != max || config_changed {
+                let fuel_changed = component.annotations.get(MAX_FUEL_ANNOTATION)
+                    != annotations.get(MAX_FUEL_ANNOTATION);
+                let recording_changed = component.annotations.get(RECORD_INVOCATIONS_ANNOTATION)
+                    != annotations.get(RECORD_INVOCATIONS_ANNOTATION);
+
+                // Modify scale only if the requested max, memory limit, fuel budget or invocation
+                // recording differs from the current one or if the configuration has changed
+                if component.max_instances != max
+                    || component.max_memory != max_memory
+                    || fuel_changed
+                    || recording_changed
+                    || config_changed
+                {
                     // We must partially clone the handler as we can't be sharing the targets between components
                     let handler = component.handler.copy_for_new();
                     if config_changed {
+                        if let Some(schema) = config_schema {
+                            self.validate_config_against_schema(&component_id, schema, &config)
+                                .await?;
+                        }
                         let (config, secrets) = self
                             .fetch_config_and_secrets(
                                 &config,
@@ -1927,6 +2565,8 @@ impl Host {
             )));
         }
 
+        self.record_component_update(component_id, new_component_ref)
+            .await;
         let host_id = host_id.to_string();
         let message = format!(
             "component {component_id} updating from {component_ref} to {new_component_ref}"
@@ -2005,6 +2645,7 @@ impl Host {
                     &component.annotations,
                     host_id,
                     max,
+                    component.max_memory,
                     new_component_ref,
                     &component_id,
                 ),
@@ -2022,6 +2663,7 @@ impl Host {
                     &component.annotations,
                     host_id,
                     0_usize,
+                    component.max_memory,
                     &component.image_reference,
                     &component.id,
                 ),
@@ -2046,11 +2688,24 @@ impl Host {
     ) -> anyhow::Result<CtlResponse<()>> {
         let cmd = serde_json::from_slice::<StartProviderCommand>(payload.as_ref())
             .context("failed to deserialize provider start command")?;
+        let (res, _task) = self.start_provider(cmd, host_id).await;
+        Ok(res)
+    }
 
+    /// Starts a provider according to `cmd` in a spawned task, which is returned along with the
+    /// response to the command if the provider is starting. The command is recorded in the host
+    /// workloads once the provider is started successfully
+    #[instrument(level = "debug", skip_all)]
+    async fn start_provider(
+        self: Arc<Self>,
+        cmd: StartProviderCommand,
+        host_id: &str,
+    ) -> (CtlResponse<()>, Option<JoinHandle<()>>) {
         if self.providers.read().await.contains_key(cmd.provider_id()) {
-            return Ok(CtlResponse::error(
-                "provider with that ID is already running",
-            ));
+            return (
+                CtlResponse::error("provider with that ID is already running"),
+                None,
+            );
         }
 
         // NOTE: We log at info since starting providers can take a while
@@ -2060,8 +2715,25 @@ impl Host {
             "handling start provider"
         );
 
+        if let Err(err) = self
+            .validate_config_against_cached_schema(
+                cmd.provider_id(),
+                cmd.provider_ref(),
+                cmd.config(),
+            )
+            .await
+        {
+            error!(
+                provider_ref = cmd.provider_ref(),
+                provider_id = cmd.provider_id(),
+                ?err,
+                "failed to start provider"
+            );
+            return (CtlResponse::error(&format!("{err:#}")), None);
+        }
+
         let host_id = host_id.to_string();
-        spawn(async move {
+        let task = spawn(async move {
             let config = cmd.config();
             let provider_id = cmd.provider_id();
             let provider_ref = cmd.provider_ref();
@@ -2078,6 +2750,25 @@ impl Host {
                 .await
             {
                 error!(provider_ref, provider_id, ?err, "failed to start provider");
+                if let Some(sig_err) = err.downcast_ref::<SignatureVerificationError>() {
+                    if let Err(err) = self
+                        .publish_event(
+                            "provider_signature_verification_failed",
+                            event::provider_signature_verification_failed(
+                                &host_id,
+                                provider_ref,
+                                provider_id,
+                                sig_err,
+                            ),
+                        )
+                        .await
+                    {
+                        error!(
+                            ?err,
+                            "failed to publish provider_signature_verification_failed event"
+                        );
+                    }
+                }
                 if let Err(err) = self
                     .publish_event(
                         "provider_start_failed",
@@ -2087,17 +2778,20 @@ impl Host {
                 {
                     error!(?err, "failed to publish provider_start_failed event");
                 }
+                return;
             }
+            self.record_provider_start(cmd).await;
         });
-        Ok(CtlResponse::<()>::success(
-            "successfully started provider".into(),
-        ))
+        (
+            CtlResponse::<()>::success("successfully started provider".into()),
+            Some(task),
+        )
     }
 
     #[instrument(level = "debug", skip_all)]
     async fn handle_start_provider_task(
-        &self,
-        config: &[String],
+        self: &Arc<Self>,
+        config_names: &[String],
         provider_id: &str,
         provider_ref: &str,
         annotations: BTreeMap<String, String>,
@@ -2106,14 +2800,31 @@ impl Host {
         trace!(provider_ref, provider_id, "start provider task");
 
         let registry_config = self.registry_config.read().await;
-        let (path, claims_token) = crate::fetch_provider(
+        let start_at = Instant::now();
+        let verified_ref = verify_signature(
             provider_ref,
+            &self.host_config.oci_opts,
+            &self.trust_roots,
+            &registry_config,
+        )
+        .await?;
+        let (path, claims_token) = crate::fetch_provider(
+            &verified_ref,
             host_id,
             self.host_config.allow_file_load,
+            &self.host_config.oci_opts,
             &registry_config,
         )
         .await
         .context("failed to fetch provider")?;
+        if is_oci_reference(provider_ref) {
+            let size = fs::metadata(&path)
+                .await
+                .map(|m| m.len())
+                .unwrap_or_default();
+            self.metrics
+                .record_oci_fetch("provider", size, start_at.elapsed());
+        }
         let claims = claims_token.as_ref().map(|t| t.claims.clone());
 
         if let Some(claims) = claims.clone() {
@@ -2137,204 +2848,96 @@ impl Host {
             "policy denied request to start provider `{request_id}`: `{message:?}`",
         );
 
-        let component_specification = self
+        let config_schema = claims
+            .as_ref()
+            .and_then(|claims| claims.metadata.as_ref())
+            .and_then(|metadata| metadata.config_schema.clone());
+        if let Some(schema) = &config_schema {
+            self.validate_config_against_schema(provider_id, schema, config_names)
+                .await?;
+        }
+
+        let mut component_specification = self
             .get_component_spec(provider_id)
             .await?
             .unwrap_or_else(|| ComponentSpecification::new(provider_ref));
+        component_specification.config_schema = config_schema;
 
         self.store_component_spec(&provider_id, &component_specification)
             .await?;
 
         let (config, secrets) = self
             .fetch_config_and_secrets(
-                config,
+                config_names,
                 claims_token.as_ref().map(|t| &t.jwt),
                 annotations.get("wasmcloud.dev/appspec"),
             )
             .await?;
 
+        let restart_policy = RestartPolicy::new(&self.host_config, &annotations)?;
+
         let mut providers = self.providers.write().await;
         if let hash_map::Entry::Vacant(entry) = providers.entry(provider_id.into()) {
-            let lattice_rpc_user_seed = self
-                .host_config
-                .rpc_key
-                .as_ref()
-                .map(|key| key.seed())
-                .transpose()
-                .context("private key missing for provider RPC key")?;
-            let default_rpc_timeout_ms = Some(
-                self.host_config
-                    .rpc_timeout
-                    .as_millis()
-                    .try_into()
-                    .context("failed to convert rpc_timeout to u64")?,
-            );
-            let otel_config = OtelConfig {
-                enable_observability: self.host_config.otel_config.enable_observability,
-                enable_traces: self.host_config.otel_config.enable_traces,
-                enable_metrics: self.host_config.otel_config.enable_metrics,
-                enable_logs: self.host_config.otel_config.enable_logs,
-                observability_endpoint: self.host_config.otel_config.observability_endpoint.clone(),
-                traces_endpoint: self.host_config.otel_config.traces_endpoint.clone(),
-                metrics_endpoint: self.host_config.otel_config.metrics_endpoint.clone(),
-                logs_endpoint: self.host_config.otel_config.logs_endpoint.clone(),
-                protocol: self.host_config.otel_config.protocol,
-                additional_ca_paths: self.host_config.otel_config.additional_ca_paths.clone(),
-                trace_level: self.host_config.otel_config.trace_level.clone(),
-            };
-
             let provider_xkey = XKey::new();
             // The provider itself needs to know its private key
-            let provider_xkey_private_key = if let Ok(seed) = provider_xkey.seed() {
-                seed
-            } else if self.host_config.secrets_topic_prefix.is_none() {
-                "".to_string()
-            } else {
-                // This should never happen since this returns an error when an Xkey is
-                // created from a public key, but if we can't generate one for whatever
-                // reason, we should bail.
-                bail!("failed to generate seed for provider xkey")
-            };
-            // We only need to store the public key of the provider xkey, as the private key is only needed by the provider
-            let xkey = XKey::from_public_key(&provider_xkey.public_key())
-                .context("failed to create XKey from provider public key xkey")?;
-
-            // Prepare startup links by generating the source and target configs. Note that because the provider may be the source
-            // or target of a link, we need to iterate over all links to find the ones that involve the provider.
-            let all_links = self.links.read().await;
-            let provider_links = all_links
-                .values()
-                .flatten()
-                .filter(|link| link.source_id() == provider_id || link.target() == provider_id);
-            let link_definitions = stream::iter(provider_links)
-                .filter_map(|link| async {
-                    if link.source_id() == provider_id || link.target() == provider_id {
-                        match self
-                            .resolve_link_config(
-                                link.clone(),
-                                claims_token.as_ref().map(|t| &t.jwt),
-                                annotations.get("wasmcloud.dev/appspec"),
-                                &xkey,
-                            )
-                            .await
-                        {
-                            Ok(provider_link) => Some(provider_link),
-                            Err(e) => {
-                                error!(
-                                    error = ?e,
-                                    provider_id,
-                                    source_id = link.source_id(),
-                                    target = link.target(),
-                                    "failed to resolve link config, skipping link"
-                                );
-                                None
-                            }
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<wasmcloud_core::InterfaceLinkDefinition>>()
-                .await;
-
-            let secrets = {
-                // NOTE(brooksmtownsend): This trait import is used here to ensure we're only exposing secret
-                // values when we need them.
-                use secrecy::ExposeSecret;
-                secrets
-                    .iter()
-                    .map(|(k, v)| match v.expose_secret() {
-                        SecretValue::String(s) => (
-                            k.clone(),
-                            wasmcloud_core::secrets::SecretValue::String(s.to_owned()),
-                        ),
-                        SecretValue::Bytes(b) => (
-                            k.clone(),
-                            wasmcloud_core::secrets::SecretValue::Bytes(b.to_owned()),
-                        ),
-                    })
-                    .collect()
-            };
-
-            let host_data = HostData {
-                host_id: self.host_key.public_key(),
-                lattice_rpc_prefix: self.host_config.lattice.to_string(),
-                link_name: "default".to_string(),
-                lattice_rpc_user_jwt: self.host_config.rpc_jwt.clone().unwrap_or_default(),
-                lattice_rpc_user_seed: lattice_rpc_user_seed.unwrap_or_default(),
-                lattice_rpc_url: self.host_config.rpc_nats_url.to_string(),
-                env_values: vec![],
-                instance_id: Uuid::new_v4().to_string(),
-                provider_key: provider_id.to_string(),
-                link_definitions,
-                config: config.get_config().await.clone(),
-                secrets,
-                provider_xkey_private_key,
-                host_xkey_public_key: self.secrets_xkey.public_key(),
-                cluster_issuers: vec![],
-                default_rpc_timeout_ms,
-                log_level: Some(self.host_config.log_level.clone()),
-                structured_logging: self.host_config.enable_structured_logging,
-                otel_config,
+            let provider_xkey_private_key = if let Ok(seed) = provider_xkey.seed() {
+                seed
+            } else if self.host_config.secrets_topic_prefix.is_none() {
+                "".to_string()
+            } else {
+                // This should never happen since this returns an error when an Xkey is
+                // created from a public key, but if we can't generate one for whatever
+                // reason, we should bail.
+                bail!("failed to generate seed for provider xkey")
             };
-            let host_data =
-                serde_json::to_vec(&host_data).context("failed to serialize provider data")?;
-
-            trace!("spawn provider process");
+            // We only need to store the public key of the provider xkey, as the private key is only needed by the provider
+            let xkey = XKey::from_public_key(&provider_xkey.public_key())
+                .context("failed to create XKey from provider public key xkey")?;
 
-            let mut child_cmd = process::Command::new(&path);
-            // Prevent the provider from inheriting the host's environment, with the exception of
-            // the following variables we manually add back
-            child_cmd.env_clear();
-
-            if cfg!(windows) {
-                // Proxy SYSTEMROOT to providers. Without this, providers on Windows won't be able to start
-                child_cmd.env(
-                    "SYSTEMROOT",
-                    env::var("SYSTEMROOT")
-                        .context("SYSTEMROOT is not set. Providers cannot be started")?,
-                );
-            }
+            let host_data = self
+                .provider_host_data(
+                    provider_id,
+                    claims_token.as_ref().map(|t| &t.jwt),
+                    &annotations,
+                    config.get_config().await.clone(),
+                    &secrets,
+                    &provider_xkey_private_key,
+                    &xkey,
+                )
+                .await?;
 
-            // Proxy RUST_LOG to (Rust) providers, so they can use the same module-level directives
-            if let Ok(rust_log) = env::var("RUST_LOG") {
-                let _ = child_cmd.env("RUST_LOG", rust_log);
-            }
+            // The limiter is only constructed once the provider is known not to be running, since
+            // it applies the limits to, and on drop removes, the cgroup of the provider ID
+            let limiter =
+                ProviderLimiter::new(&self.host_config, provider_id, &annotations).await?;
 
-            let mut child = child_cmd
-                .stdin(Stdio::piped())
-                .kill_on_drop(true)
-                .spawn()
-                .context("failed to spawn provider process")?;
-            let mut stdin = child.stdin.take().context("failed to take stdin")?;
-            stdin
-                .write_all(STANDARD.encode(&host_data).as_bytes())
-                .await
-                .context("failed to write provider data")?;
-            stdin
-                .write_all(b"\r\n")
-                .await
-                .context("failed to write newline")?;
-            stdin.shutdown().await.context("failed to close stdin")?;
+            trace!("spawn provider process");
+            let child = spawn_provider_process(&path, &host_data, &limiter).await?;
 
-            // Create a channel for watching for child process exit
+            // Create a channel for watching for child process exit, which is notified once the
+            // provider is not running and will not be restarted anymore
             let (exit_tx, exit_rx) = broadcast::channel::<()>(1);
-            spawn(async move {
-                match child.wait().await {
-                    Ok(status) => {
-                        debug!("provider @ [{}] exited with `{status:?}`", path.display());
-                    }
-                    Err(e) => {
-                        error!(
-                            "failed to wait for provider @ [{}] to execute: {e}",
-                            path.display()
-                        );
-                    }
-                }
-                if let Err(err) = exit_tx.send(()) {
-                    warn!(%err, "failed to send exit tx");
+            let (supervisor_stop, stop_rx) = watch::channel(false);
+            let restarts = Arc::new(AtomicU32::new(0));
+            spawn(
+                ProviderSupervisor {
+                    host: Arc::downgrade(self),
+                    host_id: host_id.to_string(),
+                    provider_id: provider_id.to_string(),
+                    path,
+                    config_names: config_names.to_vec(),
+                    claims_token: claims_token.clone(),
+                    annotations: annotations.clone(),
+                    provider_xkey_private_key,
+                    xkey: xkey.clone(),
+                    policy: restart_policy,
+                    limiter,
+                    metrics: Arc::clone(&self.metrics),
+                    restarts: Arc::clone(&restarts),
                 }
-            });
+                .run(child, stop_rx, exit_tx)
+                .in_current_span(),
+            );
             let mut exit_health_rx = exit_rx.resubscribe();
 
             // TODO: Change method receiver to Arc<Self> and `move` into the closure
@@ -2502,6 +3105,8 @@ impl Host {
             entry.insert(Provider {
                 health_check_task,
                 config_update_task,
+                supervisor_stop,
+                restarts,
                 annotations,
                 claims_token,
                 image_ref: provider_ref.to_string(),
@@ -2515,6 +3120,128 @@ impl Host {
         Ok(())
     }
 
+    /// Returns the serialized [`HostData`] passed to the provider process on startup, containing
+    /// all links the provider is the source or target of
+    #[allow(clippy::too_many_arguments)]
+    async fn provider_host_data(
+        &self,
+        provider_id: &str,
+        claims_jwt: Option<&String>,
+        annotations: &Annotations,
+        config: HashMap<String, String>,
+        secrets: &HashMap<String, Secret<SecretValue>>,
+        provider_xkey_private_key: &str,
+        xkey: &XKey,
+    ) -> anyhow::Result<Vec<u8>> {
+        let lattice_rpc_user_seed = self
+            .host_config
+            .rpc_key
+            .as_ref()
+            .map(|key| key.seed())
+            .transpose()
+            .context("private key missing for provider RPC key")?;
+        let default_rpc_timeout_ms = Some(
+            self.host_config
+                .rpc_timeout
+                .as_millis()
+                .try_into()
+                .context("failed to convert rpc_timeout to u64")?,
+        );
+        let otel_config = OtelConfig {
+            enable_observability: self.host_config.otel_config.enable_observability,
+            enable_traces: self.host_config.otel_config.enable_traces,
+            enable_metrics: self.host_config.otel_config.enable_metrics,
+            enable_logs: self.host_config.otel_config.enable_logs,
+            observability_endpoint: self.host_config.otel_config.observability_endpoint.clone(),
+            traces_endpoint: self.host_config.otel_config.traces_endpoint.clone(),
+            metrics_endpoint: self.host_config.otel_config.metrics_endpoint.clone(),
+            logs_endpoint: self.host_config.otel_config.logs_endpoint.clone(),
+            protocol: self.host_config.otel_config.protocol,
+            additional_ca_paths: self.host_config.otel_config.additional_ca_paths.clone(),
+            trace_level: self.host_config.otel_config.trace_level.clone(),
+        };
+
+        // Prepare startup links by generating the source and target configs. Note that because the provider may be the source
+        // or target of a link, we need to iterate over all links to find the ones that involve the provider.
+        let all_links = self.links.read().await;
+        let provider_links = all_links
+            .values()
+            .flatten()
+            .filter(|link| link.source_id() == provider_id || link.target() == provider_id);
+        let link_definitions = stream::iter(provider_links)
+            .filter_map(|link| async {
+                if link.source_id() == provider_id || link.target() == provider_id {
+                    match self
+                        .resolve_link_config(
+                            link.clone(),
+                            claims_jwt,
+                            annotations.get("wasmcloud.dev/appspec"),
+                            xkey,
+                        )
+                        .await
+                    {
+                        Ok(provider_link) => Some(provider_link),
+                        Err(e) => {
+                            error!(
+                                error = ?e,
+                                provider_id,
+                                source_id = link.source_id(),
+                                target = link.target(),
+                                "failed to resolve link config, skipping link"
+                            );
+                            None
+                        }
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<wasmcloud_core::InterfaceLinkDefinition>>()
+            .await;
+
+        let secrets = {
+            // NOTE(brooksmtownsend): This trait import is used here to ensure we're only exposing secret
+            // values when we need them.
+            use secrecy::ExposeSecret;
+            secrets
+                .iter()
+                .map(|(k, v)| match v.expose_secret() {
+                    SecretValue::String(s) => (
+                        k.clone(),
+                        wasmcloud_core::secrets::SecretValue::String(s.to_owned()),
+                    ),
+                    SecretValue::Bytes(b) => (
+                        k.clone(),
+                        wasmcloud_core::secrets::SecretValue::Bytes(b.to_owned()),
+                    ),
+                })
+                .collect()
+        };
+
+        let host_data = HostData {
+            host_id: self.host_key.public_key(),
+            lattice_rpc_prefix: self.host_config.lattice.to_string(),
+            link_name: "default".to_string(),
+            lattice_rpc_user_jwt: self.host_config.rpc_jwt.clone().unwrap_or_default(),
+            lattice_rpc_user_seed: lattice_rpc_user_seed.unwrap_or_default(),
+            lattice_rpc_url: self.host_config.rpc_nats_url.to_string(),
+            env_values: vec![],
+            instance_id: Uuid::new_v4().to_string(),
+            provider_key: provider_id.to_string(),
+            link_definitions,
+            config,
+            secrets,
+            provider_xkey_private_key: provider_xkey_private_key.to_string(),
+            host_xkey_public_key: self.secrets_xkey.public_key(),
+            cluster_issuers: vec![],
+            default_rpc_timeout_ms,
+            log_level: Some(self.host_config.log_level.clone()),
+            structured_logging: self.host_config.enable_structured_logging,
+            otel_config,
+        };
+        serde_json::to_vec(&host_data).context("failed to serialize provider data")
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_stop_provider(
         &self,
@@ -2535,16 +3262,44 @@ impl Host {
             );
             return Ok(CtlResponse::error("provider with that ID is not running"));
         };
+        let provider = entry.remove();
+        self.record_provider_stop(provider_id).await;
+        // Make sure the provider is not restarted once it shuts down
+        provider.supervisor_stop.send_replace(true);
         let Provider {
             ref annotations, ..
-        } = entry.remove();
+        } = provider;
+
+        self.request_provider_shutdown(
+            provider_id,
+            host_id,
+            self.host_config.provider_shutdown_delay,
+        )
+        .await?;
+        info!(provider_id, "provider stopped");
+        self.publish_event(
+            "provider_stopped",
+            event::provider_stopped(annotations, host_id, provider_id, "stop", None),
+        )
+        .await?;
+        Ok(CtlResponse::<()>::success(
+            "successfully stopped provider".into(),
+        ))
+    }
 
-        // Send a request to the provider, requesting a graceful shutdown
+    /// Sends a request to the provider, requesting a graceful shutdown, and waits for up to
+    /// `timeout` for the provider to acknowledge it
+    async fn request_provider_shutdown(
+        &self,
+        provider_id: &str,
+        host_id: &str,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<()> {
         let req = serde_json::to_vec(&json!({ "host_id": host_id }))
             .context("failed to encode provider stop request")?;
         let req = async_nats::Request::new()
             .payload(req.into())
-            .timeout(self.host_config.provider_shutdown_delay)
+            .timeout(timeout)
             .headers(injector_to_headers(
                 &TraceContextInjector::default_with_span(),
             ));
@@ -2565,15 +3320,7 @@ impl Host {
                 "provider did not gracefully shut down in time, shutting down forcefully"
             );
         }
-        info!(provider_id, "provider stopped");
-        self.publish_event(
-            "provider_stopped",
-            event::provider_stopped(annotations, host_id, provider_id, "stop"),
-        )
-        .await?;
-        Ok(CtlResponse::<()>::success(
-            "successfully stopped provider".into(),
-        ))
+        Ok(())
     }
 
     #[instrument(level = "debug", skip_all)]
@@ -2637,6 +3384,15 @@ impl Host {
             .context("failed to deserialize put label request")?;
         let key = host_label.key();
         let value = host_label.value();
+        if let Some(res) = self
+            .policy_denial(RequestBody::PutLabel(LabelRequest {
+                key: key.into(),
+                value: Some(value.into()),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
         let mut labels = self.labels.write().await;
         match labels.entry(key.into()) {
             BTreeMapEntry::Occupied(mut entry) => {
@@ -2668,6 +3424,15 @@ impl Host {
         let label = serde_json::from_slice::<HostLabel>(payload.as_ref())
             .context("failed to deserialize delete label request")?;
         let key = label.key();
+        if let Some(res) = self
+            .policy_denial(RequestBody::DeleteLabel(LabelRequest {
+                key: key.into(),
+                value: None,
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
         let mut labels = self.labels.write().await;
         let value = labels.remove(key);
 
@@ -2699,6 +3464,21 @@ impl Host {
         let payload = payload.as_ref();
         let link: Link = serde_json::from_slice(payload)
             .context("failed to deserialize wrpc link definition")?;
+        if let Some(res) = self
+            .policy_denial(RequestBody::PutLink(PutLinkRequest {
+                source_id: link.source_id().into(),
+                target: link.target().into(),
+                name: link.name().into(),
+                wit_namespace: link.wit_namespace().into(),
+                wit_package: link.wit_package().into(),
+                interfaces: link.interfaces().clone(),
+                source_config: link.source_config().clone(),
+                target_config: link.target_config().clone(),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
 
         let link_set_result: anyhow::Result<()> = async {
             let source_id = link.source_id();
@@ -2727,6 +3507,31 @@ impl Host {
                     .chain(link.target_config())
             ).await?;
 
+            // Validate the link configurations against the config schemas of either end, if declared.
+            // Link config is merged with the config of an end running on this host, otherwise only
+            // the properties it sets can be validated
+            for (id, config_names) in [(source_id, link.source_config()), (target, link.target_config())] {
+                let Some(schema) = self
+                    .get_component_spec(id)
+                    .await?
+                    .and_then(|spec| spec.config_schema)
+                else {
+                    continue;
+                };
+                if let Some(mut names) = self.running_config_names(id).await {
+                    names.extend(config_names.iter().cloned());
+                    self.validate_config_against_schema(id, &schema, &names).await?;
+                } else {
+                    let config = self.merged_config_values(config_names).await?;
+                    let violations = validate_config_properties(&schema, &config)
+                        .with_context(|| format!("failed to validate link config of `{id}`"))?;
+                    ensure!(
+                        violations.is_empty(),
+                        format_config_violations(id, &violations)
+                    );
+                }
+            }
+
             let mut component_spec = self
                 .get_component_spec(source_id)
                 .await?
@@ -2800,6 +3605,17 @@ impl Host {
         let wit_namespace = req.wit_namespace();
         let wit_package = req.wit_package();
         let link_name = req.link_name();
+        if let Some(res) = self
+            .policy_denial(RequestBody::DeleteLink(DeleteLinkRequest {
+                source_id: source_id.into(),
+                name: link_name.into(),
+                wit_namespace: wit_namespace.into(),
+                wit_package: wit_package.into(),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
 
         let ns_and_package = format!("{wit_namespace}:{wit_package}");
 
@@ -2873,6 +3689,14 @@ impl Host {
         let registry_creds: HashMap<String, RegistryCredential> =
             serde_json::from_slice(payload.as_ref())
                 .context("failed to deserialize registries put command")?;
+        if let Some(res) = self
+            .policy_denial(RequestBody::PutRegistries(PutRegistriesRequest {
+                registries: registry_creds.keys().cloned().collect(),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
 
         info!(
             registries = ?registry_creds.keys(),
@@ -2903,40 +3727,193 @@ impl Host {
         &self,
         config_name: &str,
         data: Bytes,
+        author: Option<String>,
     ) -> anyhow::Result<CtlResponse<()>> {
         debug!("handle config entry put");
         // Validate that the data is of the proper type by deserialing it
-        serde_json::from_slice::<HashMap<String, String>>(&data)
+        let values = serde_json::from_slice::<BTreeMap<String, String>>(&data)
             .context("config data should be a map of string -> string")?;
-        self.config_data
+        if let Some(res) = self
+            .policy_denial(RequestBody::PutConfig(ConfigRequest {
+                name: config_name.into(),
+                values: values.clone(),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
+        let revision = self
+            .config_data
             .put(config_name, data)
             .await
             .context("unable to store config data")?;
+        let recorded = self
+            .record_config_revision(config_name, revision, Some(values), author, None)
+            .await;
         // We don't write it into the cached data and instead let the caching thread handle it as we
         // won't need it immediately.
         self.publish_event("config_set", event::config_set(config_name))
             .await?;
+        recorded?;
 
         Ok(CtlResponse::<()>::success("successfully put config".into()))
     }
 
     #[instrument(level = "debug", skip_all, fields(%config_name))]
-    async fn handle_config_delete(&self, config_name: &str) -> anyhow::Result<CtlResponse<()>> {
+    async fn handle_config_delete(
+        &self,
+        config_name: &str,
+        author: Option<String>,
+    ) -> anyhow::Result<CtlResponse<()>> {
         debug!("handle config entry deletion");
+        if let Some(res) = self
+            .policy_denial(RequestBody::DeleteConfig(ConfigRequest {
+                name: config_name.into(),
+                values: BTreeMap::default(),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
 
+        let current = self
+            .config_data
+            .entry(config_name)
+            .await
+            .context("unable to get config data")?;
         self.config_data
             .purge(config_name)
             .await
             .context("Unable to delete config data")?;
+        // The purge does not return its revision, so it is looked up. If the config was
+        // concurrently put again, the deletion is recorded right after the revision it deleted,
+        // which orders it before the concurrent put
+        let last = current.map_or(0, |entry| entry.revision);
+        let revision = match self
+            .config_data
+            .entry(config_name)
+            .await
+            .context("unable to get config data")?
+        {
+            Some(entry) if entry.operation != Operation::Put => entry.revision,
+            _ => last.saturating_add(1),
+        };
+        let recorded = self
+            .record_config_revision(config_name, revision, None, author, None)
+            .await;
 
         self.publish_event("config_deleted", event::config_deleted(config_name))
             .await?;
+        recorded?;
 
         Ok(CtlResponse::<()>::success(
             "successfully deleted config".into(),
         ))
     }
 
+    #[instrument(level = "debug", skip_all, fields(%config_name))]
+    async fn handle_config_history(&self, config_name: &str) -> anyhow::Result<Vec<u8>> {
+        trace!("handling get config history");
+        // The history contains past values of the config, so access to it is subject to policy
+        if let Some(res) = self
+            .policy_denial(RequestBody::GetConfigHistory(ConfigRequest {
+                name: config_name.into(),
+                values: BTreeMap::default(),
+            }))
+            .await?
+        {
+            return serde_json::to_vec(&res).map_err(anyhow::Error::from);
+        }
+        let history = self.config_history.get(config_name).await?;
+        serde_json::to_vec(&CtlResponse::ok(history)).map_err(anyhow::Error::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%config_name))]
+    async fn handle_config_rollback(
+        &self,
+        config_name: &str,
+        payload: impl AsRef<[u8]>,
+        author: Option<String>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let request = serde_json::from_slice::<ConfigRollbackRequest>(payload.as_ref())
+            .context("failed to deserialize config rollback request")?;
+        let revision = request.revision();
+        debug!(revision, "handle config rollback");
+        let history = self.config_history.get(config_name).await?;
+        let Some(target) = history.iter().find(|r| r.revision() == revision) else {
+            return Ok(CtlResponse::error(&format!(
+                "revision {revision} of config `{config_name}` not found"
+            )));
+        };
+        let Some(values) = target.values().cloned() else {
+            return Ok(CtlResponse::error(&format!(
+                "revision {revision} deleted config `{config_name}`, delete the config instead"
+            )));
+        };
+        if let Some(res) = self
+            .policy_denial(RequestBody::PutConfig(ConfigRequest {
+                name: config_name.into(),
+                values: values.clone(),
+            }))
+            .await?
+        {
+            return Ok(res);
+        }
+
+        // Replace the current values in a single write, which fails if they are concurrently
+        // changed, so that config watchers observe the rollback at once
+        let current = self
+            .config_data
+            .entry(config_name)
+            .await
+            .context("unable to get config data")?;
+        let data = serde_json::to_vec(&values).context("failed to serialize config data")?;
+        let updated = self
+            .config_data
+            .update(
+                config_name,
+                data.into(),
+                current.map_or(0, |entry| entry.revision),
+            )
+            .await
+            .context("unable to store config data")?;
+        let Some(updated) = updated else {
+            return Ok(CtlResponse::error(&format!(
+                "config `{config_name}` was changed during rollback, retry the rollback"
+            )));
+        };
+        let recorded = self
+            .record_config_revision(config_name, updated, Some(values), author, Some(revision))
+            .await;
+        self.publish_event("config_set", event::config_set(config_name))
+            .await?;
+        recorded?;
+
+        Ok(CtlResponse::<()>::success(format!(
+            "successfully rolled back config to revision {revision}"
+        )))
+    }
+
+    /// Records the change of the named config written with `revision` in its history. The change
+    /// has already been applied at this point, so the error returned on failure tells the caller
+    /// that it may retry the change to record it
+    async fn record_config_revision(
+        &self,
+        config_name: &str,
+        revision: u64,
+        values: Option<BTreeMap<String, String>>,
+        author: Option<String>,
+        rollback_of: Option<u64>,
+    ) -> anyhow::Result<()> {
+        self.config_history
+            .record(config_name, revision, values, author, rollback_of)
+            .await
+            .inspect_err(|err| error!(%config_name, ?err, "failed to record config revision"))
+            .with_context(|| {
+                format!("config `{config_name}` was changed, but the change could not be recorded in its history, retry the change")
+            })
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_ping_hosts(
         &self,
@@ -2983,6 +3960,13 @@ impl Host {
             .split('.')
             .skip(2);
         trace!(%subject, "handling control interface request");
+        let operation = parts.clone().take(2).collect::<Vec<_>>().join(".");
+        self.metrics.record_ctl_request(operation.clone());
+        // Payloads are reference-counted, so this does not copy them
+        let audited = self
+            .audit_log
+            .as_ref()
+            .map(|_| (message.payload.clone(), message.headers.clone()));
 
         // This response is a wrapped Result<Option<Result<Vec<u8>>>> for a good reason.
         // The outer Result is for reporting protocol errors in handling the request, e.g. failing to
@@ -3039,6 +4023,11 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            (Some("host"), Some("drain"), Some(host_id), None) => Arc::clone(&self)
+                .handle_drain_host(message.payload, host_id)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             // Claims commands
             (Some("claims"), Some("get"), None, None) => self
                 .handle_claims()
@@ -3083,12 +4072,29 @@ impl Host {
                 .await
                 .map(|bytes| Some(Ok(bytes))),
             (Some("config"), Some("put"), Some(config_name), None) => self
-                .handle_config_put(config_name, message.payload)
+                .handle_config_put(
+                    config_name,
+                    message.payload,
+                    requesting_user(message.headers.as_ref()),
+                )
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
             (Some("config"), Some("del"), Some(config_name), None) => self
-                .handle_config_delete(config_name)
+                .handle_config_delete(config_name, requesting_user(message.headers.as_ref()))
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("config"), Some("history"), Some(config_name), None) => self
+                .handle_config_history(config_name)
+                .await
+                .map(|bytes| Some(Ok(bytes))),
+            (Some("config"), Some("rollback"), Some(config_name), None) => self
+                .handle_config_rollback(
+                    config_name,
+                    message.payload,
+                    requesting_user(message.headers.as_ref()),
+                )
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
@@ -3107,6 +4113,28 @@ impl Host {
             trace!(%subject, "handled control interface request");
         }
 
+        if let (Some(audit_log), Some((payload, headers))) = (&self.audit_log, audited) {
+            let (success, message) = match &ctl_response {
+                Ok(Some(Ok(response))) => response_outcome(response),
+                Ok(None) => (true, String::default()),
+                Ok(Some(Err(e))) | Err(e) => (false, e.to_string()),
+            };
+            let payload = sanitize_audit_payload(&operation, &payload);
+            let record = AuditRecord {
+                timestamp: audit_timestamp(),
+                host_id: self.host_key.public_key(),
+                subject: subject.to_string(),
+                operation,
+                user: requesting_user(headers.as_ref()),
+                payload,
+                success,
+                message,
+            };
+            if audit_log.send(record).await.is_err() {
+                error!(%subject, "audit log writer stopped, failed to write audit record");
+            }
+        }
+
         if let Some(reply) = message.reply {
             let headers = injector_to_headers(&TraceContextInjector::default_with_span());
 
@@ -3289,7 +4317,7 @@ impl Host {
         let key = format!("COMPONENT_{id}");
         let spec = self
             .data
-            .get(key)
+            .get(&key)
             .await
             .context("failed to get component spec")?
             .map(|spec_bytes| serde_json::from_slice(&spec_bytes))
@@ -3312,7 +4340,7 @@ impl Host {
             .context("failed to serialize component spec")?
             .into();
         self.data
-            .put(key, bytes)
+            .put(&key, bytes)
             .await
             .context("failed to put component spec")?;
         Ok(())
@@ -3341,7 +4369,7 @@ impl Host {
             .context("failed to serialize claims")?
             .into();
         self.data
-            .put(key, bytes)
+            .put(&key, bytes)
             .await
             .context("failed to put claims")?;
         Ok(())
@@ -3525,6 +4553,10 @@ impl Host {
             (Operation::Delete, Some(("CLAIMS", pubkey))) => {
                 self.process_claims_delete(pubkey, value).await
             }
+            (operation, Some(("WORKLOADS", host_id))) => {
+                trace!(?operation, host_id, "ignoring host workloads entry");
+                Ok(())
+            }
             (operation, Some(("REFMAP", id))) => {
                 // TODO: process REFMAP entries
                 debug!(?operation, id, "ignoring REFMAP entry");
@@ -3551,17 +4583,23 @@ impl Host {
             .map(|s| s.to_string())
             .partition(|name| name.starts_with(SECRET_PREFIX));
 
+        let start_at = Instant::now();
         let config = self
             .config_generator
             .generate(config_names)
             .await
             .context("Unable to fetch requested config")?;
+        let config_fetched_at = Instant::now();
 
         let secrets = self
             .secrets_manager
             .fetch_secrets(secret_names, entity_jwt, &self.host_token.jwt, application)
             .await
             .context("Unable to fetch requested secrets")?;
+        self.metrics.record_config_fetch(
+            config_fetched_at.duration_since(start_at),
+            config_fetched_at.elapsed(),
+        );
 
         Ok((config, secrets))
     }
@@ -3609,6 +4647,100 @@ impl Host {
         Ok(())
     }
 
+    /// Validates the merged values of the named configs against the config `schema` of the
+    /// component or provider `id`, failing with field-level errors on violations.
+    ///
+    /// Secret references are not part of the config and are skipped, as are configs that do not
+    /// exist in the store, which are reported by [`Self::validate_config`].
+    async fn validate_config_against_schema(
+        &self,
+        id: &str,
+        schema: &serde_json::Value,
+        config_names: &[String],
+    ) -> anyhow::Result<()> {
+        let config = self.merged_config_values(config_names).await?;
+        let violations = validate_config_schema(schema, &config)
+            .with_context(|| format!("failed to validate config of `{id}`"))?;
+        ensure!(
+            violations.is_empty(),
+            format_config_violations(id, &violations)
+        );
+        Ok(())
+    }
+
+    /// Validates the named configs against the config schema of the component or provider `id`
+    /// recorded in its specification, if the specification is for `image_ref` and declares one.
+    /// This allows rejecting invalid config before the component or provider is fetched
+    async fn validate_config_against_cached_schema(
+        &self,
+        id: &str,
+        image_ref: &str,
+        config_names: &[String],
+    ) -> anyhow::Result<()> {
+        let spec = match self.get_component_spec(id).await {
+            Ok(spec) => spec,
+            Err(err) => {
+                warn!(
+                    id,
+                    ?err,
+                    "failed to get component spec, skipping config validation"
+                );
+                return Ok(());
+            }
+        };
+        if let Some(schema) = spec
+            .filter(|spec| spec.url == image_ref)
+            .and_then(|spec| spec.config_schema)
+        {
+            self.validate_config_against_schema(id, &schema, config_names)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the configs the component or provider `id` is running with on this
+    /// host, if it is running
+    async fn running_config_names(&self, id: &str) -> Option<Vec<String>> {
+        if let Some(component) = self.components.read().await.get(id) {
+            let config = component.handler.config_data.read().await;
+            return Some(config.config_names().clone());
+        }
+        let provider_config = self
+            .providers
+            .read()
+            .await
+            .get(id)
+            .map(|provider| Arc::clone(&provider.config))?;
+        let config = provider_config.read().await;
+        Some(config.config_names().clone())
+    }
+
+    /// Merges the values of the named configs, in order. Secret references are skipped, as are
+    /// configs that do not exist in the store
+    async fn merged_config_values(
+        &self,
+        config_names: &[String],
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let mut config = HashMap::new();
+        for name in config_names {
+            if name.starts_with(SECRET_PREFIX) {
+                continue;
+            }
+            let Some(data) = self
+                .config_data
+                .get(name)
+                .await
+                .with_context(|| format!("failed to get config `{name}`"))?
+            else {
+                continue;
+            };
+            let values: HashMap<String, String> = serde_json::from_slice(&data)
+                .with_context(|| format!("failed to parse config `{name}`"))?;
+            config.extend(values);
+        }
+        Ok(config)
+    }
+
     /// Transform a [`wasmcloud_control_interface::Link`] into a [`wasmcloud_core::InterfaceLinkDefinition`]
     /// by fetching the source and target configurations and secrets, and encrypting the secrets.
     async fn resolve_link_config(
@@ -3985,6 +5117,28 @@ fn injector_to_headers(injector: &TraceContextInjector) -> async_nats::header::H
         .collect()
 }
 
+/// Returns the fuel budget requested for a component by its annotations, which only applies if
+/// fuel metering is enabled on the host
+fn component_max_fuel(
+    id: &str,
+    annotations: &Annotations,
+    fuel_metering: bool,
+) -> anyhow::Result<Option<u64>> {
+    let max_fuel = annotations
+        .get(MAX_FUEL_ANNOTATION)
+        .map(|max_fuel| max_fuel.parse())
+        .transpose()
+        .with_context(|| format!("invalid `{MAX_FUEL_ANNOTATION}` annotation"))?;
+    if max_fuel.is_some() && !fuel_metering {
+        warn!(
+            component_id = id,
+            "fuel budget requested for component, but fuel metering is not enabled on this host"
+        );
+        return Ok(None);
+    }
+    Ok(max_fuel)
+}
+
 #[cfg(test)]
 mod test {
     // Ensure that the helper function to translate a list of links into a map of imports works as expected
@@ -4130,4 +5284,28 @@ mod test {
 
         assert_eq!(links_map, expected_result);
     }
+
+    #[test]
+    fn component_max_fuel() {
+        use super::{component_max_fuel, Annotations, MAX_FUEL_ANNOTATION};
+
+        let annotations = Annotations::from([(MAX_FUEL_ANNOTATION.into(), "1000".into())]);
+        assert_eq!(
+            component_max_fuel("component", &annotations, true).expect("failed to parse fuel"),
+            Some(1000)
+        );
+        // The budget is ignored if fuel metering is not enabled
+        assert_eq!(
+            component_max_fuel("component", &annotations, false).expect("failed to parse fuel"),
+            None
+        );
+        assert_eq!(
+            component_max_fuel("component", &Annotations::default(), true)
+                .expect("failed to parse fuel"),
+            None
+        );
+
+        let annotations = Annotations::from([(MAX_FUEL_ANNOTATION.into(), "lots".into())]);
+        assert!(component_max_fuel("component", &annotations, true).is_err());
+    }
 }
@@ -0,0 +1,81 @@
+//! Rate limiting of component `stdout` and `stderr` output logged by the host
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Limits the number of lines of output logged per second for a single component. Also used to
+/// limit the number of events reporting egress denials of a component
+#[derive(Debug)]
+pub(crate) struct OutputRateLimiter {
+    /// Maximum number of lines logged per second, `0` disables the limit
+    max_lines: u32,
+    state: Mutex<OutputWindow>,
+}
+
+#[derive(Debug)]
+struct OutputWindow {
+    /// Start of the current one-second window
+    start: Instant,
+    /// Number of lines logged in the current window
+    lines: u32,
+    /// Number of lines dropped since the last logged line
+    dropped: u64,
+}
+
+impl OutputRateLimiter {
+    pub(crate) fn new(max_lines: u32) -> Self {
+        Self {
+            max_lines,
+            state: Mutex::new(OutputWindow {
+                start: Instant::now(),
+                lines: 0,
+                dropped: 0,
+            }),
+        }
+    }
+
+    /// Attempts to acquire a permit to log a single line at `now`.
+    ///
+    /// Returns the number of lines dropped since the last permitted line, or `None` if the line
+    /// must be dropped.
+    pub(crate) fn acquire(&self, now: Instant) -> Option<u64> {
+        if self.max_lines == 0 {
+            return Some(0);
+        }
+        let Ok(mut window) = self.state.lock() else {
+            return Some(0);
+        };
+        if now.saturating_duration_since(window.start) >= Duration::from_secs(1) {
+            window.start = now;
+            window.lines = 0;
+        }
+        if window.lines >= self.max_lines {
+            window.dropped = window.dropped.saturating_add(1);
+            return None;
+        }
+        window.lines += 1;
+        Some(core::mem::take(&mut window.dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_rate_limit() {
+        let limiter = OutputRateLimiter::new(2);
+        let start = Instant::now();
+        assert_eq!(limiter.acquire(start), Some(0));
+        assert_eq!(limiter.acquire(start), Some(0));
+        assert_eq!(limiter.acquire(start), None);
+        assert_eq!(limiter.acquire(start + Duration::from_millis(500)), None);
+        assert_eq!(limiter.acquire(start + Duration::from_secs(1)), Some(2));
+        assert_eq!(limiter.acquire(start + Duration::from_secs(1)), Some(0));
+
+        let unlimited = OutputRateLimiter::new(0);
+        for _ in 0..1000 {
+            assert_eq!(unlimited.acquire(start), Some(0));
+        }
+    }
+}
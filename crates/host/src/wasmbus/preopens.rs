@@ -0,0 +1,179 @@
+//! Parsing and validation of WASI filesystem directories preopened for components
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use anyhow::{bail, ensure, Context as _};
+use wasmcloud_runtime::component::Preopen;
+
+/// Annotation and config key used to declare directories preopened for a component.
+///
+/// The value is a comma-separated list of `host_path:guest_path[:ro|rw]` entries, for example
+/// `/var/lib/models:/models:ro,/tmp/scratch:/scratch:rw`. Directories are mounted read-only,
+/// unless `rw` is specified.
+pub(crate) const PREOPENS_KEY: &str = "wasmcloud.dev/preopens";
+
+/// Parses a single `host_path:guest_path[:ro|rw]` preopen specification
+fn parse_preopen(spec: &str) -> anyhow::Result<Preopen> {
+    let (spec, read_only) = match spec.rsplit_once(':') {
+        Some((spec, "ro")) => (spec, true),
+        Some((spec, "rw")) => (spec, false),
+        _ => (spec, true),
+    };
+    let Some((host_path, guest_path)) = spec.split_once(':') else {
+        bail!("preopen `{spec}` must be in `host_path:guest_path[:ro|rw]` format");
+    };
+    ensure!(!host_path.is_empty(), "preopen `{spec}` host path is empty");
+    ensure!(
+        !guest_path.is_empty(),
+        "preopen `{spec}` guest path is empty"
+    );
+    Ok(Preopen {
+        host_path: host_path.into(),
+        guest_path: guest_path.into(),
+        read_only,
+    })
+}
+
+/// Parses a comma-separated list of preopen specifications
+fn parse_preopens(specs: &str) -> anyhow::Result<Vec<Preopen>> {
+    specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .map(parse_preopen)
+        .collect()
+}
+
+/// Collects preopens declared for a component through annotations and its named config and
+/// ensures that all of them are located within the host `allowlist`.
+///
+/// Returned preopens have canonicalized host paths, which are ensured to be directories the host
+/// can open.
+pub(crate) fn component_preopens(
+    annotations: &BTreeMap<String, String>,
+    config: &HashMap<String, String>,
+    allowlist: &[PathBuf],
+) -> anyhow::Result<Vec<Preopen>> {
+    let mut preopens = Vec::new();
+    for specs in [annotations.get(PREOPENS_KEY), config.get(PREOPENS_KEY)]
+        .into_iter()
+        .flatten()
+    {
+        preopens.extend(parse_preopens(specs)?);
+    }
+    if preopens.is_empty() {
+        return Ok(preopens);
+    }
+    ensure!(
+        !allowlist.is_empty(),
+        "component requested preopened directories, but no host paths are allowed to be preopened on this host"
+    );
+    let allowlist = allowlist
+        .iter()
+        .map(|path| {
+            path.canonicalize().with_context(|| {
+                format!(
+                    "failed to canonicalize allowed preopen path `{}`",
+                    path.display()
+                )
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    preopens
+        .into_iter()
+        .map(|preopen| {
+            let host_path = preopen.host_path.canonicalize().with_context(|| {
+                format!(
+                    "failed to canonicalize preopen host path `{}`",
+                    preopen.host_path.display()
+                )
+            })?;
+            ensure!(
+                host_path.is_dir(),
+                "preopen host path `{}` is not a directory",
+                host_path.display()
+            );
+            ensure!(
+                allowlist
+                    .iter()
+                    .any(|allowed| host_path.starts_with(allowed)),
+                "preopen host path `{}` is not within any of the allowed host paths",
+                host_path.display()
+            );
+            // Preopening is performed for each instance, where failures cannot be reported
+            // anymore, so ensure the directory can be opened before the component is started
+            std::fs::File::open(&host_path).with_context(|| {
+                format!("failed to open preopen host path `{}`", host_path.display())
+            })?;
+            Ok(Preopen {
+                host_path,
+                ..preopen
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_preopen_specs() {
+        assert_eq!(
+            parse_preopens("/data:/data, /tmp/scratch:/scratch:rw,/models:/models:ro,")
+                .expect("failed to parse preopens"),
+            vec![
+                Preopen {
+                    host_path: "/data".into(),
+                    guest_path: "/data".into(),
+                    read_only: true,
+                },
+                Preopen {
+                    host_path: "/tmp/scratch".into(),
+                    guest_path: "/scratch".into(),
+                    read_only: false,
+                },
+                Preopen {
+                    host_path: "/models".into(),
+                    guest_path: "/models".into(),
+                    read_only: true,
+                },
+            ]
+        );
+        assert!(parse_preopens("/data").is_err());
+        assert!(parse_preopens(":/data").is_err());
+        assert!(parse_preopens("/data::rw").is_err());
+    }
+
+    #[test]
+    fn preopens_must_be_allowed() {
+        let tmp = std::env::temp_dir();
+        let annotations =
+            BTreeMap::from([(PREOPENS_KEY.to_string(), format!("{}:/tmp", tmp.display()))]);
+        assert!(component_preopens(&annotations, &HashMap::default(), &[]).is_err());
+        let preopens = component_preopens(
+            &annotations,
+            &HashMap::default(),
+            std::slice::from_ref(&tmp),
+        )
+        .expect("preopen should be allowed");
+        assert_eq!(preopens.len(), 1);
+        assert!(component_preopens(
+            &annotations,
+            &HashMap::default(),
+            &[tmp.join("wasmcloud-preopen-does-not-exist")]
+        )
+        .is_err());
+
+        // Directories, which cannot be preopened, fail with an error naming the path
+        let missing = tmp.join("wasmcloud-preopen-does-not-exist");
+        let annotations = BTreeMap::from([(
+            PREOPENS_KEY.to_string(),
+            format!("{}:/missing", missing.display()),
+        )]);
+        let err = component_preopens(&annotations, &HashMap::default(), &[tmp])
+            .expect_err("missing preopen should fail");
+        assert!(format!("{err:#}").contains(&missing.display().to_string()));
+    }
+}
@@ -0,0 +1,119 @@
+//! Persistence of component invocation recordings
+
+use core::time::Duration;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use tokio::sync::mpsc;
+use tracing::{debug, warn, Instrument as _};
+use wasmcloud_runtime::component::InvocationRecording;
+
+use super::host_config::InvocationRecordingSink;
+
+/// Annotation, which enables recording of invocations of a component if set to `true`
+pub(crate) const RECORD_INVOCATIONS_ANNOTATION: &str = "wasmcloud.dev/record-invocations";
+
+/// Maximum number of recordings of a single component waiting to be persisted, recordings are
+/// dropped if exceeded
+const RECORDING_QUEUE_SIZE: usize = 64;
+
+/// Maximum age of recordings stored in JetStream
+const RECORDING_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Returns the subject, on which recordings of invocations of `component_id` are published
+pub(crate) fn recording_subject(lattice: &str, component_id: &str) -> String {
+    format!("wasmcloud.{lattice}.recordings.{component_id}")
+}
+
+/// Writer of invocation recordings to the configured [`InvocationRecordingSink`]
+#[derive(Clone, Debug)]
+pub(crate) enum RecordingWriter {
+    Directory(PathBuf),
+    JetStream {
+        jetstream: async_nats::jetstream::Context,
+        lattice: Arc<str>,
+    },
+}
+
+impl RecordingWriter {
+    /// Prepares `sink` for writing, creating the JetStream stream if it does not exist yet
+    pub(crate) async fn new(
+        sink: &InvocationRecordingSink,
+        jetstream: &async_nats::jetstream::Context,
+        lattice: &Arc<str>,
+    ) -> anyhow::Result<Self> {
+        match sink {
+            InvocationRecordingSink::Directory(dir) => Ok(Self::Directory(dir.clone())),
+            InvocationRecordingSink::JetStream => {
+                let name = format!("RECORDINGS_{lattice}");
+                jetstream
+                    .get_or_create_stream(async_nats::jetstream::stream::Config {
+                        name: name.clone(),
+                        subjects: vec![recording_subject(lattice, ">")],
+                        max_age: RECORDING_MAX_AGE,
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .with_context(|| format!("failed to create stream `{name}`"))?;
+                Ok(Self::JetStream {
+                    jetstream: jetstream.clone(),
+                    lattice: Arc::clone(lattice),
+                })
+            }
+        }
+    }
+
+    /// Spawns a task persisting the recordings of `component_id` sent on the returned channel.
+    /// The task exits once all senders are dropped.
+    pub(crate) fn spawn(&self, component_id: Arc<str>) -> mpsc::Sender<InvocationRecording> {
+        let (tx, mut rx) = mpsc::channel(RECORDING_QUEUE_SIZE);
+        let writer = self.clone();
+        tokio::spawn(
+            async move {
+                while let Some(recording) = rx.recv().await {
+                    if let Err(err) = writer.write(&component_id, &recording).await {
+                        warn!(?err, ?component_id, "failed to write invocation recording");
+                    }
+                }
+                debug!(?component_id, "invocation recording writer done");
+            }
+            .in_current_span(),
+        );
+        tx
+    }
+
+    async fn write(
+        &self,
+        component_id: &str,
+        recording: &InvocationRecording,
+    ) -> anyhow::Result<()> {
+        let payload =
+            serde_json::to_vec(recording).context("failed to serialize invocation recording")?;
+        match self {
+            Self::Directory(dir) => {
+                let dir = dir.join(component_id);
+                tokio::fs::create_dir_all(&dir)
+                    .await
+                    .with_context(|| format!("failed to create directory `{}`", dir.display()))?;
+                let path = dir.join(format!("{}.json", ulid::Ulid::new()));
+                tokio::fs::write(&path, payload)
+                    .await
+                    .with_context(|| format!("failed to write `{}`", path.display()))
+            }
+            Self::JetStream { jetstream, lattice } => {
+                jetstream
+                    .publish(recording_subject(lattice, component_id), payload.into())
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .context("failed to publish invocation recording")?
+                    .await
+                    .map_err(|err| anyhow!(err))
+                    .context("invocation recording was not acknowledged")?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Recording of the workloads a host intends to run in the lattice data bucket, such that they can
+//! be brought back after the host restarts
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, instrument};
+use wasmcloud_control_interface::{ScaleComponentCommand, StartProviderCommand};
+
+use super::Host;
+
+/// Workloads a host intends to run
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Workloads {
+    /// Latest scale commands of running components, keyed by component ID
+    #[serde(default)]
+    components: BTreeMap<String, ScaleComponentCommand>,
+    /// Start commands of running providers, keyed by provider ID
+    #[serde(default)]
+    providers: BTreeMap<String, StartProviderCommand>,
+}
+
+impl Workloads {
+    fn scale_component(&mut self, cmd: &ScaleComponentCommand) {
+        if cmd.max_instances() == 0 {
+            self.components.remove(cmd.component_id());
+        } else {
+            self.components
+                .insert(cmd.component_id().into(), cmd.clone());
+        }
+    }
+
+    fn update_component(&mut self, component_id: &str, component_ref: &str) -> anyhow::Result<()> {
+        let Some(cmd) = self.components.get_mut(component_id) else {
+            return Ok(());
+        };
+        let mut builder = ScaleComponentCommand::builder()
+            .component_ref(component_ref)
+            .component_id(component_id)
+            .max_instances(cmd.max_instances())
+            .host_id(cmd.host_id())
+            .config(cmd.config().clone())
+            .allow_update(cmd.allow_update());
+        if let Some(annotations) = cmd.annotations() {
+            builder = builder.annotations(annotations.clone());
+        }
+        if let Some(max_fuel) = cmd.max_fuel() {
+            builder = builder.max_fuel(max_fuel);
+        }
+        if let Some(max_memory) = cmd.max_memory() {
+            builder = builder.max_memory(max_memory);
+        }
+        *cmd = builder
+            .build()
+            .map_err(|e| anyhow!("failed to build component scale command: {e}"))?;
+        Ok(())
+    }
+
+    fn start_provider(&mut self, cmd: StartProviderCommand) {
+        self.providers.insert(cmd.provider_id().into(), cmd);
+    }
+
+    fn stop_provider(&mut self, provider_id: &str) {
+        self.providers.remove(provider_id);
+    }
+}
+
+impl Host {
+    /// Returns the key of the workloads of this host in the lattice data bucket
+    fn workloads_key(&self) -> String {
+        format!("WORKLOADS_{}", self.host_key.public_key())
+    }
+
+    /// Applies `f` to the workloads of this host and records the result in the lattice data bucket,
+    /// if workload rehydration is enabled
+    async fn update_workloads(&self, f: impl FnOnce(&mut Workloads) -> anyhow::Result<()>) {
+        if !self.host_config.rehydrate_workloads {
+            return;
+        }
+        // The lock is held until the workloads are written, such that updates are recorded in order
+        let mut workloads = self.workloads.lock().await;
+        let mut next = workloads.clone();
+        let res: anyhow::Result<()> = async {
+            f(&mut next)?;
+            if next == *workloads {
+                return Ok(());
+            }
+            let buf = serde_json::to_vec(&next).context("failed to encode workloads")?;
+            self.data
+                .put(&self.workloads_key(), buf.into())
+                .await
+                .context("failed to write workloads to lattice data bucket")?;
+            *workloads = next;
+            Ok(())
+        }
+        .await;
+        if let Err(err) = res {
+            error!(?err, "failed to record host workloads");
+        }
+    }
+
+    pub(crate) async fn record_component_scale(&self, cmd: &ScaleComponentCommand) {
+        self.update_workloads(|workloads| {
+            workloads.scale_component(cmd);
+            Ok(())
+        })
+        .await;
+    }
+
+    pub(crate) async fn record_component_update(&self, component_id: &str, component_ref: &str) {
+        self.update_workloads(|workloads| workloads.update_component(component_id, component_ref))
+            .await;
+    }
+
+    pub(crate) async fn record_provider_start(&self, cmd: StartProviderCommand) {
+        self.update_workloads(|workloads| {
+            workloads.start_provider(cmd);
+            Ok(())
+        })
+        .await;
+    }
+
+    pub(crate) async fn record_provider_stop(&self, provider_id: &str) {
+        self.update_workloads(|workloads| {
+            workloads.stop_provider(provider_id);
+            Ok(())
+        })
+        .await;
+    }
+
+    /// Brings back the workloads recorded by a previous run of this host and waits for them to be
+    /// started
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn rehydrate_workloads(self: &Arc<Self>) -> anyhow::Result<()> {
+        let Some(buf) = self
+            .data
+            .get(&self.workloads_key())
+            .await
+            .context("failed to read workloads from lattice data bucket")?
+        else {
+            debug!("no workloads recorded for host");
+            return Ok(());
+        };
+        let workloads: Workloads =
+            serde_json::from_slice(&buf).context("failed to decode workloads")?;
+        info!(
+            components = workloads.components.len(),
+            providers = workloads.providers.len(),
+            "rehydrating workloads"
+        );
+        *self.workloads.lock().await = workloads.clone();
+
+        let host_id = self.host_key.public_key();
+        let mut tasks = Vec::with_capacity(workloads.components.len() + workloads.providers.len());
+        for cmd in workloads.components.values() {
+            let (_, task) = Arc::clone(self).scale_component(cmd, &host_id).await;
+            tasks.extend(task);
+        }
+        for cmd in workloads.providers.into_values() {
+            let (_, task) = Arc::clone(self).start_provider(cmd, &host_id).await;
+            tasks.extend(task);
+        }
+        for res in join_all(tasks).await {
+            if let Err(err) = res {
+                error!(?err, "workload rehydration task failed");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workloads() -> anyhow::Result<()> {
+        let scale = |max_instances| {
+            ScaleComponentCommand::builder()
+                .component_ref("ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0")
+                .component_id("http-hello")
+                .max_instances(max_instances)
+                .host_id("host")
+                .max_fuel(100)
+                .build()
+                .map_err(|e| anyhow!(e))
+        };
+        let mut workloads = Workloads::default();
+        workloads.scale_component(&scale(4)?);
+        workloads.update_component(
+            "http-hello",
+            "ghcr.io/wasmcloud/components/http-hello-world-rust:0.2.0",
+        )?;
+        workloads.update_component("missing", "ghcr.io/wasmcloud/missing:0.1.0")?;
+        let cmd = &workloads.components["http-hello"];
+        assert_eq!(
+            cmd.component_ref(),
+            "ghcr.io/wasmcloud/components/http-hello-world-rust:0.2.0"
+        );
+        assert_eq!(cmd.max_instances(), 4);
+        assert_eq!(cmd.max_fuel(), Some(100));
+        assert_eq!(workloads.components.len(), 1);
+
+        workloads.start_provider(
+            StartProviderCommand::builder()
+                .provider_ref("ghcr.io/wasmcloud/http-server:0.24.0")
+                .provider_id("http-server")
+                .host_id("host")
+                .build()
+                .map_err(|e| anyhow!(e))?,
+        );
+        let buf = serde_json::to_vec(&workloads)?;
+        assert_eq!(serde_json::from_slice::<Workloads>(&buf)?, workloads);
+
+        workloads.scale_component(&scale(0)?);
+        workloads.stop_provider("http-server");
+        assert_eq!(workloads, Workloads::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+//! Supervision of capability provider processes
+
+use core::time::Duration;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Weak};
+
+use anyhow::Context as _;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use nkeys::XKey;
+use tokio::io::AsyncWriteExt as _;
+use tokio::sync::{broadcast, watch};
+use tokio::time::{sleep, Instant};
+use tokio::{process, select};
+use tracing::{debug, error, info, warn};
+use wascap::jwt;
+
+use super::host_config::ProviderRestartPolicy;
+use super::limits::ProviderLimiter;
+use super::{event, Annotations, Host, HostConfig};
+use crate::HostMetrics;
+
+/// Annotation used to override the host default restart policy of a provider
+pub(crate) const RESTART_POLICY_ANNOTATION: &str = "wasmcloud.dev/restart-policy";
+
+/// Annotation used to override the host default maximum number of consecutive restarts of a
+/// provider
+pub(crate) const MAX_RESTARTS_ANNOTATION: &str = "wasmcloud.dev/max-restarts";
+
+/// Providers, which ran for at least this long before exiting, are considered to have recovered,
+/// which resets the restart backoff and budget
+const PROVIDER_STABLE_PERIOD: Duration = Duration::from_secs(5 * 60);
+
+/// Restart behavior of a single provider
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct RestartPolicy {
+    pub policy: ProviderRestartPolicy,
+    pub max_restarts: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RestartPolicy {
+    /// Returns the restart policy of a provider started with `annotations`, falling back to the
+    /// defaults in `config`
+    pub(crate) fn new(config: &HostConfig, annotations: &Annotations) -> anyhow::Result<Self> {
+        let policy = annotations
+            .get(RESTART_POLICY_ANNOTATION)
+            .map(|policy| policy.parse())
+            .transpose()
+            .with_context(|| format!("invalid `{RESTART_POLICY_ANNOTATION}` annotation"))?
+            .unwrap_or(config.provider_restart_policy);
+        let max_restarts = annotations
+            .get(MAX_RESTARTS_ANNOTATION)
+            .map(|max_restarts| max_restarts.parse())
+            .transpose()
+            .with_context(|| format!("invalid `{MAX_RESTARTS_ANNOTATION}` annotation"))?
+            .unwrap_or(config.provider_max_restarts);
+        Ok(Self {
+            policy,
+            max_restarts,
+            backoff: config.provider_restart_backoff,
+            max_backoff: config.provider_max_restart_backoff,
+        })
+    }
+
+    /// Returns whether a provider, which exited, should be restarted
+    fn should_restart(&self, failed: bool) -> bool {
+        match self.policy {
+            ProviderRestartPolicy::Never => false,
+            ProviderRestartPolicy::OnFailure => failed,
+            ProviderRestartPolicy::Always => true,
+        }
+    }
+
+    /// Returns the delay before the consecutive restart number `n`, starting at `0`
+    fn backoff(&self, n: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(n))
+            .min(self.max_backoff)
+    }
+}
+
+/// Spawns the provider executable at `path` subject to the limits enforced by `limiter` and writes
+/// the serialized `host_data` to its stdin
+pub(crate) async fn spawn_provider_process(
+    path: &Path,
+    host_data: &[u8],
+    limiter: &ProviderLimiter,
+) -> anyhow::Result<process::Child> {
+    let mut child_cmd = process::Command::new(path);
+    // Prevent the provider from inheriting the host's environment, with the exception of
+    // the following variables we manually add back
+    child_cmd.env_clear();
+
+    if cfg!(windows) {
+        // Proxy SYSTEMROOT to providers. Without this, providers on Windows won't be able to start
+        child_cmd.env(
+            "SYSTEMROOT",
+            env::var("SYSTEMROOT").context("SYSTEMROOT is not set. Providers cannot be started")?,
+        );
+    }
+
+    // Proxy RUST_LOG to (Rust) providers, so they can use the same module-level directives
+    if let Ok(rust_log) = env::var("RUST_LOG") {
+        let _ = child_cmd.env("RUST_LOG", rust_log);
+    }
+    limiter.apply(&mut child_cmd)?;
+
+    let mut child = child_cmd
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn provider process")?;
+    let mut stdin = child.stdin.take().context("failed to take stdin")?;
+    stdin
+        .write_all(STANDARD.encode(host_data).as_bytes())
+        .await
+        .context("failed to write provider data")?;
+    stdin
+        .write_all(b"\r\n")
+        .await
+        .context("failed to write newline")?;
+    stdin.shutdown().await.context("failed to close stdin")?;
+    Ok(child)
+}
+
+/// Returns whether supervision of a provider was stopped by the host
+fn is_stopped(stop: &watch::Receiver<bool>) -> bool {
+    stop.has_changed().is_err() || *stop.borrow()
+}
+
+/// Supervisor of a provider process, which restarts the provider according to its
+/// [`RestartPolicy`]
+pub(crate) struct ProviderSupervisor {
+    pub host: Weak<Host>,
+    pub host_id: String,
+    pub provider_id: String,
+    pub path: PathBuf,
+    pub config_names: Vec<String>,
+    pub claims_token: Option<jwt::Token<jwt::CapabilityProvider>>,
+    pub annotations: Annotations,
+    pub provider_xkey_private_key: String,
+    pub xkey: XKey,
+    pub policy: RestartPolicy,
+    /// Enforces resource limits of the provider processes
+    pub limiter: ProviderLimiter,
+    pub metrics: Arc<HostMetrics>,
+    /// Total number of restarts of the provider
+    pub restarts: Arc<AtomicU32>,
+}
+
+impl ProviderSupervisor {
+    /// Starts a new provider process with links and configuration resolved at the time of the
+    /// restart
+    async fn respawn(&self, host: &Host) -> anyhow::Result<process::Child> {
+        let claims_jwt = self.claims_token.as_ref().map(|t| &t.jwt);
+        let (config, secrets) = host
+            .fetch_config_and_secrets(
+                &self.config_names,
+                claims_jwt,
+                self.annotations.get("wasmcloud.dev/appspec"),
+            )
+            .await?;
+        let host_data = host
+            .provider_host_data(
+                &self.provider_id,
+                claims_jwt,
+                &self.annotations,
+                config.get_config().await.clone(),
+                &secrets,
+                &self.provider_xkey_private_key,
+                &self.xkey,
+            )
+            .await?;
+        spawn_provider_process(&self.path, &host_data, &self.limiter).await
+    }
+
+    /// Supervises the provider `child` process until supervision is stopped via `stop`, the
+    /// provider exits and should not be restarted or the provider exhausts its restart budget.
+    /// `exit` is notified once the provider is not running anymore
+    pub(crate) async fn run(
+        self,
+        mut child: process::Child,
+        mut stop: watch::Receiver<bool>,
+        exit: broadcast::Sender<()>,
+    ) {
+        let provider_id = self.provider_id.as_str();
+        let mut consecutive_restarts = 0;
+        let mut oom_kills = self.limiter.oom_kills().await;
+        // Reason and exceeded limit, if any, of the provider exiting without being stopped by the
+        // host
+        let exited = 'supervise: loop {
+            let started_at = Instant::now();
+            self.metrics.record_provider_processes(provider_id, 1);
+            let status = child.wait().await;
+            self.metrics.record_provider_processes(provider_id, -1);
+            let (failed, mut reason) = match status {
+                Ok(status) => {
+                    debug!(
+                        provider_id,
+                        "provider @ [{}] exited with `{status:?}`",
+                        self.path.display()
+                    );
+                    (!status.success(), status.to_string())
+                }
+                Err(e) => {
+                    error!(
+                        provider_id,
+                        "failed to wait for provider @ [{}] to execute: {e}",
+                        self.path.display()
+                    );
+                    (true, format!("failed to wait for provider process: {e}"))
+                }
+            };
+            if is_stopped(&stop) {
+                break None;
+            }
+            let kills = self.limiter.oom_kills().await;
+            let limit_exceeded = (kills > oom_kills).then_some("memory");
+            oom_kills = kills;
+            if let Some(limit) = limit_exceeded {
+                warn!(
+                    provider_id,
+                    limit, "provider was killed after exceeding its limit"
+                );
+                reason = format!("{reason}, {limit} limit exceeded");
+            }
+            if !self.policy.should_restart(failed) {
+                info!(
+                    provider_id,
+                    reason,
+                    policy = %self.policy.policy,
+                    "provider exited and will not be restarted"
+                );
+                // Providers, which are never restarted, are kept in the inventory until stopped
+                // by a control interface request
+                if self.policy.policy == ProviderRestartPolicy::Never {
+                    break None;
+                }
+                break Some((reason, limit_exceeded));
+            }
+            if started_at.elapsed() >= PROVIDER_STABLE_PERIOD {
+                consecutive_restarts = 0;
+            }
+            child = loop {
+                if consecutive_restarts >= self.policy.max_restarts {
+                    let restarts = self.restarts.load(Ordering::Relaxed);
+                    error!(
+                        provider_id,
+                        reason,
+                        restarts,
+                        "provider exceeded its restart budget and will not be restarted"
+                    );
+                    if let Some(host) = self.host.upgrade() {
+                        if let Err(err) = host
+                            .publish_event(
+                                "provider_crash_looping",
+                                event::provider_crash_looping(
+                                    &self.host_id,
+                                    provider_id,
+                                    restarts,
+                                    &reason,
+                                ),
+                            )
+                            .await
+                        {
+                            warn!(
+                                ?err,
+                                provider_id, "failed to publish provider_crash_looping event"
+                            );
+                        }
+                    }
+                    break 'supervise Some((reason, limit_exceeded));
+                }
+                let backoff = self.policy.backoff(consecutive_restarts);
+                consecutive_restarts += 1;
+                warn!(provider_id, reason, ?backoff, "restarting provider");
+                select! {
+                    () = sleep(backoff) => {}
+                    _ = stop.changed() => {}
+                }
+                if is_stopped(&stop) {
+                    break 'supervise None;
+                }
+                let Some(host) = self.host.upgrade() else {
+                    break 'supervise None;
+                };
+                match self.respawn(&host).await {
+                    Ok(mut child) if is_stopped(&stop) => {
+                        if let Err(err) = child.kill().await {
+                            warn!(%err, provider_id, "failed to kill restarted provider");
+                        }
+                        break 'supervise None;
+                    }
+                    Ok(child) => {
+                        let restarts = self.restarts.fetch_add(1, Ordering::Relaxed) + 1;
+                        self.metrics.record_provider_restart(provider_id);
+                        info!(provider_id, restarts, "provider restarted");
+                        if let Err(err) = host
+                            .publish_event(
+                                "provider_restarted",
+                                event::provider_restarted(
+                                    &self.host_id,
+                                    provider_id,
+                                    restarts,
+                                    &reason,
+                                ),
+                            )
+                            .await
+                        {
+                            warn!(
+                                ?err,
+                                provider_id, "failed to publish provider_restarted event"
+                            );
+                        }
+                        break child;
+                    }
+                    Err(err) => {
+                        error!(?err, provider_id, "failed to restart provider");
+                        reason = format!("{err:#}");
+                    }
+                }
+            };
+        };
+        if let Some((reason, limit_exceeded)) = exited {
+            self.remove(&reason, limit_exceeded).await;
+        }
+        if let Err(err) = exit.send(()) {
+            warn!(%err, provider_id, "failed to send exit tx");
+        }
+    }
+
+    /// Removes the provider, which exited and will not be restarted, from the host and publishes
+    /// a `provider_stopped` event
+    async fn remove(&self, reason: &str, limit_exceeded: Option<&str>) {
+        let provider_id = self.provider_id.as_str();
+        let Some(host) = self.host.upgrade() else {
+            return;
+        };
+        {
+            let mut providers = host.providers.write().await;
+            match providers.get(provider_id) {
+                // Only remove the provider if it was not replaced in the meantime
+                Some(provider) if Arc::ptr_eq(&provider.restarts, &self.restarts) => {
+                    providers.remove(provider_id);
+                }
+                _ => return,
+            }
+        }
+        if let Err(err) = host
+            .publish_event(
+                "provider_stopped",
+                event::provider_stopped(
+                    &self.annotations,
+                    &self.host_id,
+                    provider_id,
+                    reason,
+                    limit_exceeded,
+                ),
+            )
+            .await
+        {
+            warn!(
+                ?err,
+                provider_id, "failed to publish provider_stopped event"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_policy() -> anyhow::Result<()> {
+        let config = HostConfig::default();
+        let policy = RestartPolicy::new(&config, &Annotations::default())?;
+        assert_eq!(policy.policy, ProviderRestartPolicy::Never);
+        assert_eq!(policy.max_restarts, config.provider_max_restarts);
+        assert!(!policy.should_restart(true));
+        assert!(!policy.should_restart(false));
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(8));
+        assert_eq!(policy.backoff(6), Duration::from_secs(60));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(60));
+
+        let policy = RestartPolicy::new(
+            &config,
+            &Annotations::from([(RESTART_POLICY_ANNOTATION.into(), "on-failure".into())]),
+        )?;
+        assert_eq!(policy.policy, ProviderRestartPolicy::OnFailure);
+        assert!(policy.should_restart(true));
+        assert!(!policy.should_restart(false));
+
+        let policy = RestartPolicy::new(
+            &config,
+            &Annotations::from([
+                (RESTART_POLICY_ANNOTATION.into(), "always".into()),
+                (MAX_RESTARTS_ANNOTATION.into(), "2".into()),
+            ]),
+        )?;
+        assert_eq!(policy.policy, ProviderRestartPolicy::Always);
+        assert_eq!(policy.max_restarts, 2);
+        assert!(policy.should_restart(false));
+
+        assert!(RestartPolicy::new(
+            &config,
+            &Annotations::from([(RESTART_POLICY_ANNOTATION.into(), "sometimes".into())]),
+        )
+        .is_err());
+        Ok(())
+    }
+}
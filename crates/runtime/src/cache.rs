@@ -0,0 +1,347 @@
+use core::hash::{Hash as _, Hasher};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use sha2::{Digest as _, Sha256};
+use tracing::{debug, instrument, warn};
+
+/// Default maximum size of the [`ComponentCache`] on disk (1 GiB)
+pub const DEFAULT_COMPONENT_CACHE_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// File extension used for serialized components stored in the cache
+const CACHE_ENTRY_EXTENSION: &str = "cwasm";
+
+/// Content-addressed, on-disk cache of precompiled (serialized) wasmtime components.
+///
+/// Entries are keyed by the SHA-256 digest of the component binary and a fingerprint of the
+/// engine configuration, which includes the wasmtime version, so artifacts produced by an
+/// incompatible engine are never loaded. The total size of the cache is bounded, least recently
+/// used entries are evicted first.
+#[derive(Clone, Debug)]
+pub struct ComponentCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+/// [Hasher] feeding all written bytes into a [Sha256] digest, used to compute a stable engine
+/// fingerprint from [`wasmtime::Engine::precompile_compatibility_hash`]
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        let mut buf = [0; 8];
+        buf.copy_from_slice(&digest[..8]);
+        u64::from_le_bytes(buf)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+impl ComponentCache {
+    /// Returns a new [`ComponentCache`] stored in `dir` and limited to
+    /// [`DEFAULT_COMPONENT_CACHE_MAX_SIZE`] bytes
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size: DEFAULT_COMPONENT_CACHE_MAX_SIZE,
+        }
+    }
+
+    /// Sets the maximum total size of the cache, in bytes
+    #[must_use]
+    pub fn max_size(self, max_size: u64) -> Self {
+        Self { max_size, ..self }
+    }
+
+    /// Returns the directory, in which cache entries are stored
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Computes the path of the cache entry keyed by the parts of `key` compiled by `engine`
+    fn entry_path(&self, engine: &wasmtime::Engine, key: &[&[u8]]) -> PathBuf {
+        let mut fingerprint = Sha256Hasher(Sha256::new());
+        engine
+            .precompile_compatibility_hash()
+            .hash(&mut fingerprint);
+        let fingerprint = fingerprint.0.finalize();
+        let digest = key
+            .iter()
+            .fold(Sha256::new(), Sha256::chain_update)
+            .finalize();
+        self.dir.join(format!(
+            "{}-{}.{CACHE_ENTRY_EXTENSION}",
+            hex::encode(digest),
+            hex::encode(&fingerprint[..8]),
+        ))
+    }
+
+    /// Looks up a precompiled component for `wasm`, compiling and storing it in the cache on miss.
+    ///
+    /// Failure to read or write the cache is not fatal, the component is compiled from scratch
+    /// in that case.
+    ///
+    /// # Errors
+    ///
+    /// Fails if compilation of `wasm` fails
+    pub fn get_or_compile(
+        &self,
+        engine: &wasmtime::Engine,
+        wasm: &[u8],
+    ) -> anyhow::Result<wasmtime::component::Component> {
+        self.get_or_insert_with(engine, &[wasm], || {
+            wasmtime::component::Component::new(engine, wasm).context("failed to compile component")
+        })
+    }
+
+    /// Looks up a precompiled component keyed by the parts of `key`, storing the component returned
+    /// by `compile` in the cache on miss. This allows caching the result of transformations
+    /// applied to Wasm prior to compilation, e.g. adaptation of core modules, keyed by the
+    /// original Wasm and the inputs of the transformation.
+    #[instrument(level = "debug", skip_all, fields(dir = ?self.dir))]
+    pub(crate) fn get_or_insert_with(
+        &self,
+        engine: &wasmtime::Engine,
+        key: &[&[u8]],
+        compile: impl FnOnce() -> anyhow::Result<wasmtime::component::Component>,
+    ) -> anyhow::Result<wasmtime::component::Component> {
+        let path = self.entry_path(engine, key);
+        match Self::load(engine, &path) {
+            Ok(Some(component)) => {
+                debug!(?path, "loaded precompiled component from cache");
+                return Ok(component);
+            }
+            Ok(None) => debug!(?path, "component cache miss"),
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?path,
+                    "failed to load precompiled component, removing entry"
+                );
+                if let Err(err) = fs::remove_file(&path) {
+                    if err.kind() != io::ErrorKind::NotFound {
+                        warn!(?err, ?path, "failed to remove invalid cache entry");
+                    }
+                }
+            }
+        }
+        let component = compile()?;
+        if let Err(err) = self.store(&component, &path) {
+            warn!(
+                ?err,
+                ?path,
+                "failed to store precompiled component in cache"
+            );
+        } else if let Err(err) = self.evict() {
+            warn!(?err, "failed to evict component cache entries");
+        }
+        Ok(component)
+    }
+
+    fn load(
+        engine: &wasmtime::Engine,
+        path: &Path,
+    ) -> anyhow::Result<Option<wasmtime::component::Component>> {
+        match engine.detect_precompiled_file(path) {
+            Ok(Some(wasmtime::Precompiled::Component)) => {}
+            Ok(Some(wasmtime::Precompiled::Module)) => {
+                anyhow::bail!("cache entry is a precompiled module, expected a component")
+            }
+            Ok(None) => anyhow::bail!("cache entry is not a precompiled artifact"),
+            Err(err) => {
+                return match err.downcast_ref::<io::Error>() {
+                    Some(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                    _ => Err(err.context("failed to inspect cache entry")),
+                }
+            }
+        }
+        // SAFETY: the cache directory is owned by the host and entries are only ever written by
+        // `Self::store` using `Component::serialize`. wasmtime validates that the artifact was
+        // produced by a compatible engine and version before loading it.
+        let component = unsafe { wasmtime::component::Component::deserialize_file(engine, path) }
+            .context("failed to deserialize precompiled component")?;
+        // Mark the entry as recently used for LRU eviction
+        if let Err(err) = fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|f| f.set_modified(SystemTime::now()))
+        {
+            debug!(
+                ?err,
+                ?path,
+                "failed to update cache entry modification time"
+            );
+        }
+        Ok(Some(component))
+    }
+
+    fn store(&self, component: &wasmtime::component::Component, path: &Path) -> anyhow::Result<()> {
+        let buf = component
+            .serialize()
+            .context("failed to serialize component")?;
+        fs::create_dir_all(&self.dir).context("failed to create cache directory")?;
+        // Write to a temporary file first, so that concurrent readers never observe partial entries
+        let tmp = path.with_extension(format!("{:016x}.tmp", rand::random::<u64>()));
+        fs::write(&tmp, buf).context("failed to write cache entry")?;
+        if let Err(err) = fs::rename(&tmp, path) {
+            let _ = fs::remove_file(&tmp);
+            return Err(err).context("failed to rename cache entry");
+        }
+        Ok(())
+    }
+
+    /// Removes least recently used entries until the total size of the cache fits within the
+    /// configured maximum size.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cache directory cannot be read
+    #[instrument(level = "debug", skip_all, fields(dir = ?self.dir))]
+    pub fn evict(&self) -> anyhow::Result<()> {
+        let mut entries = Vec::new();
+        let mut total: u64 = 0;
+        for entry in fs::read_dir(&self.dir).context("failed to read cache directory")? {
+            let entry = entry.context("failed to read cache directory entry")?;
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(CACHE_ENTRY_EXTENSION) {
+                continue;
+            }
+            let Ok(md) = entry.metadata() else {
+                continue;
+            };
+            if !md.is_file() {
+                continue;
+            }
+            let used = md.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            total = total.saturating_add(md.len());
+            entries.push((used, md.len(), path));
+        }
+        if total <= self.max_size {
+            return Ok(());
+        }
+        entries.sort_unstable_by_key(|(used, ..)| *used);
+        for (_, size, path) in entries {
+            if total <= self.max_size {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => {
+                    debug!(?path, size, "evicted component cache entry");
+                    total = total.saturating_sub(size);
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    total = total.saturating_sub(size);
+                }
+                Err(err) => warn!(?err, ?path, "failed to evict component cache entry"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::bail;
+
+    fn engine(consume_fuel: bool) -> anyhow::Result<wasmtime::Engine> {
+        let mut config = wasmtime::Config::default();
+        config.wasm_component_model(true);
+        config.consume_fuel(consume_fuel);
+        wasmtime::Engine::new(&config)
+    }
+
+    fn entries(cache: &ComponentCache) -> anyhow::Result<Vec<PathBuf>> {
+        let mut entries = fs::read_dir(cache.dir())?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn cached(
+        cache: &ComponentCache,
+        engine: &wasmtime::Engine,
+        wasm: &[u8],
+    ) -> anyhow::Result<wasmtime::component::Component> {
+        cache.get_or_insert_with(engine, &[wasm], || bail!("cache miss"))
+    }
+
+    #[test]
+    fn hit_and_miss() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ComponentCache::new(dir.path().join("components"));
+        let engine = engine(false)?;
+        let wasm = wat::parse_str("(component)")?;
+
+        assert!(cached(&cache, &engine, &wasm).is_err());
+        cache.get_or_compile(&engine, &wasm)?;
+        assert_eq!(entries(&cache)?, [cache.entry_path(&engine, &[&wasm])]);
+        cached(&cache, &engine, &wasm)?;
+
+        let other = wat::parse_str("(component (core module))")?;
+        assert!(cached(&cache, &engine, &other).is_err());
+        cache.get_or_compile(&engine, &other)?;
+        assert_eq!(entries(&cache)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn engine_change_invalidates() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ComponentCache::new(dir.path());
+        let wasm = wat::parse_str("(component)")?;
+        let engine = engine(false)?;
+        cache.get_or_compile(&engine, &wasm)?;
+
+        let fuel_engine = self::engine(true)?;
+        assert_ne!(
+            cache.entry_path(&engine, &[&wasm]),
+            cache.entry_path(&fuel_engine, &[&wasm])
+        );
+        assert!(cached(&cache, &fuel_engine, &wasm).is_err());
+        cache.get_or_compile(&fuel_engine, &wasm)?;
+        cached(&cache, &fuel_engine, &wasm)?;
+        cached(&cache, &engine, &wasm)?;
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_entry() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ComponentCache::new(dir.path());
+        let engine = engine(false)?;
+        let wasm = wat::parse_str("(component)")?;
+        cache.get_or_compile(&engine, &wasm)?;
+
+        let path = cache.entry_path(&engine, &[&wasm]);
+        fs::write(&path, b"not a precompiled component")?;
+        assert!(cached(&cache, &engine, &wasm).is_err());
+        // The corrupted entry is removed and replaced on the next compilation
+        assert!(!path.exists());
+        cache.get_or_compile(&engine, &wasm)?;
+        cached(&cache, &engine, &wasm)?;
+        Ok(())
+    }
+
+    #[test]
+    fn eviction() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ComponentCache::new(dir.path()).max_size(0);
+        let engine = engine(false)?;
+        let wasm = wat::parse_str("(component)")?;
+        cache.get_or_compile(&engine, &wasm)?;
+        assert_eq!(entries(&cache)?, Vec::<PathBuf>::new());
+        Ok(())
+    }
+}
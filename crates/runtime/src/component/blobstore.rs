@@ -59,7 +59,7 @@ async fn invoke_with_fallback<
                 );
                 f_0_1_0().await
             }
-            InvocationErrorKind::Trap => Err(err),
+            InvocationErrorKind::OutOfFuel | InvocationErrorKind::Trap => Err(err),
         },
     }
 }
@@ -1,5 +1,5 @@
 //! Compatibility implementation of the `wasmcloud:bus/lattice@1.0.0` interface
-use super::{Ctx, Handler, TableResult};
+use super::{Bus as _, Ctx, Handler, TableResult};
 
 use crate::capability::bus1_0_0::lattice;
 
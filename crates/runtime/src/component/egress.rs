@@ -0,0 +1,31 @@
+use core::net::SocketAddr;
+
+use async_trait::async_trait;
+use wasmtime_wasi::SocketAddrUse;
+
+/// Outbound network access attempted by a component
+#[derive(Clone, Debug)]
+pub enum EgressTarget {
+    /// Outgoing `wasi:http` request
+    Http {
+        /// Host of the request URI, IPv6 addresses are not enclosed in brackets
+        host: String,
+        /// Port of the request URI or the default port of the scheme
+        port: u16,
+    },
+    /// `wasi:sockets` use of a socket address
+    Socket {
+        /// Socket address being used
+        addr: SocketAddr,
+        /// Purpose of the address use
+        usage: SocketAddrUse,
+    },
+}
+
+/// Outbound network policy enforcement
+#[async_trait]
+pub trait Egress {
+    /// Returns whether the component is allowed to access `target`
+    #[allow(clippy::double_must_use)]
+    async fn check_egress(&self, target: &EgressTarget) -> bool;
+}
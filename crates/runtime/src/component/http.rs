@@ -1,7 +1,12 @@
-use super::{new_store, Ctx, Handler, Instance, ReplacedInstanceTarget, WrpcServeEvent};
+use super::{
+    fuel_consumed, is_out_of_fuel, new_store, Ctx, Egress as _, EgressTarget, Handler, Instance,
+    RecordedContext, ReplacedInstanceTarget, WrpcServeEvent,
+};
 
 use crate::capability::http::types;
 
+use std::sync::Arc;
+
 use anyhow::{bail, Context as _};
 use futures::stream::StreamExt as _;
 use tokio::sync::oneshot;
@@ -106,15 +111,37 @@ where
     where
         Self: Sized,
     {
+        let uri = request.uri();
+        let Some(host) = uri.host() else {
+            return Err(types::ErrorCode::HttpRequestUriInvalid.into());
+        };
+        let target = EgressTarget::Http {
+            host: host.trim_start_matches('[').trim_end_matches(']').into(),
+            port: uri.port_u16().unwrap_or_else(|| {
+                if uri.scheme() == Some(&http::uri::Scheme::HTTPS) {
+                    443
+                } else {
+                    80
+                }
+            }),
+        };
+        let handler = self.handler.clone();
         Ok(HostFutureIncomingResponse::pending(
             wasmtime_wasi::runtime::spawn(
-                invoke_outgoing_handle(self.handler.clone(), request, config).in_current_span(),
+                async move {
+                    if !handler.check_egress(&target).await {
+                        debug!(?target, "outgoing HTTP request denied by egress policy");
+                        return Ok(Err(types::ErrorCode::HttpRequestDenied));
+                    }
+                    invoke_outgoing_handle(handler, request, config).await
+                }
+                .in_current_span(),
             ),
         ))
     }
 }
 
-impl<H, C> ServeIncomingHandlerWasmtime<C> for Instance<H, C>
+impl<H, C> ServeIncomingHandlerWasmtime<RecordedContext<C>> for Instance<H, C>
 where
     H: Handler,
     C: Send,
@@ -122,7 +149,10 @@ where
     #[instrument(level = "debug", skip_all)]
     async fn handle(
         &self,
-        cx: C,
+        RecordedContext {
+            context: cx,
+            recorder,
+        }: RecordedContext<C>,
         request: ::http::Request<wasmtime_wasi_http::body::HyperIncomingBody>,
     ) -> anyhow::Result<
         Result<
@@ -134,7 +164,9 @@ where
         let scheme = wrpc_interface_http::bindings::wrpc::http::types::Scheme::from(scheme).into();
 
         let (tx, rx) = oneshot::channel();
-        let mut store = new_store(&self.engine, self.handler.clone(), self.max_execution_time);
+        let mut store = new_store(&self.engine, self.handler.clone(), &self.store_config);
+        store.data().handler.recording().attach(recorder);
+        let meter = Arc::clone(&store.data().fuel_consumed);
         let pre = incoming_http_bindings::IncomingHttpPre::new(self.pre.clone())
             .context("failed to pre-instantiate `wasi:http/incoming-handler`")?;
         trace!("instantiating `wasi:http/incoming-handler`");
@@ -194,11 +226,17 @@ where
         }
         .await;
         let success = res.as_ref().is_ok_and(Result::is_ok);
+        let fuel_consumed = fuel_consumed(
+            self.store_config.fuel,
+            &meter,
+            res.as_ref().is_err_and(is_out_of_fuel),
+        );
         if let Err(err) = self
             .events
             .try_send(WrpcServeEvent::HttpIncomingHandlerHandleReturned {
                 context: cx,
                 success,
+                fuel_consumed,
             })
         {
             warn!(
@@ -1,9 +1,9 @@
-use super::{Ctx, Handler};
+use super::{Ctx, Handler, StdioStream};
 
 use crate::capability::logging::logging;
 
 use async_trait::async_trait;
-use tracing::instrument;
+use tracing::{info, instrument};
 
 pub mod unversioned_logging_bindings {
     wasmtime::component::bindgen!({
@@ -35,6 +35,12 @@ pub trait Logging {
         context: String,
         message: String,
     ) -> anyhow::Result<()>;
+
+    /// Handle a single line written by the component to `stdout` or `stderr`,
+    /// without the trailing newline. By default, the line is emitted as an `INFO` event
+    fn log_output(&self, stream: StdioStream, line: &str) {
+        info!(%stream, "{line}");
+    }
 }
 
 #[async_trait]
@@ -1,4 +1,7 @@
-use super::{new_store, Ctx, Handler, Instance, WrpcServeEvent};
+use super::{
+    fuel_consumed, is_out_of_fuel, new_store, Ctx, Handler, Instance, RecordedContext,
+    WrpcServeEvent,
+};
 
 use crate::capability::messaging::{consumer, types};
 use crate::capability::wrpc;
@@ -86,7 +89,8 @@ where
     }
 }
 
-impl<H, C> wrpc_handler_bindings::exports::wasmcloud::messaging::handler::Handler<C>
+impl<H, C>
+    wrpc_handler_bindings::exports::wasmcloud::messaging::handler::Handler<RecordedContext<C>>
     for Instance<H, C>
 where
     H: Handler,
@@ -95,14 +99,18 @@ where
     #[instrument(level = "debug", skip_all)]
     async fn handle_message(
         &self,
-        cx: C,
+        RecordedContext {
+            context: cx,
+            recorder,
+        }: RecordedContext<C>,
         wrpc_handler_bindings::wasmcloud::messaging::types::BrokerMessage {
             subject,
             body,
             reply_to,
         }: wrpc_handler_bindings::wasmcloud::messaging::types::BrokerMessage,
     ) -> anyhow::Result<Result<(), String>> {
-        let mut store = new_store(&self.engine, self.handler.clone(), self.max_execution_time);
+        let mut store = new_store(&self.engine, self.handler.clone(), &self.store_config);
+        store.data().handler.recording().attach(recorder);
         let pre = wasmtime_handler_bindings::MessagingHandlerPre::new(self.pre.clone())
             .context("failed to pre-instantiate `wasmcloud:messaging/handler`")?;
         let bindings = pre.instantiate_async(&mut store).await?;
@@ -119,11 +127,17 @@ where
             .await
             .context("failed to call `wasmcloud:messaging/handler.handle-message`");
         let success = res.is_ok();
+        let fuel_consumed = fuel_consumed(
+            self.store_config.fuel,
+            &store.data().fuel_consumed,
+            res.as_ref().is_err_and(is_out_of_fuel),
+        );
         if let Err(err) =
             self.events
                 .try_send(WrpcServeEvent::MessagingHandlerHandleMessageReturned {
                     context: cx,
                     success,
+                    fuel_consumed,
                 })
         {
             warn!(
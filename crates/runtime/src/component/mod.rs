@@ -1,23 +1,31 @@
 use crate::capability::{self};
+use crate::component::recording::{
+    RecordedContext, Recorder, RecordingHandler, RecordingMode, RecordingServer, ReplayServer,
+    Replayer, StoreRecording,
+};
+use crate::component::stdio::LineOutput;
 use crate::Runtime;
 
 use core::fmt::{self, Debug};
 use core::future::Future;
 use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
 use core::time::Duration;
 
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
 use anyhow::{ensure, Context as _};
-use futures::{Stream, TryStreamExt as _};
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncRead, AsyncReadExt as _};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tracing::{debug, instrument, warn, Instrument as _, Span};
 use wascap::jwt;
 use wascap::wasm::extract_claims;
-use wasi_preview1_component_adapter_provider::{
-    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
-};
 use wasmtime::component::{types, Linker, ResourceTable, ResourceTableError};
-use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
+use wasmtime::{CallHook, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiView};
 use wasmtime_wasi_http::WasiHttpCtx;
 use wrpc_runtime_wasmtime::{
     collect_component_resources, link_item, ServeExt as _, SharedResourceTable, WrpcView,
@@ -26,18 +34,25 @@ use wrpc_runtime_wasmtime::{
 pub use bus::Bus;
 pub use bus1_0_0::Bus as Bus1_0_0;
 pub use config::Config;
+pub use egress::{Egress, EgressTarget};
 pub use logging::Logging;
+pub use recording::{InvocationRecording, RecordedError, RecordedImport, RecordedStream};
 pub use secrets::Secrets;
+pub use stdio::StdioStream;
+pub use wasmtime_wasi::SocketAddrUse;
 
 pub(crate) mod blobstore;
 mod bus;
 mod bus1_0_0;
 mod config;
+mod egress;
 mod http;
 mod keyvalue;
 mod logging;
 mod messaging;
+mod recording;
 mod secrets;
+mod stdio;
 
 /// Instance target, which is replaced in wRPC
 ///
@@ -179,15 +194,29 @@ macro_rules! skip_static_instances {
 }
 
 /// This represents a kind of wRPC invocation error
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InvocationErrorKind {
     /// This occurs when the endpoint is not found, for example as would happen when the runtime
     /// would attempt to call `foo:bar/baz@0.2.0`, but the peer served `foo:bar/baz@0.1.0`.
     NotFound,
 
+    /// This occurs when the invocation exhausted its fuel budget, see [`Component::set_max_fuel`]
+    OutOfFuel,
+
     /// An error kind, which will result in a trap in the component
     Trap,
 }
 
+/// Returns `true` if the error was caused by a component exhausting its fuel budget
+#[must_use]
+pub fn is_out_of_fuel(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<wasmtime::Trap>(),
+        Some(wasmtime::Trap::OutOfFuel)
+    )
+}
+
 /// Implementations of this trait are able to introspect an error returned by wRPC invocations
 pub trait InvocationErrorIntrospect {
     /// Classify [`InvocationErrorKind`] of an error returned by wRPC
@@ -199,6 +228,7 @@ pub trait Handler:
     wrpc_transport::Invoke<Context = Option<ReplacedInstanceTarget>>
     + Bus
     + Config
+    + Egress
     + Logging
     + Secrets
     + InvocationErrorIntrospect
@@ -213,6 +243,7 @@ impl<
         T: wrpc_transport::Invoke<Context = Option<ReplacedInstanceTarget>>
             + Bus
             + Config
+            + Egress
             + Logging
             + Secrets
             + InvocationErrorIntrospect
@@ -231,6 +262,26 @@ pub struct ComponentConfig {
     pub require_signature: bool,
 }
 
+/// Host directory made available to a component via `wasi:filesystem`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Preopen {
+    /// Path of the directory on the host
+    pub host_path: PathBuf,
+    /// Path, at which the directory is available to the component
+    pub guest_path: String,
+    /// Whether the component is only allowed to read the directory contents
+    pub read_only: bool,
+}
+
+/// `wasi:cli` environment of component instances
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CliEnvironment {
+    /// Arguments passed to the component after `argv[0]`
+    pub args: Vec<String>,
+    /// Environment variables available to the component
+    pub env: Vec<(String, String)>,
+}
+
 /// Extracts and validates claims contained within a WebAssembly binary, if present
 ///
 /// # Arguments
@@ -269,6 +320,12 @@ where
     claims: Option<jwt::Claims<jwt::Component>>,
     instance_pre: wasmtime::component::InstancePre<Ctx<H>>,
     max_execution_time: Duration,
+    fuel_metering: bool,
+    max_fuel: Option<u64>,
+    max_memory: Option<u64>,
+    preopens: Arc<[Preopen]>,
+    cli_environment: Option<watch::Receiver<CliEnvironment>>,
+    recording: RecordingMode,
 }
 
 impl<H> Debug for Component<H>
@@ -280,36 +337,147 @@ where
             .field("claims", &self.claims)
             .field("runtime", &"wasmtime")
             .field("max_execution_time", &self.max_execution_time)
+            .field("max_fuel", &self.max_fuel)
+            .field("max_memory", &self.max_memory)
+            .field("preopens", &self.preopens)
+            .field("cli_environment", &self.cli_environment)
+            .field("recording", &self.recording)
             .finish_non_exhaustive()
     }
 }
 
+/// Meter holding the amount of fuel consumed by a single [`wasmtime::Store`]
+type FuelMeter = Arc<AtomicU64>;
+
+/// Fuel meter and recording state of a single [`wasmtime::Store`]
+type StoreHandles = (FuelMeter, StoreRecording);
+
+/// Takes the handles of the last store constructed by `serve_function`, attaches `recorder` to
+/// it and returns its fuel meter
+fn take_store_handles(
+    last_store: &Mutex<Option<StoreHandles>>,
+    recorder: Option<Recorder>,
+) -> Option<FuelMeter> {
+    let (meter, recording) = last_store.lock().ok()?.take()?;
+    recording.attach(recorder);
+    Some(meter)
+}
+
+/// Per-component configuration applied to each [`wasmtime::Store`]
+#[derive(Clone, Debug)]
+struct StoreConfig {
+    max_execution_time: Duration,
+    fuel: Option<u64>,
+    max_memory: Option<u64>,
+    preopens: Arc<[Preopen]>,
+    cli_environment: Option<watch::Receiver<CliEnvironment>>,
+    recording: RecordingMode,
+}
+
 fn new_store<H: Handler>(
     engine: &wasmtime::Engine,
     handler: H,
-    max_execution_time: Duration,
+    StoreConfig {
+        max_execution_time,
+        fuel,
+        max_memory,
+        preopens,
+        cli_environment,
+        recording,
+    }: &StoreConfig,
 ) -> wasmtime::Store<Ctx<H>> {
     let table = ResourceTable::new();
-    let wasi = WasiCtxBuilder::new()
-        .args(&["main.wasm"]) // TODO: Configure argv[0]
-        .inherit_stderr()
-        .build();
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.arg("main.wasm") // TODO: Configure argv[0]
+        .stdout(LineOutput::new(handler.clone(), StdioStream::Stdout))
+        .stderr(LineOutput::new(handler.clone(), StdioStream::Stderr))
+        .socket_addr_check({
+            let handler = handler.clone();
+            move |addr, usage| {
+                let handler = handler.clone();
+                // The check future is required to be `Sync`, which `async_trait` futures are not
+                let check = tokio::spawn(async move {
+                    handler
+                        .check_egress(&EgressTarget::Socket { addr, usage })
+                        .await
+                });
+                Box::pin(async move { check.await.unwrap_or(false) })
+            }
+        });
+    if let Some(cli_environment) = cli_environment {
+        let CliEnvironment { args, env } = &*cli_environment.borrow();
+        wasi.args(args).envs(env);
+    }
+    for Preopen {
+        host_path,
+        guest_path,
+        read_only,
+    } in preopens.iter()
+    {
+        let (dir_perms, file_perms) = if *read_only {
+            (DirPerms::READ, FilePerms::READ)
+        } else {
+            (DirPerms::all(), FilePerms::all())
+        };
+        if let Err(err) = wasi.preopened_dir(host_path, guest_path, dir_perms, file_perms) {
+            warn!(?err, ?host_path, guest_path, "failed to preopen directory");
+        }
+    }
+    let recording = StoreRecording::new(recording);
+    recording.configure_wasi(&mut wasi);
+    let wasi = wasi.build();
+
+    let mut limits = StoreLimitsBuilder::new();
+    if let Some(max_memory) = *max_memory {
+        limits = limits.memory_size(usize::try_from(max_memory).unwrap_or(usize::MAX));
+    }
 
     let mut store = wasmtime::Store::new(
         engine,
         Ctx {
-            handler,
+            handler: RecordingHandler::new(handler, recording),
             wasi,
             http: WasiHttpCtx::new(),
             table,
             shared_resources: SharedResourceTable::default(),
-            timeout: max_execution_time,
+            timeout: *max_execution_time,
+            fuel_consumed: FuelMeter::default(),
+            limits: limits.build(),
         },
     );
+    store.limiter(|ctx| &mut ctx.limits);
     store.set_epoch_deadline(max_execution_time.as_secs());
+    if let Some(fuel) = *fuel {
+        if let Err(err) = store.set_fuel(fuel) {
+            warn!(?err, "failed to set store fuel");
+        } else {
+            // The store is owned by the invocation, so keep track of consumed fuel on every
+            // transition between the guest and the host
+            store.call_hook(move |store, hook| {
+                if matches!(hook, CallHook::CallingHost | CallHook::ReturningFromWasm) {
+                    let remaining = store.get_fuel()?;
+                    store
+                        .data()
+                        .fuel_consumed
+                        .store(fuel.saturating_sub(remaining), Ordering::Relaxed);
+                }
+                Ok(())
+            });
+        }
+    }
     store
 }
 
+/// Returns the amount of fuel consumed by an invocation given its fuel budget, if metering is enabled
+fn fuel_consumed(fuel: Option<u64>, meter: &AtomicU64, out_of_fuel: bool) -> Option<u64> {
+    let fuel = fuel?;
+    if out_of_fuel {
+        Some(fuel)
+    } else {
+        Some(meter.load(Ordering::Relaxed))
+    }
+}
+
 /// Events sent by [`Component::serve_wrpc`]
 #[derive(Clone, Debug)]
 pub enum WrpcServeEvent<C> {
@@ -319,6 +487,8 @@ pub enum WrpcServeEvent<C> {
         context: C,
         /// Whether the invocation was successfully handled
         success: bool,
+        /// Amount of fuel consumed by the invocation, if fuel metering is enabled
+        fuel_consumed: Option<u64>,
     },
     /// `wasmcloud:messaging/handler.handle-message` return event
     MessagingHandlerHandleMessageReturned {
@@ -326,6 +496,8 @@ pub enum WrpcServeEvent<C> {
         context: C,
         /// Whether the invocation was successfully handled
         success: bool,
+        /// Amount of fuel consumed by the invocation, if fuel metering is enabled
+        fuel_consumed: Option<u64>,
     },
     /// dynamic export return event
     DynamicExportReturned {
@@ -333,6 +505,8 @@ pub enum WrpcServeEvent<C> {
         context: C,
         /// Whether the invocation was successfully handled
         success: bool,
+        /// Amount of fuel consumed by the invocation, if fuel metering is enabled
+        fuel_consumed: Option<u64>,
     },
 }
 
@@ -355,27 +529,42 @@ where
 {
     /// Extracts [Claims](jwt::Claims) from WebAssembly component and compiles it using [Runtime].
     ///
-    /// If `wasm` represents a core Wasm module, then it will first be turned into a component.
+    /// If `wasm` represents a WASI preview 1 core Wasm module and the [Runtime] allows it, then it
+    /// will first be adapted to a component using the reactor adapter, or the command adapter if
+    /// the module exports `_start` and the [Runtime] is configured to adapt command modules.
+    /// If the [Runtime] is configured with a [`ComponentCache`](crate::ComponentCache), a
+    /// precompiled artifact is loaded from it, if present. Adapted core modules are cached keyed
+    /// by the original module and the adapter, so adaptation is only performed once.
     #[instrument(level = "trace", skip_all)]
     pub fn new(rt: &Runtime, wasm: &[u8]) -> anyhow::Result<Self> {
-        if wasmparser::Parser::is_core_wasm(wasm) {
-            let wasm = wit_component::ComponentEncoder::default()
-                .module(wasm)
-                .context("failed to set core component module")?
-                .adapter(
-                    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME,
-                    WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
-                )
-                .context("failed to add WASI preview1 adapter")?
-                .encode()
-                .context("failed to encode a component from module")?;
-            return Self::new(rt, &wasm);
-        }
         let engine = rt.engine.clone();
         let claims_token = claims_token(wasm)?;
         let claims = claims_token.map(|c| c.claims);
-        let component = wasmtime::component::Component::new(&engine, wasm)
-            .context("failed to compile component")?;
+        let component = if wasmcloud_component_adapters::is_core_module(wasm) {
+            ensure!(
+                rt.adapt_core_modules,
+                "core Wasm modules are not supported by this runtime, a component is required"
+            );
+            let adapter =
+                wasmcloud_component_adapters::wasip2_adapter_for(wasm, rt.adapt_command_modules)
+                    .context("failed to select adapter for core module")?;
+            let compile = || {
+                let wasm = wasmcloud_component_adapters::adapt_wasip1_component(wasm, adapter)
+                    .context("failed to adapt core module to a component")?;
+                wasmtime::component::Component::new(&engine, wasm)
+                    .context("failed to compile component")
+            };
+            if let Some(cache) = &rt.component_cache {
+                cache.get_or_insert_with(&engine, &[wasm, adapter], compile)?
+            } else {
+                compile()?
+            }
+        } else if let Some(cache) = &rt.component_cache {
+            cache.get_or_compile(&engine, wasm)?
+        } else {
+            wasmtime::component::Component::new(&engine, wasm)
+                .context("failed to compile component")?
+        };
 
         let mut linker = Linker::new(&engine);
 
@@ -433,6 +622,12 @@ where
             claims,
             instance_pre,
             max_execution_time: rt.max_execution_time,
+            fuel_metering: rt.fuel_metering,
+            max_fuel: None,
+            max_memory: None,
+            preopens: Arc::default(),
+            cli_environment: None,
+            recording: RecordingMode::Disabled,
         })
     }
 
@@ -444,6 +639,71 @@ where
         self
     }
 
+    /// Sets the maximum amount of fuel a single invocation of functionality exported by this
+    /// component may consume. [`None`] means the budget is unlimited.
+    /// This has no effect unless fuel metering is enabled for the [Runtime].
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_max_fuel(&mut self, max_fuel: Option<u64>) -> &mut Self {
+        self.max_fuel = max_fuel;
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of each linear memory of an instance of this component.
+    /// [`None`] means only the limit configured for the [Runtime] applies, which also remains the
+    /// upper bound if a larger value is set. Instantiation fails if the component requires more
+    /// memory than allowed, and growing memory past the limit fails within the guest.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_max_memory(&mut self, max_memory: Option<u64>) -> &mut Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    /// Sets the host directories, which are made available to the component via `wasi:filesystem`.
+    /// It is the responsibility of the caller to ensure the component is allowed to access them.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_preopens(&mut self, preopens: impl Into<Arc<[Preopen]>>) -> &mut Self {
+        self.preopens = preopens.into();
+        self
+    }
+
+    /// Sets the source of `wasi:cli` arguments and environment variables of the component.
+    /// Each instance is created using the latest value observed by the receiver.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_cli_environment(
+        &mut self,
+        cli_environment: watch::Receiver<CliEnvironment>,
+    ) -> &mut Self {
+        self.cli_environment = Some(cli_environment);
+        self
+    }
+
+    /// Sets the channel, on which recordings of invocations of functionality exported by this
+    /// component are sent. [`None`] disables recording.
+    /// An invocation is sent once all resources associated with it are released, recordings are
+    /// dropped if the channel is full.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_invocation_recording(
+        &mut self,
+        recordings: Option<mpsc::Sender<InvocationRecording>>,
+    ) -> &mut Self {
+        self.recording = recordings.map_or(RecordingMode::Disabled, RecordingMode::Record);
+        self
+    }
+
+    /// Returns the [`StoreConfig`] used for all invocations of this component
+    fn store_config(&self) -> StoreConfig {
+        StoreConfig {
+            max_execution_time: self.max_execution_time,
+            fuel: self
+                .fuel_metering
+                .then(|| self.max_fuel.unwrap_or(u64::MAX)),
+            max_memory: self.max_memory,
+            preopens: Arc::clone(&self.preopens),
+            cli_environment: self.cli_environment.clone(),
+            recording: self.recording.clone(),
+        }
+    }
+
     /// Reads the WebAssembly binary asynchronously and calls [Component::new].
     ///
     /// # Errors
@@ -493,13 +753,21 @@ where
         S: wrpc_transport::Serve,
     {
         let span = Span::current();
-        let max_execution_time = self.max_execution_time;
+        let store_config = self.store_config();
+        let fuel = store_config.fuel;
+        let srv = &RecordingServer {
+            srv,
+            sink: match &self.recording {
+                RecordingMode::Record(sink) => Some(sink.clone()),
+                RecordingMode::Disabled | RecordingMode::Replay(..) => None,
+            },
+        };
         let mut invocations = vec![];
         let instance = Instance {
             engine: self.engine.clone(),
             pre: self.instance_pre.clone(),
             handler: handler.clone(),
-            max_execution_time: self.max_execution_time,
+            store_config: store_config.clone(),
             events: events.clone(),
         };
         for (name, ty) in self
@@ -538,10 +806,24 @@ where
                     let engine = self.engine.clone();
                     let handler = handler.clone();
                     let pre = self.instance_pre.clone();
+                    // `serve_function` constructs the store right before yielding the
+                    // invocation, keep track of the fuel meter and recording state of the last
+                    // constructed store
+                    let last_store = Arc::new(Mutex::new(None));
                     debug!(?name, "serving root function");
                     let func = srv
                         .serve_function(
-                            move || new_store(&engine, handler.clone(), max_execution_time),
+                            {
+                                let last_store = Arc::clone(&last_store);
+                                let store_config = store_config.clone();
+                                move || {
+                                    let store = new_store(&engine, handler.clone(), &store_config);
+                                    if let Ok(mut last_store) = last_store.lock() {
+                                        *last_store = Some(store.data().handles());
+                                    }
+                                    store
+                                }
+                            },
                             pre,
                             ty,
                             "",
@@ -553,14 +835,23 @@ where
                     let span = span.clone();
                     invocations.push(Box::pin(func.map_ok(move |(cx, res)| {
                         let events = events.clone();
+                        let meter = take_store_handles(&last_store, cx.recorder);
                         Box::pin(
                             async move {
                                 let res = res.await;
                                 let success = res.is_ok();
+                                let fuel_consumed = meter.and_then(|meter| {
+                                    fuel_consumed(
+                                        fuel,
+                                        &meter,
+                                        res.as_ref().is_err_and(is_out_of_fuel),
+                                    )
+                                });
                                 if let Err(err) =
                                     events.try_send(WrpcServeEvent::DynamicExportReturned {
-                                        context: cx,
+                                        context: cx.context,
                                         success,
+                                        fuel_consumed,
                                     })
                                 {
                                     warn!(
@@ -591,11 +882,24 @@ where
                                 let engine = self.engine.clone();
                                 let handler = handler.clone();
                                 let pre = self.instance_pre.clone();
+                                let last_store = Arc::new(Mutex::new(None));
                                 debug!(?instance_name, ?name, "serving instance function");
                                 let func = srv
                                     .serve_function(
-                                        move || {
-                                            new_store(&engine, handler.clone(), max_execution_time)
+                                        {
+                                            let last_store = Arc::clone(&last_store);
+                                            let store_config = store_config.clone();
+                                            move || {
+                                                let store = new_store(
+                                                    &engine,
+                                                    handler.clone(),
+                                                    &store_config,
+                                                );
+                                                if let Ok(mut last_store) = last_store.lock() {
+                                                    *last_store = Some(store.data().handles());
+                                                }
+                                                store
+                                            }
                                         },
                                         pre,
                                         ty,
@@ -608,14 +912,23 @@ where
                                 let span = span.clone();
                                 invocations.push(Box::pin(func.map_ok(move |(cx, res)| {
                                     let events = events.clone();
+                                    let meter = take_store_handles(&last_store, cx.recorder);
                                     Box::pin(
                                         async move {
                                             let res = res.await;
                                             let success = res.is_ok();
+                                            let fuel_consumed = meter.and_then(|meter| {
+                                                fuel_consumed(
+                                                    fuel,
+                                                    &meter,
+                                                    res.as_ref().is_err_and(is_out_of_fuel),
+                                                )
+                                            });
                                             if let Err(err) = events.try_send(
                                                 WrpcServeEvent::DynamicExportReturned {
-                                                    context: cx,
+                                                    context: cx.context,
                                                     success,
+                                                    fuel_consumed,
                                                 },
                                             ) {
                                                 warn!(
@@ -665,6 +978,39 @@ where
         }
         Ok(invocations)
     }
+
+    /// Replays an invocation captured by [`Component::set_invocation_recording`].
+    ///
+    /// Results of all imports, runtime config, clock and random values observed by the component
+    /// are taken from the recording, so the supplied [`Handler`] is only used for logging and
+    /// egress checks. Returns the encoded results of the replayed invocation, which can be
+    /// compared to [`InvocationRecording::results`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the component does not export the recorded function or the invocation fails
+    #[instrument(level = "debug", skip_all)]
+    pub async fn replay(
+        &self,
+        handler: H,
+        recording: InvocationRecording,
+    ) -> anyhow::Result<RecordedStream> {
+        let instance = recording.instance.clone();
+        let name = recording.name.clone();
+        let replayer = Arc::new(Replayer::new(recording));
+        let srv = ReplayServer::new(Arc::clone(&replayer));
+        let mut component = self.clone();
+        component.recording = RecordingMode::Replay(replayer);
+        let (events, _events_rx) = mpsc::channel(1);
+        let invocations = component.serve_wrpc(&srv, handler, events).await?;
+        let invocation = stream::select_all(invocations)
+            .next()
+            .await
+            .with_context(|| format!("component does not export `{instance}#{name}`"))?
+            .context("failed to accept replayed invocation")?;
+        invocation.await.context("replayed invocation failed")?;
+        Ok(srv.results())
+    }
 }
 
 impl<H> From<Component<H>> for Option<jwt::Claims<jwt::Component>>
@@ -683,7 +1029,7 @@ where
     engine: wasmtime::Engine,
     pre: wasmtime::component::InstancePre<Ctx<H>>,
     handler: H,
-    max_execution_time: Duration,
+    store_config: StoreConfig,
     events: mpsc::Sender<WrpcServeEvent<C>>,
 }
 
@@ -696,7 +1042,7 @@ where
             engine: self.engine.clone(),
             pre: self.pre.clone(),
             handler: self.handler.clone(),
-            max_execution_time: self.max_execution_time,
+            store_config: self.store_config.clone(),
             events: self.events.clone(),
         }
     }
@@ -708,12 +1054,23 @@ struct Ctx<H>
 where
     H: Handler,
 {
-    handler: H,
+    handler: RecordingHandler<H>,
     wasi: WasiCtx,
     http: WasiHttpCtx,
     table: ResourceTable,
     shared_resources: SharedResourceTable,
     timeout: Duration,
+    fuel_consumed: FuelMeter,
+    limits: StoreLimits,
+}
+
+impl<H: Handler> Ctx<H> {
+    fn handles(&self) -> StoreHandles {
+        (
+            Arc::clone(&self.fuel_consumed),
+            self.handler.recording().clone(),
+        )
+    }
 }
 
 impl<H: Handler> WasiView for Ctx<H> {
@@ -727,9 +1084,9 @@ impl<H: Handler> WasiView for Ctx<H> {
 }
 
 impl<H: Handler> WrpcView for Ctx<H> {
-    type Invoke = H;
+    type Invoke = RecordingHandler<H>;
 
-    fn client(&self) -> &H {
+    fn client(&self) -> &RecordingHandler<H> {
         &self.handler
     }
 
@@ -747,3 +1104,261 @@ impl<H: Handler> Debug for Ctx<H> {
         f.debug_struct("Ctx").field("runtime", &"wasmtime").finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::future;
+    use core::task::{Context, Poll};
+
+    use anyhow::{anyhow, bail};
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use tokio::io::{AsyncWrite, ReadBuf};
+    use wasmcloud_core::CallTargetInterface;
+
+    use crate::capability::logging::logging;
+    use crate::capability::{config, secrets};
+
+    /// Stream, which is always empty and discards everything written to it
+    struct NoopStream;
+
+    impl wrpc_transport::Index<Self> for NoopStream {
+        fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+            bail!("cannot index stream with path {path:?}")
+        }
+    }
+
+    impl AsyncRead for NoopStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for NoopStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Handler, which does not link any imports
+    #[derive(Clone)]
+    struct TestHandler;
+
+    impl wrpc_transport::Invoke for TestHandler {
+        type Context = Option<ReplacedInstanceTarget>;
+        type Outgoing = NoopStream;
+        type Incoming = NoopStream;
+
+        fn invoke<P>(
+            &self,
+            _cx: Self::Context,
+            instance: &str,
+            func: &str,
+            _params: Bytes,
+            _paths: impl AsRef<[P]> + Send,
+        ) -> impl Future<Output = anyhow::Result<(Self::Outgoing, Self::Incoming)>> + Send
+        where
+            P: AsRef<[Option<usize>]> + Send + Sync,
+        {
+            future::ready(Err(anyhow!("`{instance}#{func}` is not linked")))
+        }
+    }
+
+    #[async_trait]
+    impl Bus for TestHandler {
+        async fn set_link_name(
+            &self,
+            _link_name: String,
+            _interfaces: Vec<Arc<CallTargetInterface>>,
+        ) -> anyhow::Result<Result<(), String>> {
+            Ok(Ok(()))
+        }
+    }
+
+    #[async_trait]
+    impl Config for TestHandler {
+        async fn get(
+            &self,
+            _key: &str,
+        ) -> anyhow::Result<Result<Option<String>, config::store::Error>> {
+            Ok(Ok(None))
+        }
+
+        async fn get_all(
+            &self,
+        ) -> anyhow::Result<Result<Vec<(String, String)>, config::store::Error>> {
+            Ok(Ok(Vec::default()))
+        }
+    }
+
+    #[async_trait]
+    impl Egress for TestHandler {
+        async fn check_egress(&self, _target: &EgressTarget) -> bool {
+            false
+        }
+    }
+
+    #[async_trait]
+    impl Logging for TestHandler {
+        async fn log(
+            &self,
+            _level: logging::Level,
+            _context: String,
+            _message: String,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Secrets for TestHandler {
+        async fn get(
+            &self,
+            _key: &str,
+        ) -> anyhow::Result<Result<secrets::store::Secret, secrets::store::SecretsError>> {
+            Ok(Err(secrets::store::SecretsError::NotFound))
+        }
+
+        async fn reveal(
+            &self,
+            _secret: secrets::reveal::Secret,
+        ) -> anyhow::Result<secrets::reveal::SecretValue> {
+            bail!("no secrets")
+        }
+    }
+
+    impl InvocationErrorIntrospect for TestHandler {
+        fn invocation_error_kind(&self, _err: &anyhow::Error) -> InvocationErrorKind {
+            InvocationErrorKind::NotFound
+        }
+    }
+
+    const FUEL: u64 = 10_000;
+
+    fn component(rt: &Runtime) -> anyhow::Result<Component<TestHandler>> {
+        let wasm = wat::parse_str(
+            r#"(component
+                (core module $m
+                    (func (export "spin") (loop br 0))
+                    (func (export "noop"))
+                )
+                (core instance $i (instantiate $m))
+                (func (export "spin") (canon lift (core func $i "spin")))
+                (func (export "noop") (canon lift (core func $i "noop")))
+            )"#,
+        )?;
+        let mut component = Component::new(rt, &wasm)?;
+        component.set_max_fuel(Some(FUEL));
+        Ok(component)
+    }
+
+    #[tokio::test]
+    async fn fuel_exhaustion() -> anyhow::Result<()> {
+        let (rt, _epoch) = Runtime::builder().fuel_metering().build()?;
+        let component = component(&rt)?;
+        let config = component.store_config();
+        assert_eq!(config.fuel, Some(FUEL));
+
+        let mut store = new_store(&rt.engine, TestHandler, &config);
+        let instance = component.instance_pre.instantiate_async(&mut store).await?;
+
+        let noop = instance.get_typed_func::<(), ()>(&mut store, "noop")?;
+        noop.call_async(&mut store, ()).await?;
+        noop.post_return_async(&mut store).await?;
+        let consumed = fuel_consumed(config.fuel, &store.data().fuel_consumed, false);
+        assert!(consumed.is_some_and(|consumed| consumed < FUEL));
+
+        let spin = instance.get_typed_func::<(), ()>(&mut store, "spin")?;
+        let err = spin
+            .call_async(&mut store, ())
+            .await
+            .expect_err("fuel budget not enforced");
+        assert!(is_out_of_fuel(&err));
+        assert_eq!(
+            fuel_consumed(config.fuel, &store.data().fuel_consumed, true),
+            Some(FUEL)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memory_limit() -> anyhow::Result<()> {
+        const PAGE_SIZE: u64 = 64 * 1024;
+
+        let (rt, _epoch) = Runtime::builder().build()?;
+        // Like common guest allocators, `grow` traps if the memory cannot be grown
+        let wasm = wat::parse_str(
+            r#"(component
+                (core module $m
+                    (memory 1)
+                    (func (export "grow") (param i32)
+                        (if (i32.eq (memory.grow (local.get 0)) (i32.const -1))
+                            (then unreachable)
+                        )
+                    )
+                )
+                (core instance $i (instantiate $m))
+                (func (export "grow") (param "pages" u32) (canon lift (core func $i "grow")))
+            )"#,
+        )?;
+        let mut component = Component::<TestHandler>::new(&rt, &wasm)?;
+        component.set_max_memory(Some(4 * PAGE_SIZE));
+        let config = component.store_config();
+
+        let mut store = new_store(&rt.engine, TestHandler, &config);
+        let instance = component.instance_pre.instantiate_async(&mut store).await?;
+        let grow = instance.get_typed_func::<(u32,), ()>(&mut store, "grow")?;
+        // Growing up to the limit succeeds
+        grow.call_async(&mut store, (3,)).await?;
+        grow.post_return_async(&mut store).await?;
+        // Exceeding the limit traps the invocation
+        let err = grow
+            .call_async(&mut store, (1,))
+            .await
+            .expect_err("memory limit not enforced");
+        assert_eq!(
+            err.downcast_ref::<wasmtime::Trap>(),
+            Some(&wasmtime::Trap::UnreachableCodeReached)
+        );
+
+        // Instances, which require more memory than allowed to start, fail to instantiate
+        component.set_max_memory(Some(PAGE_SIZE / 2));
+        let config = component.store_config();
+        let mut store = new_store(&rt.engine, TestHandler, &config);
+        assert!(component
+            .instance_pre
+            .instantiate_async(&mut store)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fuel_metering_disabled() -> anyhow::Result<()> {
+        let (rt, _epoch) = Runtime::builder().build()?;
+        let config = component(&rt)?.store_config();
+        // The fuel budget is ignored, since the engine does not consume fuel
+        assert_eq!(config.fuel, None);
+        assert_eq!(fuel_consumed(config.fuel, &AtomicU64::new(42), false), None);
+        assert!(!is_out_of_fuel(&anyhow::anyhow!("out of fuel")));
+        Ok(())
+    }
+}
@@ -0,0 +1,1281 @@
+//! Recording of component invocations and their deterministic replay.
+//!
+//! A recording captures a single invocation of a function exported by a component together with
+//! everything the component observed from the host while handling it: results of wRPC imports
+//! (e.g. `wasi:keyvalue`, `wasi:blobstore` or outgoing `wasi:http` requests), runtime config,
+//! clocks and random values.
+//!
+//! Secrets, `wasi:sockets` and `wasi:filesystem` are not recorded. On replay, secrets are not
+//! available and recorded import invocations are matched by instance and function name in the
+//! order they were made.
+
+use super::{
+    Bus, Config, Egress, EgressTarget, Handler, InvocationErrorIntrospect, InvocationErrorKind,
+    Logging, ReplacedInstanceTarget, Secrets, StdioStream,
+};
+
+use crate::capability::logging::logging;
+use crate::capability::{config, secrets, CallTargetInterface};
+
+use core::fmt;
+use core::future::{self, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Instant, SystemTime};
+
+use anyhow::{bail, Context as _};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream, Stream, TryStreamExt as _};
+use serde::{Deserialize, Serialize};
+use serde_with::base64::Base64;
+use serde_with::{serde_as, DisplayFromStr};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use wasmtime_wasi::{HostMonotonicClock, HostWallClock, RngCore, WasiCtxBuilder};
+use wrpc_transport::{Index, Serve};
+
+/// Byte stream of an invocation, consisting of the data of the root stream and data of nested
+/// streams used to transfer async values
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RecordedStream {
+    /// Data transferred on the root stream
+    #[serde_as(as = "Base64")]
+    #[serde(default)]
+    pub data: Vec<u8>,
+    /// Data transferred on nested streams keyed by their path, e.g. `0.1`
+    #[serde_as(as = "BTreeMap<_, Base64>")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nested: BTreeMap<String, Vec<u8>>,
+}
+
+/// Error returned by an import invocation
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RecordedError {
+    /// Error message
+    pub message: String,
+    /// Classification of the error
+    pub kind: InvocationErrorKind,
+}
+
+/// wRPC import invocation made by a component
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RecordedImport {
+    /// Invoked instance, e.g. `wrpc:keyvalue/store@0.2.0-draft`
+    pub instance: String,
+    /// Invoked function, e.g. `get`
+    pub name: String,
+    /// Encoded parameters
+    #[serde_as(as = "Base64")]
+    pub params: Vec<u8>,
+    /// Encoded results received
+    #[serde(default)]
+    pub results: RecordedStream,
+    /// Error returned by the invocation, if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RecordedError>,
+}
+
+/// Recording of a single invocation of a function exported by a component
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InvocationRecording {
+    /// Invoked instance, empty for root functions
+    pub instance: String,
+    /// Invoked function
+    pub name: String,
+    /// Encoded parameters of the invocation
+    pub params: RecordedStream,
+    /// Encoded results of the invocation
+    #[serde(default)]
+    pub results: RecordedStream,
+    /// wRPC import invocations in the order they were made
+    #[serde(default)]
+    pub imports: Vec<RecordedImport>,
+    /// Runtime config values read by the component
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+    /// Values returned by `wasi:clocks/wall-clock.now`
+    #[serde(default)]
+    pub wall_clock: Vec<Duration>,
+    /// Values returned by `wasi:clocks/monotonic-clock.now`
+    #[serde(default)]
+    pub monotonic_clock: Vec<u64>,
+    /// Bytes returned by `wasi:random/random`
+    #[serde_as(as = "Base64")]
+    #[serde(default)]
+    pub random: Vec<u8>,
+    /// Bytes returned by `wasi:random/insecure`
+    #[serde_as(as = "Base64")]
+    #[serde(default)]
+    pub insecure_random: Vec<u8>,
+    /// Value returned by `wasi:random/insecure-seed`
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default)]
+    pub insecure_random_seed: u128,
+}
+
+/// Returns the key of a nested stream at `path` within a [`RecordedStream`]
+fn path_key(path: &[usize]) -> String {
+    path.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Recording mode of a [Component](super::Component)
+#[derive(Clone, Debug, Default)]
+pub(crate) enum RecordingMode {
+    /// Invocations are not recorded
+    #[default]
+    Disabled,
+    /// Invocations are recorded and sent on the channel once finished
+    Record(mpsc::Sender<InvocationRecording>),
+    /// A recorded invocation is being replayed
+    Replay(Arc<Replayer>),
+}
+
+struct RecorderState {
+    recording: InvocationRecording,
+    sink: mpsc::Sender<InvocationRecording>,
+}
+
+impl Drop for RecorderState {
+    fn drop(&mut self) {
+        let recording = std::mem::take(&mut self.recording);
+        if let Err(err) = self.sink.try_send(recording) {
+            warn!(?err, "failed to send invocation recording");
+        }
+    }
+}
+
+/// Recorder of a single invocation, the recording is sent once all handles to it are dropped
+#[derive(Clone)]
+pub(crate) struct Recorder(Arc<Mutex<RecorderState>>);
+
+impl Recorder {
+    fn new(instance: &str, name: &str, sink: mpsc::Sender<InvocationRecording>) -> Self {
+        Self(Arc::new(Mutex::new(RecorderState {
+            recording: InvocationRecording {
+                instance: instance.into(),
+                name: name.into(),
+                ..Default::default()
+            },
+            sink,
+        })))
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut InvocationRecording) -> T) -> Option<T> {
+        let mut state = self.0.lock().ok()?;
+        Some(f(&mut state.recording))
+    }
+}
+
+/// Stream of an [`InvocationRecording`] being recorded
+#[derive(Clone, Copy, Debug)]
+enum StreamTarget {
+    Params,
+    Results,
+    Import(usize),
+}
+
+/// Records data transferred on a single (possibly nested) invocation stream
+#[derive(Clone)]
+pub(crate) struct StreamRecorder {
+    recorder: Recorder,
+    target: StreamTarget,
+    path: Vec<usize>,
+}
+
+impl StreamRecorder {
+    fn new(recorder: Recorder, target: StreamTarget) -> Self {
+        Self {
+            recorder,
+            target,
+            path: Vec::default(),
+        }
+    }
+
+    fn index(&self, path: &[usize]) -> Self {
+        let mut nested = self.path.clone();
+        nested.extend_from_slice(path);
+        Self {
+            recorder: self.recorder.clone(),
+            target: self.target,
+            path: nested,
+        }
+    }
+
+    fn record(&self, buf: &[u8]) {
+        if buf.is_empty() {
+            return;
+        }
+        self.recorder.update(|recording| {
+            let stream = match self.target {
+                StreamTarget::Params => &mut recording.params,
+                StreamTarget::Results => &mut recording.results,
+                StreamTarget::Import(i) => match recording.imports.get_mut(i) {
+                    Some(import) => &mut import.results,
+                    None => return,
+                },
+            };
+            if self.path.is_empty() {
+                stream.data.extend_from_slice(buf);
+            } else {
+                stream
+                    .nested
+                    .entry(path_key(&self.path))
+                    .or_default()
+                    .extend_from_slice(buf);
+            }
+        });
+    }
+}
+
+/// Incoming byte stream replayed from a [`RecordedStream`]
+pub(crate) struct ReplayStream {
+    stream: Arc<RecordedStream>,
+    key: Option<String>,
+    path: Vec<usize>,
+    pos: usize,
+}
+
+impl ReplayStream {
+    fn new(stream: Arc<RecordedStream>) -> Self {
+        Self {
+            stream,
+            key: None,
+            path: Vec::default(),
+            pos: 0,
+        }
+    }
+}
+
+impl Index<Self> for ReplayStream {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        let mut nested = self.path.clone();
+        nested.extend_from_slice(path);
+        Ok(Self {
+            stream: Arc::clone(&self.stream),
+            key: Some(path_key(&nested)),
+            path: nested,
+            pos: 0,
+        })
+    }
+}
+
+impl AsyncRead for ReplayStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let data = match &this.key {
+            None => this.stream.data.as_slice(),
+            Some(key) => this.stream.nested.get(key).map_or(&[][..], Vec::as_slice),
+        };
+        let data = data.get(this.pos..).unwrap_or_default();
+        let n = data.len().min(buf.remaining());
+        buf.put_slice(&data[..n]);
+        this.pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Outgoing byte stream, which captures the data written into a [`RecordedStream`], if any
+#[derive(Default)]
+pub(crate) struct ReplaySink {
+    stream: Option<Arc<Mutex<RecordedStream>>>,
+    path: Vec<usize>,
+}
+
+impl Index<Self> for ReplaySink {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        let mut nested = self.path.clone();
+        nested.extend_from_slice(path);
+        Ok(Self {
+            stream: self.stream.clone(),
+            path: nested,
+        })
+    }
+}
+
+impl AsyncWrite for ReplaySink {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(Ok(mut stream)) = self.stream.as_ref().map(|stream| stream.lock()) {
+            if self.path.is_empty() {
+                stream.data.extend_from_slice(buf);
+            } else {
+                stream
+                    .nested
+                    .entry(path_key(&self.path))
+                    .or_default()
+                    .extend_from_slice(buf);
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Incoming byte stream, which is either received from the transport, optionally recording
+/// all data read, or replayed from a recording
+pub(crate) enum RecordedIncoming<T> {
+    Live(T, Option<StreamRecorder>),
+    Replay(ReplayStream),
+}
+
+impl<T: Index<T>> Index<Self> for RecordedIncoming<T> {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        match self {
+            Self::Live(stream, recorder) => Ok(Self::Live(
+                stream.index(path)?,
+                recorder.as_ref().map(|recorder| recorder.index(path)),
+            )),
+            Self::Replay(stream) => stream.index(path).map(Self::Replay),
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for RecordedIncoming<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Live(stream, None) => Pin::new(stream).poll_read(cx, buf),
+            Self::Live(stream, Some(recorder)) => {
+                let start = buf.filled().len();
+                let res = Pin::new(stream).poll_read(cx, buf);
+                if let Poll::Ready(Ok(())) = res {
+                    recorder.record(&buf.filled()[start..]);
+                }
+                res
+            }
+            Self::Replay(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+/// Outgoing byte stream, which is either sent on the transport, optionally recording all
+/// data written, or discarded on replay
+pub(crate) enum RecordedOutgoing<T> {
+    Live(T, Option<StreamRecorder>),
+    Replay(ReplaySink),
+}
+
+impl<T: Index<T>> Index<Self> for RecordedOutgoing<T> {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        match self {
+            Self::Live(stream, recorder) => Ok(Self::Live(
+                stream.index(path)?,
+                recorder.as_ref().map(|recorder| recorder.index(path)),
+            )),
+            Self::Replay(stream) => stream.index(path).map(Self::Replay),
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for RecordedOutgoing<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Live(stream, None) => Pin::new(stream).poll_write(cx, buf),
+            Self::Live(stream, Some(recorder)) => {
+                let res = Pin::new(stream).poll_write(cx, buf);
+                if let Poll::Ready(Ok(n)) = res {
+                    recorder.record(&buf[..n]);
+                }
+                res
+            }
+            Self::Replay(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Live(stream, _) => Pin::new(stream).poll_flush(cx),
+            Self::Replay(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Live(stream, _) => Pin::new(stream).poll_shutdown(cx),
+            Self::Replay(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Invocation context of [`RecordingServer`]
+pub(crate) struct RecordedContext<C> {
+    /// Context of the underlying transport
+    pub(crate) context: C,
+    /// Recorder of the invocation, if recording is enabled
+    pub(crate) recorder: Option<Recorder>,
+}
+
+/// [`Serve`] implementation, which records accepted invocations if a sink is configured
+pub(crate) struct RecordingServer<'a, S> {
+    pub(crate) srv: &'a S,
+    pub(crate) sink: Option<mpsc::Sender<InvocationRecording>>,
+}
+
+impl<S: Serve> Serve for RecordingServer<'_, S> {
+    type Context = RecordedContext<S::Context>;
+    type Outgoing = RecordedOutgoing<S::Outgoing>;
+    type Incoming = RecordedIncoming<S::Incoming>;
+
+    async fn serve(
+        &self,
+        instance: &str,
+        func: &str,
+        paths: impl Into<Arc<[Box<[Option<usize>]>]>> + Send,
+    ) -> anyhow::Result<
+        impl Stream<Item = anyhow::Result<(Self::Context, Self::Outgoing, Self::Incoming)>>
+            + Send
+            + 'static,
+    > {
+        let invocations = self.srv.serve(instance, func, paths).await?;
+        let sink = self.sink.clone();
+        let instance = instance.to_string();
+        let func = func.to_string();
+        Ok(invocations.map_ok(move |(context, tx, rx)| {
+            let recorder = sink
+                .clone()
+                .map(|sink| Recorder::new(&instance, &func, sink));
+            let stream = |target| {
+                recorder
+                    .clone()
+                    .map(|recorder| StreamRecorder::new(recorder, target))
+            };
+            let tx = RecordedOutgoing::Live(tx, stream(StreamTarget::Results));
+            let rx = RecordedIncoming::Live(rx, stream(StreamTarget::Params));
+            (RecordedContext { context, recorder }, tx, rx)
+        }))
+    }
+}
+
+/// [`Serve`] implementation, which yields the recorded invocation exactly once and captures
+/// the results written
+pub(crate) struct ReplayServer {
+    recording: Arc<Replayer>,
+    results: Arc<Mutex<RecordedStream>>,
+}
+
+impl ReplayServer {
+    pub(crate) fn new(recording: Arc<Replayer>) -> Self {
+        Self {
+            recording,
+            results: Arc::default(),
+        }
+    }
+
+    /// Returns the results written so far
+    pub(crate) fn results(&self) -> RecordedStream {
+        self.results
+            .lock()
+            .map(|results| results.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Serve for ReplayServer {
+    type Context = ();
+    type Outgoing = ReplaySink;
+    type Incoming = ReplayStream;
+
+    fn serve(
+        &self,
+        instance: &str,
+        func: &str,
+        _paths: impl Into<Arc<[Box<[Option<usize>]>]>> + Send,
+    ) -> impl Future<
+        Output = anyhow::Result<
+            impl Stream<Item = anyhow::Result<(Self::Context, Self::Outgoing, Self::Incoming)>>
+                + Send
+                + 'static,
+        >,
+    > + Send {
+        let InvocationRecording {
+            instance: recorded_instance,
+            name,
+            params,
+            ..
+        } = &self.recording.recording;
+        let invocation = (instance == recorded_instance && func == name).then(|| {
+            let tx = ReplaySink {
+                stream: Some(Arc::clone(&self.results)),
+                path: Vec::default(),
+            };
+            let rx = ReplayStream::new(Arc::new(params.clone()));
+            Ok(((), tx, rx))
+        });
+        future::ready(Ok(stream::iter(invocation)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    imports: Vec<bool>,
+    wall_clock: usize,
+    monotonic_clock: usize,
+    random: usize,
+    insecure_random: usize,
+}
+
+/// Replays values observed by a component from an [`InvocationRecording`]
+#[derive(Debug)]
+pub(crate) struct Replayer {
+    recording: InvocationRecording,
+    state: Mutex<ReplayState>,
+}
+
+impl Replayer {
+    pub(crate) fn new(recording: InvocationRecording) -> Self {
+        let state = ReplayState {
+            imports: vec![false; recording.imports.len()],
+            ..ReplayState::default()
+        };
+        Self {
+            recording,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Returns the first import invocation of `func` on `instance` not replayed yet
+    fn next_import(
+        &self,
+        instance: &str,
+        func: &str,
+        params: &[u8],
+    ) -> anyhow::Result<&RecordedImport> {
+        let Ok(mut state) = self.state.lock() else {
+            bail!("replay state lock poisoned");
+        };
+        let (i, import) = self
+            .recording
+            .imports
+            .iter()
+            .enumerate()
+            .find(|(i, import)| {
+                !state.imports[*i] && import.instance == instance && import.name == func
+            })
+            .with_context(|| format!("invocation of `{instance}#{func}` was not recorded"))?;
+        state.imports[i] = true;
+        if import.params != params {
+            warn!(
+                instance,
+                func, "replayed invocation parameters differ from the recording"
+            );
+        }
+        Ok(import)
+    }
+
+    /// Returns the next recorded value or the last one, if all values were replayed already
+    fn next<T: Copy + Default>(
+        values: &[T],
+        pos: impl FnOnce(&mut ReplayState) -> &mut usize,
+        state: &Mutex<ReplayState>,
+    ) -> T {
+        let Ok(mut state) = state.lock() else {
+            return T::default();
+        };
+        let pos = pos(&mut state);
+        let v = values
+            .get(*pos)
+            .or_else(|| values.last())
+            .copied()
+            .unwrap_or_default();
+        *pos = pos.saturating_add(1);
+        v
+    }
+
+    fn fill_random(&self, insecure: bool, buf: &mut [u8]) {
+        let Ok(mut state) = self.state.lock() else {
+            buf.fill(0);
+            return;
+        };
+        let (recorded, pos) = if insecure {
+            (&self.recording.insecure_random, &mut state.insecure_random)
+        } else {
+            (&self.recording.random, &mut state.random)
+        };
+        let recorded = recorded.get(*pos..).unwrap_or_default();
+        let n = recorded.len().min(buf.len());
+        buf[..n].copy_from_slice(&recorded[..n]);
+        if n < buf.len() {
+            debug!("recorded random bytes exhausted, returning zeroes");
+            buf[n..].fill(0);
+        }
+        *pos = pos.saturating_add(n);
+    }
+}
+
+/// State of a [`Recorder`] attached to a single [`wasmtime::Store`]
+pub(crate) struct RecorderSlot {
+    recorder: OnceLock<Recorder>,
+    insecure_random_seed: u128,
+}
+
+/// Recording state of a single [`wasmtime::Store`]
+#[derive(Clone, Default)]
+pub(crate) enum StoreRecording {
+    #[default]
+    Disabled,
+    Record(Arc<RecorderSlot>),
+    Replay(Arc<Replayer>),
+}
+
+impl StoreRecording {
+    pub(crate) fn new(mode: &RecordingMode) -> Self {
+        match mode {
+            RecordingMode::Disabled => Self::Disabled,
+            RecordingMode::Record(..) => Self::Record(Arc::new(RecorderSlot {
+                recorder: OnceLock::new(),
+                insecure_random_seed: rand::random(),
+            })),
+            RecordingMode::Replay(replayer) => Self::Replay(Arc::clone(replayer)),
+        }
+    }
+
+    /// Attaches the recorder of the invocation handled by the store, if any
+    pub(crate) fn attach(&self, recorder: Option<Recorder>) {
+        let (Self::Record(slot), Some(recorder)) = (self, recorder) else {
+            return;
+        };
+        recorder.update(|recording| recording.insecure_random_seed = slot.insecure_random_seed);
+        if slot.recorder.set(recorder).is_err() {
+            warn!("store is already recording an invocation");
+        }
+    }
+
+    fn recorder(&self) -> Option<&Recorder> {
+        match self {
+            Self::Record(slot) => slot.recorder.get(),
+            Self::Disabled | Self::Replay(..) => None,
+        }
+    }
+
+    /// Installs recording or replaying clocks and random number generators
+    pub(crate) fn configure_wasi(&self, wasi: &mut WasiCtxBuilder) {
+        let insecure_random_seed = match self {
+            Self::Disabled => return,
+            Self::Record(slot) => slot.insecure_random_seed,
+            Self::Replay(replayer) => replayer.recording.insecure_random_seed,
+        };
+        wasi.wall_clock(RecordingClock::new(self.clone()))
+            .monotonic_clock(RecordingClock::new(self.clone()))
+            .secure_random(RecordingRng::new(self.clone(), false))
+            .insecure_random(RecordingRng::new(self.clone(), true))
+            .insecure_random_seed(insecure_random_seed);
+    }
+}
+
+/// Wall and monotonic clock, which records the values returned or replays them
+struct RecordingClock {
+    recording: StoreRecording,
+    start: Instant,
+}
+
+impl RecordingClock {
+    fn new(recording: StoreRecording) -> Self {
+        Self {
+            recording,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl HostWallClock for RecordingClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        if let StoreRecording::Replay(replayer) = &self.recording {
+            return Replayer::next(
+                &replayer.recording.wall_clock,
+                |state| &mut state.wall_clock,
+                &replayer.state,
+            );
+        }
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        if let Some(recorder) = self.recording.recorder() {
+            recorder.update(|recording| recording.wall_clock.push(now));
+        }
+        now
+    }
+}
+
+impl HostMonotonicClock for RecordingClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        if let StoreRecording::Replay(replayer) = &self.recording {
+            return Replayer::next(
+                &replayer.recording.monotonic_clock,
+                |state| &mut state.monotonic_clock,
+                &replayer.state,
+            );
+        }
+        let now = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        if let Some(recorder) = self.recording.recorder() {
+            recorder.update(|recording| recording.monotonic_clock.push(now));
+        }
+        now
+    }
+}
+
+/// Random number generator, which records the bytes returned or replays them
+struct RecordingRng {
+    recording: StoreRecording,
+    insecure: bool,
+    rng: Box<dyn RngCore + Send>,
+}
+
+impl RecordingRng {
+    fn new(recording: StoreRecording, insecure: bool) -> Self {
+        Self {
+            recording,
+            insecure,
+            rng: wasmtime_wasi::thread_rng(),
+        }
+    }
+}
+
+impl RngCore for RecordingRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let StoreRecording::Replay(replayer) = &self.recording {
+            replayer.fill_random(self.insecure, dest);
+            return;
+        }
+        self.rng.fill_bytes(dest);
+        if let Some(recorder) = self.recording.recorder() {
+            recorder.update(|recording| {
+                if self.insecure {
+                    recording.insecure_random.extend_from_slice(dest);
+                } else {
+                    recording.random.extend_from_slice(dest);
+                }
+            });
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Error returned by a replayed import invocation
+#[derive(Debug)]
+struct ReplayedError(RecordedError);
+
+impl fmt::Display for ReplayedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.message)
+    }
+}
+
+impl std::error::Error for ReplayedError {}
+
+/// [`Handler`] of a single [`wasmtime::Store`], which records the values returned by the
+/// wrapped handler or replays them
+#[derive(Clone)]
+pub(crate) struct RecordingHandler<H> {
+    inner: H,
+    recording: StoreRecording,
+}
+
+impl<H> RecordingHandler<H> {
+    pub(crate) fn new(inner: H, recording: StoreRecording) -> Self {
+        Self { inner, recording }
+    }
+
+    pub(crate) fn recording(&self) -> &StoreRecording {
+        &self.recording
+    }
+}
+
+impl<H: Handler> wrpc_transport::Invoke for RecordingHandler<H> {
+    type Context = Option<ReplacedInstanceTarget>;
+    type Outgoing = RecordedOutgoing<H::Outgoing>;
+    type Incoming = RecordedIncoming<H::Incoming>;
+
+    async fn invoke<P>(
+        &self,
+        cx: Self::Context,
+        instance: &str,
+        func: &str,
+        params: Bytes,
+        paths: impl AsRef<[P]> + Send,
+    ) -> anyhow::Result<(Self::Outgoing, Self::Incoming)>
+    where
+        P: AsRef<[Option<usize>]> + Send + Sync,
+    {
+        if let StoreRecording::Replay(replayer) = &self.recording {
+            let import = replayer.next_import(instance, func, &params)?;
+            if let Some(err) = &import.error {
+                return Err(ReplayedError(err.clone()).into());
+            }
+            let rx = ReplayStream::new(Arc::new(import.results.clone()));
+            return Ok((
+                RecordedOutgoing::Replay(ReplaySink::default()),
+                RecordedIncoming::Replay(rx),
+            ));
+        }
+        let Some(recorder) = self.recording.recorder() else {
+            let (tx, rx) = self.inner.invoke(cx, instance, func, params, paths).await?;
+            return Ok((
+                RecordedOutgoing::Live(tx, None),
+                RecordedIncoming::Live(rx, None),
+            ));
+        };
+        let res = self
+            .inner
+            .invoke(cx, instance, func, params.clone(), paths)
+            .await;
+        let error = res.as_ref().err().map(|err| RecordedError {
+            message: format!("{err:#}"),
+            kind: self.inner.invocation_error_kind(err),
+        });
+        let i = recorder.update(|recording| {
+            recording.imports.push(RecordedImport {
+                instance: instance.into(),
+                name: func.into(),
+                params: params.into(),
+                results: RecordedStream::default(),
+                error,
+            });
+            recording.imports.len() - 1
+        });
+        let (tx, rx) = res?;
+        let recorder = i.map(|i| StreamRecorder::new(recorder.clone(), StreamTarget::Import(i)));
+        Ok((
+            RecordedOutgoing::Live(tx, None),
+            RecordedIncoming::Live(rx, recorder),
+        ))
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Bus for RecordingHandler<H> {
+    async fn set_link_name(
+        &self,
+        link_name: String,
+        interfaces: Vec<Arc<CallTargetInterface>>,
+    ) -> anyhow::Result<Result<(), String>> {
+        self.inner.set_link_name(link_name, interfaces).await
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Config for RecordingHandler<H> {
+    async fn get(&self, key: &str) -> anyhow::Result<Result<Option<String>, config::store::Error>> {
+        if let StoreRecording::Replay(replayer) = &self.recording {
+            return Ok(Ok(replayer.recording.config.get(key).cloned()));
+        }
+        let res = Config::get(&self.inner, key).await;
+        if let (Some(recorder), Ok(Ok(Some(value)))) = (self.recording.recorder(), &res) {
+            recorder.update(|recording| recording.config.insert(key.into(), value.clone()));
+        }
+        res
+    }
+
+    async fn get_all(&self) -> anyhow::Result<Result<Vec<(String, String)>, config::store::Error>> {
+        if let StoreRecording::Replay(replayer) = &self.recording {
+            return Ok(Ok(replayer.recording.config.clone().into_iter().collect()));
+        }
+        let res = self.inner.get_all().await;
+        if let (Some(recorder), Ok(Ok(entries))) = (self.recording.recorder(), &res) {
+            recorder.update(|recording| recording.config.extend(entries.iter().cloned()));
+        }
+        res
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Egress for RecordingHandler<H> {
+    async fn check_egress(&self, target: &EgressTarget) -> bool {
+        self.inner.check_egress(target).await
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Logging for RecordingHandler<H> {
+    async fn log(
+        &self,
+        level: logging::Level,
+        context: String,
+        message: String,
+    ) -> anyhow::Result<()> {
+        self.inner.log(level, context, message).await
+    }
+
+    fn log_output(&self, stream: StdioStream, line: &str) {
+        self.inner.log_output(stream, line);
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Secrets for RecordingHandler<H> {
+    async fn get(
+        &self,
+        key: &str,
+    ) -> anyhow::Result<Result<secrets::store::Secret, secrets::store::SecretsError>> {
+        if let StoreRecording::Replay(..) = self.recording {
+            return Ok(Err(secrets::store::SecretsError::Upstream(
+                "secrets are not available during replay".into(),
+            )));
+        }
+        Secrets::get(&self.inner, key).await
+    }
+
+    async fn reveal(
+        &self,
+        secret: secrets::reveal::Secret,
+    ) -> anyhow::Result<secrets::reveal::SecretValue> {
+        if let StoreRecording::Replay(..) = self.recording {
+            bail!("secrets are not available during replay");
+        }
+        self.inner.reveal(secret).await
+    }
+}
+
+impl<H: Handler> InvocationErrorIntrospect for RecordingHandler<H> {
+    fn invocation_error_kind(&self, err: &anyhow::Error) -> InvocationErrorKind {
+        if let Some(ReplayedError(err)) = err.chain().find_map(|err| err.downcast_ref()) {
+            return err.kind;
+        }
+        self.inner.invocation_error_kind(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::anyhow;
+    use futures::StreamExt as _;
+    use tokio::io::AsyncReadExt as _;
+    use wrpc_transport::Invoke as _;
+
+    const INSTANCE: &str = "wasi:http/incoming-handler";
+    const FUNC: &str = "handle";
+
+    /// Handler serving `wasi:keyvalue/store.get` and a single config value, if `linked`
+    #[derive(Clone)]
+    struct TestHandler {
+        linked: bool,
+    }
+
+    impl wrpc_transport::Invoke for TestHandler {
+        type Context = Option<ReplacedInstanceTarget>;
+        type Outgoing = ReplaySink;
+        type Incoming = ReplayStream;
+
+        fn invoke<P>(
+            &self,
+            _cx: Self::Context,
+            instance: &str,
+            func: &str,
+            params: Bytes,
+            _paths: impl AsRef<[P]> + Send,
+        ) -> impl Future<Output = anyhow::Result<(Self::Outgoing, Self::Incoming)>> + Send
+        where
+            P: AsRef<[Option<usize>]> + Send + Sync,
+        {
+            if !self.linked || (instance, func) != ("wasi:keyvalue/store", "get") {
+                return future::ready(Err(anyhow!("`{instance}#{func}` is not linked")));
+            }
+            let results = RecordedStream {
+                data: [b"value:".as_slice(), &params].concat(),
+                ..Default::default()
+            };
+            future::ready(Ok((
+                ReplaySink::default(),
+                ReplayStream::new(Arc::new(results)),
+            )))
+        }
+    }
+
+    #[async_trait]
+    impl Bus for TestHandler {
+        async fn set_link_name(
+            &self,
+            _link_name: String,
+            _interfaces: Vec<Arc<CallTargetInterface>>,
+        ) -> anyhow::Result<Result<(), String>> {
+            Ok(Ok(()))
+        }
+    }
+
+    #[async_trait]
+    impl Config for TestHandler {
+        async fn get(
+            &self,
+            key: &str,
+        ) -> anyhow::Result<Result<Option<String>, config::store::Error>> {
+            Ok(Ok(
+                (self.linked && key == "greeting").then(|| "hello".into())
+            ))
+        }
+
+        async fn get_all(
+            &self,
+        ) -> anyhow::Result<Result<Vec<(String, String)>, config::store::Error>> {
+            Ok(Ok(Vec::default()))
+        }
+    }
+
+    #[async_trait]
+    impl Egress for TestHandler {
+        async fn check_egress(&self, _target: &EgressTarget) -> bool {
+            false
+        }
+    }
+
+    #[async_trait]
+    impl Logging for TestHandler {
+        async fn log(
+            &self,
+            _level: logging::Level,
+            _context: String,
+            _message: String,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Secrets for TestHandler {
+        async fn get(
+            &self,
+            _key: &str,
+        ) -> anyhow::Result<Result<secrets::store::Secret, secrets::store::SecretsError>> {
+            Ok(Err(secrets::store::SecretsError::NotFound))
+        }
+
+        async fn reveal(
+            &self,
+            _secret: secrets::reveal::Secret,
+        ) -> anyhow::Result<secrets::reveal::SecretValue> {
+            bail!("no secrets")
+        }
+    }
+
+    impl InvocationErrorIntrospect for TestHandler {
+        fn invocation_error_kind(&self, _err: &anyhow::Error) -> InvocationErrorKind {
+            InvocationErrorKind::NotFound
+        }
+    }
+
+    async fn get(
+        handler: &RecordingHandler<TestHandler>,
+        instance: &str,
+        func: &str,
+        key: &'static str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (_, mut rx) = handler
+            .invoke(
+                None,
+                instance,
+                func,
+                Bytes::from(key),
+                Vec::<Box<[Option<usize>]>>::default(),
+            )
+            .await?;
+        let mut buf = Vec::default();
+        rx.read_to_end(&mut buf).await?;
+        Ok(buf)
+    }
+
+    /// Values observed by an invocation
+    #[derive(Debug, PartialEq)]
+    struct Observed {
+        value: Vec<u8>,
+        greeting: Option<String>,
+        wall_clock: Duration,
+        monotonic_clock: u64,
+        random: [u8; 16],
+    }
+
+    async fn observe(recording: &StoreRecording, linked: bool) -> anyhow::Result<Observed> {
+        let handler = RecordingHandler::new(TestHandler { linked }, recording.clone());
+        let value = get(&handler, "wasi:keyvalue/store", "get", "key").await?;
+        let greeting = Config::get(&handler, "greeting")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let clock = RecordingClock::new(recording.clone());
+        let mut random = [0; 16];
+        RecordingRng::new(recording.clone(), false).fill_bytes(&mut random);
+        Ok(Observed {
+            value,
+            greeting,
+            wall_clock: HostWallClock::now(&clock),
+            monotonic_clock: HostMonotonicClock::now(&clock),
+            random,
+        })
+    }
+
+    #[tokio::test]
+    async fn record_replay_roundtrip() -> anyhow::Result<()> {
+        let (tx, mut rx) = mpsc::channel(1);
+        let recording = StoreRecording::new(&RecordingMode::Record(tx.clone()));
+        recording.attach(Some(Recorder::new(INSTANCE, FUNC, tx)));
+        let recorded = observe(&recording, true).await?;
+        assert_eq!(recorded.value, b"value:key");
+        assert_eq!(recorded.greeting.as_deref(), Some("hello"));
+        drop(recording);
+
+        // The recording is sent once the store is dropped and survives being written to a file
+        let recording = rx.try_recv()?;
+        let recording: InvocationRecording =
+            serde_json::from_slice(&serde_json::to_vec(&recording)?)?;
+        assert_eq!(recording.instance, INSTANCE);
+        assert_eq!(recording.name, FUNC);
+        assert_eq!(recording.imports.len(), 1);
+        assert_eq!(recording.imports[0].params, b"key");
+        assert_eq!(recording.imports[0].results.data, b"value:key");
+        assert_eq!(recording.wall_clock, [recorded.wall_clock]);
+        assert_eq!(recording.monotonic_clock, [recorded.monotonic_clock]);
+        assert_eq!(recording.random, recorded.random);
+
+        // On replay, nothing is taken from the handler, which has no links or config
+        let replayer = Arc::new(Replayer::new(recording));
+        let observed = observe(&StoreRecording::Replay(Arc::clone(&replayer)), false).await?;
+        assert_eq!(observed, recorded);
+
+        let srv = ReplayServer::new(replayer);
+        let invocations: Vec<_> = srv
+            .serve(INSTANCE, FUNC, Vec::<Box<[Option<usize>]>>::default())
+            .await?
+            .collect()
+            .await;
+        assert_eq!(invocations.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replay_diverging_invocation() -> anyhow::Result<()> {
+        let replayer = Arc::new(Replayer::new(InvocationRecording {
+            instance: INSTANCE.into(),
+            name: FUNC.into(),
+            imports: vec![
+                RecordedImport {
+                    instance: "wasi:keyvalue/store".into(),
+                    name: "get".into(),
+                    params: b"key".to_vec(),
+                    results: RecordedStream {
+                        data: b"value".to_vec(),
+                        ..Default::default()
+                    },
+                    error: None,
+                },
+                RecordedImport {
+                    instance: "wasi:keyvalue/store".into(),
+                    name: "set".into(),
+                    params: b"key".to_vec(),
+                    results: RecordedStream::default(),
+                    error: Some(RecordedError {
+                        message: "permission denied".into(),
+                        kind: InvocationErrorKind::Trap,
+                    }),
+                },
+            ],
+            wall_clock: vec![Duration::from_secs(1)],
+            random: vec![1, 2],
+            ..Default::default()
+        }));
+        let recording = StoreRecording::Replay(Arc::clone(&replayer));
+        let handler = RecordingHandler::new(TestHandler { linked: true }, recording.clone());
+
+        // Parameters differing from the recording still replay the recorded results
+        assert_eq!(
+            get(&handler, "wasi:keyvalue/store", "get", "other").await?,
+            b"value"
+        );
+        // Each recorded import invocation is replayed once only
+        let err = get(&handler, "wasi:keyvalue/store", "get", "key")
+            .await
+            .expect_err("replayed import invocation twice");
+        assert!(err.to_string().contains("was not recorded"));
+        assert!(get(
+            &handler,
+            "wasi:blobstore/blobstore",
+            "get-container-data",
+            "key"
+        )
+        .await
+        .is_err());
+        // Recorded errors are replayed with their classification
+        let err = get(&handler, "wasi:keyvalue/store", "set", "key")
+            .await
+            .expect_err("recorded error not replayed");
+        assert_eq!(err.to_string(), "permission denied");
+        assert_eq!(
+            handler.invocation_error_kind(&err),
+            InvocationErrorKind::Trap
+        );
+
+        // Exhausted clocks repeat the last value and exhausted random bytes are zeroed
+        let clock = RecordingClock::new(recording.clone());
+        assert_eq!(HostWallClock::now(&clock), Duration::from_secs(1));
+        assert_eq!(HostWallClock::now(&clock), Duration::from_secs(1));
+        assert_eq!(HostMonotonicClock::now(&clock), 0);
+        let mut random = [0xff; 4];
+        RecordingRng::new(recording, false).fill_bytes(&mut random);
+        assert_eq!(random, [1, 2, 0, 0]);
+
+        // Invocations of other functions than the recorded one are not served
+        let srv = ReplayServer::new(replayer);
+        let invocations: Vec<_> = srv
+            .serve(INSTANCE, "other", Vec::<Box<[Option<usize>]>>::default())
+            .await?
+            .collect()
+            .await;
+        assert!(invocations.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use super::Handler;
+
+use core::fmt;
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use wasmtime_wasi::{HostOutputStream, StdoutStream, StreamResult, Subscribe};
+
+/// Maximum length of a single line of component output, longer lines are split
+const MAX_LINE_LENGTH: usize = 8192;
+
+/// Standard output stream of a component
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StdioStream {
+    /// `stdout`
+    Stdout,
+    /// `stderr`
+    Stderr,
+}
+
+impl fmt::Display for StdioStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdout => f.write_str("stdout"),
+            Self::Stderr => f.write_str("stderr"),
+        }
+    }
+}
+
+/// Line buffer of a single output stream of a component instance
+struct LineBuffer<H: Handler> {
+    handler: H,
+    stream: StdioStream,
+    buf: Vec<u8>,
+}
+
+impl<H: Handler> LineBuffer<H> {
+    fn emit(&mut self, end: usize) {
+        let line = String::from_utf8_lossy(&self.buf[..end]);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        self.handler.log_output(self.stream, line);
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) {
+        while let Some(i) = bytes.iter().position(|b| *b == b'\n') {
+            self.buf.extend_from_slice(&bytes[..i]);
+            self.emit(self.buf.len());
+            self.buf.clear();
+            bytes = &bytes[i + 1..];
+        }
+        self.buf.extend_from_slice(bytes);
+        while self.buf.len() >= MAX_LINE_LENGTH {
+            self.emit(MAX_LINE_LENGTH);
+            self.buf.drain(..MAX_LINE_LENGTH);
+        }
+    }
+}
+
+impl<H: Handler> Drop for LineBuffer<H> {
+    fn drop(&mut self) {
+        if !self.buf.is_empty() {
+            self.emit(self.buf.len());
+        }
+    }
+}
+
+/// [`StdoutStream`] passing each line written by the component to [`Logging::log_output`].
+///
+/// All streams returned by [`StdoutStream::stream`] share a single line buffer, which is flushed
+/// once the instance is dropped.
+///
+/// [`Logging::log_output`]: super::Logging::log_output
+pub(crate) struct LineOutput<H: Handler>(Arc<Mutex<LineBuffer<H>>>);
+
+impl<H: Handler> Clone for LineOutput<H> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<H: Handler> LineOutput<H> {
+    pub(crate) fn new(handler: H, stream: StdioStream) -> Self {
+        Self(Arc::new(Mutex::new(LineBuffer {
+            handler,
+            stream,
+            buf: Vec::default(),
+        })))
+    }
+}
+
+impl<H: Handler> StdoutStream for LineOutput<H> {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+impl<H: Handler> HostOutputStream for LineOutput<H> {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        if let Ok(mut buf) = self.0.lock() {
+            buf.write(&bytes);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        // Only complete lines are emitted, flushing does not affect the buffered partial line
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(MAX_LINE_LENGTH)
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Subscribe for LineOutput<H> {
+    async fn ready(&mut self) {}
+}
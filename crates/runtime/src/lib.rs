@@ -16,9 +16,13 @@ pub mod capability;
 /// Shared wasmCloud runtime engine
 pub mod runtime;
 
+/// On-disk cache of precompiled components
+pub mod cache;
+
 /// wasmCloud I/O functionality
 pub mod io;
 
+pub use cache::ComponentCache;
 pub use component::{Component, ComponentConfig};
 pub use runtime::*;
 
@@ -1,9 +1,10 @@
-use crate::ComponentConfig;
+use crate::{ComponentCache, ComponentConfig};
 
 use core::fmt;
 use core::fmt::Debug;
 use core::time::Duration;
 
+use std::sync::Arc;
 use std::thread;
 
 use anyhow::Context;
@@ -18,6 +19,7 @@ pub const MAX_COMPONENTS: u32 = 10_000;
 
 /// [`RuntimeBuilder`] used to configure and build a [Runtime]
 #[derive(Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct RuntimeBuilder {
     engine_config: wasmtime::Config,
     max_components: u32,
@@ -26,6 +28,10 @@ pub struct RuntimeBuilder {
     max_execution_time: Duration,
     component_config: ComponentConfig,
     force_pooling_allocator: bool,
+    component_cache: Option<ComponentCache>,
+    fuel_metering: bool,
+    adapt_core_modules: bool,
+    adapt_command_modules: bool,
 }
 
 impl RuntimeBuilder {
@@ -48,6 +54,10 @@ impl RuntimeBuilder {
             max_execution_time: Duration::from_secs(10 * 60),
             component_config: ComponentConfig::default(),
             force_pooling_allocator: false,
+            component_cache: None,
+            fuel_metering: false,
+            adapt_core_modules: true,
+            adapt_command_modules: false,
         }
     }
 
@@ -78,7 +88,9 @@ impl RuntimeBuilder {
         }
     }
 
-    /// Sets the maximum amount of linear memory that can be used by all components. Defaults to 10MB
+    /// Sets the maximum amount of linear memory that can be used by all components. Defaults to 10MB.
+    /// Individual components can be restricted further using
+    /// [`Component::set_max_memory`](crate::Component::set_max_memory)
     #[must_use]
     pub fn max_linear_memory(self, max_linear_memory: u64) -> Self {
         Self {
@@ -107,6 +119,48 @@ impl RuntimeBuilder {
         }
     }
 
+    /// Enables fuel metering. Each invocation of a component is given a fuel budget, which can be
+    /// set per component using [`Component::set_max_fuel`](crate::Component::set_max_fuel), and
+    /// execution traps once the budget is exhausted. This incurs a small runtime overhead.
+    #[must_use]
+    pub fn fuel_metering(self) -> Self {
+        Self {
+            fuel_metering: true,
+            ..self
+        }
+    }
+
+    /// Sets whether WASI preview 1 core Wasm modules are accepted and adapted to components when
+    /// loaded. Defaults to `true`
+    #[must_use]
+    pub fn adapt_core_modules(self, adapt_core_modules: bool) -> Self {
+        Self {
+            adapt_core_modules,
+            ..self
+        }
+    }
+
+    /// Sets whether WASI preview 1 core Wasm modules exporting a `_start` function are adapted to
+    /// components using the command adapter, which exports `wasi:cli/run`. Otherwise, all core
+    /// modules are adapted using the reactor adapter. Defaults to `false`
+    #[must_use]
+    pub fn adapt_command_modules(self, adapt_command_modules: bool) -> Self {
+        Self {
+            adapt_command_modules,
+            ..self
+        }
+    }
+
+    /// Sets a [`ComponentCache`] used to store and look up precompiled components, which avoids
+    /// recompiling the same component on every start
+    #[must_use]
+    pub fn component_cache(self, component_cache: ComponentCache) -> Self {
+        Self {
+            component_cache: Some(component_cache),
+            ..self
+        }
+    }
+
     /// Turns this builder into a [`Runtime`]
     ///
     /// # Errors
@@ -152,6 +206,7 @@ impl RuntimeBuilder {
             .table_keep_resident(10 * 1024);
         self.engine_config
             .allocation_strategy(InstanceAllocationStrategy::Pooling(pooling_config));
+        self.engine_config.consume_fuel(self.fuel_metering);
         let engine = match wasmtime::Engine::new(&self.engine_config)
             .context("failed to construct engine")
         {
@@ -181,6 +236,10 @@ impl RuntimeBuilder {
                 engine,
                 component_config: self.component_config,
                 max_execution_time: self.max_execution_time,
+                component_cache: self.component_cache.map(Arc::new),
+                fuel_metering: self.fuel_metering,
+                adapt_core_modules: self.adapt_core_modules,
+                adapt_command_modules: self.adapt_command_modules,
             },
             epoch,
         ))
@@ -201,6 +260,10 @@ pub struct Runtime {
     pub(crate) engine: wasmtime::Engine,
     pub(crate) component_config: ComponentConfig,
     pub(crate) max_execution_time: Duration,
+    pub(crate) component_cache: Option<Arc<ComponentCache>>,
+    pub(crate) fuel_metering: bool,
+    pub(crate) adapt_core_modules: bool,
+    pub(crate) adapt_command_modules: bool,
 }
 
 impl Debug for Runtime {
@@ -209,6 +272,10 @@ impl Debug for Runtime {
             .field("component_config", &self.component_config)
             .field("runtime", &"wasmtime")
             .field("max_execution_time", &"max_execution_time")
+            .field("component_cache", &self.component_cache)
+            .field("fuel_metering", &self.fuel_metering)
+            .field("adapt_core_modules", &self.adapt_core_modules)
+            .field("adapt_command_modules", &self.adapt_command_modules)
             .finish_non_exhaustive()
     }
 }
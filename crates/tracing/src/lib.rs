@@ -5,9 +5,11 @@ use heck::ToKebabCase;
 #[cfg(feature = "otel")]
 pub use opentelemetry::{
     global,
-    metrics::{Counter, Histogram, Meter, Unit},
+    metrics::{Counter, Gauge, Histogram, Meter, Unit, UpDownCounter},
     KeyValue,
 };
+#[cfg(feature = "prometheus")]
+pub use prometheus::Registry as PrometheusRegistry;
 use wasmcloud_core::logging::Level;
 #[cfg(feature = "otel")]
 use wasmcloud_core::tls;
@@ -55,7 +57,12 @@ pub fn configure_observability(
     let normalized_service_name = service_name.to_kebab_case();
 
     if otel_config.metrics_enabled() {
-        metrics::configure_metrics(&normalized_service_name, otel_config)?;
+        metrics::configure_metrics(
+            &normalized_service_name,
+            otel_config,
+            #[cfg(feature = "prometheus")]
+            None,
+        )?;
     }
 
     traces::configure_tracing(
@@ -68,6 +75,36 @@ pub fn configure_observability(
     )
 }
 
+/// Configures observability for each type of signal, additionally exporting all metrics to
+/// `prometheus_registry`, regardless of whether OTEL metrics are enabled
+#[cfg(feature = "prometheus")]
+pub fn configure_observability_with_prometheus(
+    service_name: &str,
+    otel_config: &OtelConfig,
+    use_structured_logging: bool,
+    flame_graph: Option<impl AsRef<Path>>,
+    log_level_override: Option<&Level>,
+    trace_level_override: Option<&Level>,
+    prometheus_registry: &prometheus::Registry,
+) -> anyhow::Result<(tracing::Dispatch, traces::FlushGuard)> {
+    let normalized_service_name = service_name.to_kebab_case();
+
+    metrics::configure_metrics(
+        &normalized_service_name,
+        otel_config,
+        Some(prometheus_registry),
+    )?;
+
+    traces::configure_tracing(
+        &normalized_service_name,
+        otel_config,
+        use_structured_logging,
+        flame_graph,
+        log_level_override,
+        trace_level_override,
+    )
+}
+
 // This method builds a custom reqwest 0.11 Client, because the HttpClient trait
 // defined in the `opentelemetry-http` crate is defined against reqwest 0.11 types:
 // * https://github.com/open-telemetry/opentelemetry-rust/blob/opentelemetry-otlp-0.16.0/opentelemetry-http/src/lib.rs#L50-L65
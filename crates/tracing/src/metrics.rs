@@ -7,43 +7,59 @@ use anyhow::Context;
 pub fn configure_metrics(
     service_name: &str,
     otel_config: &wasmcloud_core::OtelConfig,
+    #[cfg(feature = "prometheus")] prometheus_registry: Option<&prometheus::Registry>,
 ) -> anyhow::Result<()> {
     use opentelemetry_otlp::{MetricsExporterBuilder, WithExportConfig};
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
     use wasmcloud_core::OtelProtocol;
 
-    let builder: MetricsExporterBuilder = match otel_config.protocol {
-        OtelProtocol::Http => {
-            let client = crate::get_http_client(otel_config)
-                .context("failed to get an http client for otel metrics exporter")?;
-            opentelemetry_otlp::new_exporter()
-                .http()
-                .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
-                .with_http_client(client)
-                .with_endpoint(otel_config.metrics_endpoint())
-                .into()
-        }
-        OtelProtocol::Grpc => {
-            // TODO(joonas): Configure tonic::transport::ClientTlsConfig via .with_tls_config(...), passing in additional certificates.
-            opentelemetry_otlp::new_exporter()
-                .tonic()
-                .with_endpoint(otel_config.metrics_endpoint())
-                .into()
-        }
-    };
-
-    opentelemetry_otlp::new_pipeline()
-        .metrics(opentelemetry_sdk::runtime::Tokio)
-        .with_exporter(builder)
-        .with_resource(opentelemetry_sdk::Resource::new(vec![
+    let mut provider =
+        SdkMeterProvider::builder().with_resource(opentelemetry_sdk::Resource::new(vec![
             opentelemetry::KeyValue::new("service.name", service_name.to_string()),
-        ]))
-        .with_aggregation_selector(ExponentialHistogramAggregationSelector::new())
-        .with_temporality_selector(
-            opentelemetry_sdk::metrics::reader::DefaultTemporalitySelector::new(),
-        )
-        .build()
-        .context("failed to create OTEL metrics provider")?;
+        ]));
+
+    if otel_config.metrics_enabled() {
+        let builder: MetricsExporterBuilder = match otel_config.protocol {
+            OtelProtocol::Http => {
+                let client = crate::get_http_client(otel_config)
+                    .context("failed to get an http client for otel metrics exporter")?;
+                opentelemetry_otlp::new_exporter()
+                    .http()
+                    .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+                    .with_http_client(client)
+                    .with_endpoint(otel_config.metrics_endpoint())
+                    .into()
+            }
+            OtelProtocol::Grpc => {
+                // TODO(joonas): Configure tonic::transport::ClientTlsConfig via .with_tls_config(...), passing in additional certificates.
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(otel_config.metrics_endpoint())
+                    .into()
+            }
+        };
+        let exporter = builder
+            .build_metrics_exporter(
+                Box::new(opentelemetry_sdk::metrics::reader::DefaultTemporalitySelector::new()),
+                Box::new(ExponentialHistogramAggregationSelector::new()),
+            )
+            .context("failed to create OTEL metrics exporter")?;
+        provider = provider.with_reader(
+            PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio).build(),
+        );
+    }
+
+    // Prometheus does not support exponential histograms, so the default aggregation is used
+    #[cfg(feature = "prometheus")]
+    if let Some(registry) = prometheus_registry {
+        let exporter = opentelemetry_prometheus::exporter()
+            .with_registry(registry.clone())
+            .build()
+            .context("failed to create Prometheus metrics exporter")?;
+        provider = provider.with_reader(exporter);
+    }
 
+    opentelemetry::global::set_meter_provider(provider.build());
     Ok(())
 }
 
@@ -75,6 +75,10 @@ pub struct Component {
     /// Indicates whether this module is a capability provider
     #[serde(rename = "prov", default = "default_as_false")]
     pub provider: bool,
+
+    /// If the component chooses, it can supply a JSON schema that describes its expected config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_schema: Option<serde_json::Value>,
 }
 
 /// The claims metadata corresponding to a capability provider
@@ -840,6 +844,7 @@ impl Component {
             rev,
             ver,
             call_alias: normalize_call_alias(call_alias),
+            config_schema: None,
         }
     }
 }
@@ -6,6 +6,8 @@ use clap::{self, Arg, Command, FromArgMatches, Parser, Subcommand};
 use serde_json::json;
 use tracing_subscriber::EnvFilter;
 use wash_cli::wit::WitCommand;
+use wash_lib::cli::audit::AuditCommand;
+use wash_lib::cli::cache::CacheCommand;
 use wash_lib::cli::capture::{CaptureCommand, CaptureSubcommand};
 use wash_lib::cli::claims::ClaimsCliCommand;
 use wash_lib::cli::get::GetCommand;
@@ -38,6 +40,7 @@ use wash_cli::generate::{self, NewCliCommand};
 use wash_cli::keys::{self, KeysCliCommand};
 use wash_cli::par::{self, ParCliCommand};
 use wash_cli::plugin::{self, PluginCommand};
+use wash_cli::replay::{self, ReplayCommand};
 use wash_cli::secrets::{self, SecretsCliCommand};
 use wash_cli::style::WASH_CLI_STYLE;
 use wash_cli::ui::{self, UiCommand};
@@ -80,6 +83,8 @@ Iterate:
   update       Update a component running in a host to newer image reference
   link         Link one component to another on a set of interfaces
   call         Invoke a simple function on a component running in a wasmCloud host
+  replay       Replay a component invocation recorded by a wasmCloud host
+  audit        Show the audit log of control interface requests handled by wasmCloud hosts
   label        Label (or un-label) a host with a key=value label pair
   config       Create configuration for components, capability providers and links
   secrets      Create secret references for components, capability providers and links
@@ -92,6 +97,7 @@ Configure:
   completions  Generate shell completions for wash
   ctx          Manage wasmCloud host configuration contexts
   drain        Manage contents of local wasmCloud caches
+  cache        Manage the OCI artifact cache of wasmCloud hosts
   keys         Utilities for generating and managing signing keys
   claims       Generate and manage JWTs for wasmCloud components and capability providers
   plugin       Manage wash plugins
@@ -156,9 +162,15 @@ enum CliCommand {
     /// Manage declarative applications and deployments (wadm)
     #[clap(name = "app", subcommand)]
     App(AppCliCommand),
+    /// Show the audit log of control interface requests handled by wasmCloud hosts
+    #[clap(name = "audit")]
+    Audit(AuditCommand),
     /// Build (and sign) a wasmCloud component or capability provider
     #[clap(name = "build")]
     Build(BuildCommand),
+    /// Manage the OCI artifact cache of wasmCloud hosts
+    #[clap(name = "cache", subcommand)]
+    Cache(CacheCommand),
     /// Invoke a simple function on a component running in a wasmCloud host
     #[clap(name = "call")]
     Call(CallCli),
@@ -213,6 +225,9 @@ enum CliCommand {
     /// Pull an artifact from an OCI compliant registry
     #[clap(name = "pull")]
     RegPull(RegistryPullCommand),
+    /// Replay a component invocation recorded by a wasmCloud host
+    #[clap(name = "replay")]
+    Replay(ReplayCommand),
     /// Manage secret references
     #[clap(name = "secrets", alias = "secret", subcommand)]
     Secrets(SecretsCliCommand),
@@ -399,7 +414,13 @@ async fn main() {
     );
     let res: anyhow::Result<CommandOutput> = match cli.command {
         CliCommand::App(app_cli) => app::handle_command(app_cli, output_kind).await,
+        CliCommand::Audit(audit_cli) => {
+            wash_lib::cli::audit::handle_command(audit_cli, output_kind).await
+        }
         CliCommand::Build(build_cli) => build::handle_command(build_cli).await,
+        CliCommand::Cache(cache_cli) => {
+            wash_lib::cli::cache::handle_command(cache_cli, output_kind).await
+        }
         CliCommand::Call(call_cli) => call::handle_command(call_cli.command()).await,
         CliCommand::Capture(capture_cli) => {
             if !cli.experimental {
@@ -436,6 +457,7 @@ async fn main() {
         CliCommand::RegPull(reg_pull_cli) => {
             common::registry_cmd::registry_pull(reg_pull_cli, output_kind).await
         }
+        CliCommand::Replay(replay_cli) => replay::handle_command(replay_cli).await,
         CliCommand::Spy(spy_cli) => {
             if !cli.experimental {
                 experimental_error_message("spy")
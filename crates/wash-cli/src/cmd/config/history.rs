@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde_json::json;
+use wash_lib::cli::{CliConnectionOpts, CommandOutput, OutputKind};
+use wash_lib::config::WashConnectionOptions;
+use wasmcloud_control_interface::{ConfigChange, ConfigRevision};
+
+use crate::appearance::spinner::Spinner;
+use crate::errors::suggest_run_host_error;
+
+/// Invoke `wash config history`, listing the revisions of a named config or, if `diff` is set,
+/// the changes between two of them
+pub(crate) async fn invoke(
+    opts: CliConnectionOpts,
+    name: &str,
+    diff: Option<(u64, u64)>,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    let sp: Spinner = Spinner::new(&output_kind)?;
+    sp.update_spinner_message("Getting configuration history...".to_string());
+
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let ctl_client = wco.into_ctl_client(None).await?;
+
+    let history_response = ctl_client
+        .get_config_history(name)
+        .await
+        .map_err(suggest_run_host_error)?;
+
+    sp.finish_and_clear();
+
+    if !history_response.succeeded() {
+        anyhow::bail!(
+            "Error getting configuration history: {}",
+            history_response.message()
+        );
+    }
+    let history = history_response.into_data().unwrap_or_default();
+
+    if let Some((from, to)) = diff {
+        let find = |revision: u64| {
+            history
+                .iter()
+                .find(|r| r.revision() == revision)
+                .with_context(|| format!("revision {revision} of configuration '{name}' not found"))
+        };
+        let changes = find(from)?.diff(find(to)?);
+        let text = if changes.is_empty() {
+            format!("No changes between revisions {from} and {to}")
+        } else {
+            changes
+                .iter()
+                .map(format_change)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        return Ok(CommandOutput::new(
+            text,
+            HashMap::from_iter([
+                ("from".to_string(), json!(from)),
+                ("to".to_string(), json!(to)),
+                ("changes".to_string(), json!(changes)),
+            ]),
+        ));
+    }
+
+    let text = if history.is_empty() {
+        format!("No revisions recorded for configuration '{name}'")
+    } else {
+        history
+            .iter()
+            .map(format_revision)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Ok(CommandOutput::new(
+        text,
+        HashMap::from_iter([("revisions".to_string(), json!(history))]),
+    ))
+}
+
+fn format_revision(revision: &ConfigRevision) -> String {
+    let change = match (revision.values(), revision.rollback_of()) {
+        (None, _) => "deleted".to_string(),
+        (Some(_), Some(rollback_of)) => format!("rolled back to revision {rollback_of}"),
+        (Some(values), None) => format!("put {} value(s)", values.len()),
+    };
+    format!(
+        "{:>4}  {}  {}  {change}",
+        revision.revision(),
+        revision.timestamp(),
+        revision.author().unwrap_or("-"),
+    )
+}
+
+fn format_change(change: &ConfigChange) -> String {
+    match change {
+        ConfigChange::Added { key, value } => format!("+ {key}={value}"),
+        ConfigChange::Removed { key, value } => format!("- {key}={value}"),
+        ConfigChange::Changed { key, from, to } => format!("~ {key}={from} -> {to}"),
+    }
+}
@@ -1,6 +1,6 @@
 //! `wash config` related (sub)commands
 
-use clap::Subcommand;
+use clap::{Args, Subcommand};
 use wash_lib::cli::{input_vec_to_hashmap, CliConnectionOpts, CommandOutput, OutputKind};
 
 use crate::cmd;
@@ -8,7 +8,35 @@ use crate::secrets::ensure_not_secret;
 
 pub(crate) mod delete;
 pub(crate) mod get;
+pub(crate) mod history;
 pub(crate) mod put;
+pub(crate) mod rollback;
+
+/// Options for validating configuration values against the config schema of a component or
+/// provider before putting them
+#[derive(Debug, Clone, Default, Args)]
+pub struct ValidateOpts {
+    /// Validate the configuration values against the config schema embedded in the component or
+    /// provider at this path or OCI reference before putting them. Since a component or provider
+    /// may be configured by several named configurations, required properties are not checked
+    #[clap(long = "validate-for", value_name = "REF")]
+    pub validate_for: Option<String>,
+    /// Allow latest artifact tags when fetching the reference to validate for
+    #[clap(long = "allow-latest", requires = "validate_for")]
+    pub allow_latest: bool,
+    /// OCI username, if omitted anonymous authentication will be used
+    #[clap(long = "user", env = "WASH_REG_USER", hide_env_values = true)]
+    pub user: Option<String>,
+    /// OCI password, if omitted anonymous authentication will be used
+    #[clap(long = "password", env = "WASH_REG_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+    /// Allow insecure (HTTP) registry connections
+    #[clap(long = "insecure", requires = "validate_for")]
+    pub insecure: bool,
+    /// Skip checking OCI registry's certificate for validity
+    #[clap(long = "insecure-skip-tls-verify", requires = "validate_for")]
+    pub insecure_skip_tls_verify: bool,
+}
 
 #[derive(Debug, Clone, Subcommand)]
 #[allow(clippy::enum_variant_names)]
@@ -24,6 +52,8 @@ pub enum ConfigCliCommand {
         /// The configuration values to put, in the form of `key=value`. Can be specified multiple times, but must be specified at least once.
         #[clap(name = "config_value", required = true)]
         config_values: Vec<String>,
+        #[clap(flatten)]
+        validate: ValidateOpts,
     },
     /// Get a named configuration
     #[clap(name = "get")]
@@ -43,6 +73,30 @@ pub enum ConfigCliCommand {
         #[clap(name = "name")]
         name: String,
     },
+    /// List the revisions of a named configuration, which are kept after it is deleted
+    #[clap(name = "history")]
+    HistoryCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to list the revisions of
+        #[clap(name = "name")]
+        name: String,
+        /// Show the changes between two revisions instead of listing them
+        #[clap(long = "diff", num_args = 2, value_names = ["FROM", "TO"])]
+        diff: Option<Vec<u64>>,
+    },
+    /// Roll a named configuration back to an earlier revision
+    #[clap(name = "rollback")]
+    RollbackCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to roll back
+        #[clap(name = "name")]
+        name: String,
+        /// The revision to restore, as listed by `wash config history`
+        #[clap(name = "revision")]
+        revision: u64,
+    },
 }
 
 /// Handle any `wash config` prefixed (sub)command
@@ -55,12 +109,14 @@ pub async fn handle_command(
             opts,
             name,
             config_values,
+            validate,
         } => {
             ensure_not_secret(&name)?;
             cmd::config::put::invoke(
                 opts,
                 &name,
                 input_vec_to_hashmap(config_values)?,
+                validate,
                 output_kind,
             )
             .await
@@ -73,5 +129,22 @@ pub async fn handle_command(
             ensure_not_secret(&name)?;
             cmd::config::delete::invoke(opts, &name, output_kind).await
         }
+        ConfigCliCommand::HistoryCommand { opts, name, diff } => {
+            ensure_not_secret(&name)?;
+            let diff = match diff.as_deref() {
+                Some(&[from, to]) => Some((from, to)),
+                Some(_) => anyhow::bail!("--diff requires exactly two revisions"),
+                None => None,
+            };
+            cmd::config::history::invoke(opts, &name, diff, output_kind).await
+        }
+        ConfigCliCommand::RollbackCommand {
+            opts,
+            name,
+            revision,
+        } => {
+            ensure_not_secret(&name)?;
+            cmd::config::rollback::invoke(opts, &name, revision, output_kind).await
+        }
     }
 }
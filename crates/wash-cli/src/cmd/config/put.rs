@@ -1,20 +1,46 @@
 use std::collections::HashMap;
 
+use anyhow::{bail, Context};
 use serde_json::json;
+use wash_lib::cli::inspect::get_config_schema;
 use wash_lib::cli::{CliConnectionOpts, CommandOutput, OutputKind};
 use wash_lib::config::WashConnectionOptions;
+use wash_lib::registry::OciPullOptions;
+use wasmcloud_core::{format_config_violations, validate_config_properties};
 use wasmcloud_secrets_types::SECRET_PREFIX;
 
+use super::ValidateOpts;
 use crate::appearance::spinner::Spinner;
 use crate::errors::suggest_run_host_error;
 
-/// Invoke a `wash config put` command
+/// Invoke a `wash config put` command, validating the values against the config schema of the
+/// component or provider to validate for first if set. Required properties are not checked, since
+/// they may be set by other named configurations
 pub(crate) async fn invoke(
     opts: CliConnectionOpts,
     name: &str,
     values: HashMap<String, String>,
+    validate: ValidateOpts,
     output_kind: OutputKind,
 ) -> anyhow::Result<CommandOutput> {
+    if let Some(target) = validate.validate_for {
+        let options = OciPullOptions {
+            digest: None,
+            allow_latest: validate.allow_latest,
+            user: validate.user,
+            password: validate.password,
+            insecure: validate.insecure,
+            insecure_skip_tls_verify: validate.insecure_skip_tls_verify,
+        };
+        let schema = get_config_schema(&target, options)
+            .await?
+            .with_context(|| format!("`{target}` does not declare a config schema"))?;
+        let violations = validate_config_properties(&schema, &values)?;
+        if !violations.is_empty() {
+            bail!(format_config_violations(&target, &violations));
+        }
+    }
+
     let sp: Spinner = Spinner::new(&output_kind)?;
     let is_secret = name.starts_with(SECRET_PREFIX);
     let msg = if is_secret {
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+use wash_lib::cli::{CliConnectionOpts, CommandOutput, OutputKind};
+use wash_lib::config::WashConnectionOptions;
+
+use crate::appearance::spinner::Spinner;
+use crate::errors::suggest_run_host_error;
+
+/// Invoke `wash config rollback`
+pub(crate) async fn invoke(
+    opts: CliConnectionOpts,
+    name: &str,
+    revision: u64,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    let sp: Spinner = Spinner::new(&output_kind)?;
+    sp.update_spinner_message(format!(
+        "Rolling back configuration to revision {revision}..."
+    ));
+
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let ctl_client = wco.into_ctl_client(None).await?;
+
+    let rollback_response = ctl_client
+        .rollback_config(name, revision)
+        .await
+        .map_err(suggest_run_host_error)?;
+
+    sp.finish_and_clear();
+
+    let message = if rollback_response.succeeded() {
+        format!("Configuration '{name}' rolled back to revision {revision}.")
+    } else {
+        rollback_response.message().to_string()
+    };
+    let json_out = HashMap::from_iter([
+        ("success".to_string(), json!(rollback_response.succeeded())),
+        ("message".to_string(), json!(message)),
+    ]);
+
+    Ok(CommandOutput::new(message, json_out))
+}
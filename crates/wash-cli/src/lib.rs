@@ -19,6 +19,7 @@ pub mod generate;
 pub mod keys;
 pub mod par;
 pub mod plugin;
+pub mod replay;
 pub mod secrets;
 pub mod style;
 pub mod ui;
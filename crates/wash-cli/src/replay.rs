@@ -0,0 +1,158 @@
+//! Deterministic replay of component invocations recorded by a wasmCloud host
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use clap::Args;
+use serde_json::json;
+use wash_lib::cli::CommandOutput;
+use wasmcloud_runtime::capability::logging::logging;
+use wasmcloud_runtime::capability::{config, secrets, CallTargetInterface};
+use wasmcloud_runtime::component::{
+    Bus, Config, Egress, EgressTarget, InvocationErrorIntrospect, InvocationErrorKind,
+    InvocationRecording, Logging, ReplacedInstanceTarget, Secrets, StdioStream,
+};
+use wasmcloud_runtime::{Component, Runtime};
+
+#[derive(Debug, Clone, Args)]
+pub struct ReplayCommand {
+    /// Path to the Wasm component, which handled the recorded invocation
+    #[clap(name = "component")]
+    component: PathBuf,
+
+    /// Path to the invocation recording written by a wasmCloud host
+    #[clap(name = "recording")]
+    recording: PathBuf,
+}
+
+/// Handler used during replay. All values observed by the component are taken from the recording,
+/// so this only prints component logs.
+#[derive(Clone)]
+struct ReplayHandler;
+
+impl wrpc_transport::Invoke for ReplayHandler {
+    type Context = Option<ReplacedInstanceTarget>;
+    type Outgoing = wrpc_transport::frame::Outgoing;
+    type Incoming = wrpc_transport::frame::Incoming;
+
+    async fn invoke<P>(
+        &self,
+        _cx: Self::Context,
+        instance: &str,
+        func: &str,
+        _params: Bytes,
+        _paths: impl AsRef<[P]> + Send,
+    ) -> Result<(Self::Outgoing, Self::Incoming)>
+    where
+        P: AsRef<[Option<usize>]> + Send + Sync,
+    {
+        bail!("invocation of `{instance}#{func}` cannot be performed during replay")
+    }
+}
+
+#[async_trait]
+impl Bus for ReplayHandler {
+    async fn set_link_name(
+        &self,
+        _link_name: String,
+        _interfaces: Vec<Arc<CallTargetInterface>>,
+    ) -> Result<Result<(), String>> {
+        Ok(Ok(()))
+    }
+}
+
+#[async_trait]
+impl Config for ReplayHandler {
+    async fn get(&self, _key: &str) -> Result<Result<Option<String>, config::store::Error>> {
+        Ok(Ok(None))
+    }
+
+    async fn get_all(&self) -> Result<Result<Vec<(String, String)>, config::store::Error>> {
+        Ok(Ok(Vec::default()))
+    }
+}
+
+#[async_trait]
+impl Egress for ReplayHandler {
+    async fn check_egress(&self, _target: &EgressTarget) -> bool {
+        // Outgoing requests are replayed from the recording and never leave the process
+        true
+    }
+}
+
+#[async_trait]
+impl Logging for ReplayHandler {
+    async fn log(&self, level: logging::Level, context: String, message: String) -> Result<()> {
+        eprintln!("[{level:?}] {context}: {message}");
+        Ok(())
+    }
+
+    fn log_output(&self, stream: StdioStream, line: &str) {
+        eprintln!("[{stream}] {line}");
+    }
+}
+
+#[async_trait]
+impl Secrets for ReplayHandler {
+    async fn get(
+        &self,
+        _key: &str,
+    ) -> Result<Result<secrets::store::Secret, secrets::store::SecretsError>> {
+        Ok(Err(secrets::store::SecretsError::NotFound))
+    }
+
+    async fn reveal(
+        &self,
+        _secret: secrets::reveal::Secret,
+    ) -> Result<secrets::reveal::SecretValue> {
+        bail!("secrets are not available during replay")
+    }
+}
+
+impl InvocationErrorIntrospect for ReplayHandler {
+    fn invocation_error_kind(&self, _err: &anyhow::Error) -> InvocationErrorKind {
+        InvocationErrorKind::Trap
+    }
+}
+
+pub async fn handle_command(cmd: ReplayCommand) -> Result<CommandOutput> {
+    let wasm = tokio::fs::read(&cmd.component)
+        .await
+        .with_context(|| format!("failed to read `{}`", cmd.component.display()))?;
+    let recording = tokio::fs::read(&cmd.recording)
+        .await
+        .with_context(|| format!("failed to read `{}`", cmd.recording.display()))?;
+    let recording: InvocationRecording =
+        serde_json::from_slice(&recording).context("failed to parse invocation recording")?;
+
+    let (rt, _epoch) = Runtime::new().context("failed to construct runtime")?;
+    let component = Component::new(&rt, &wasm).context("failed to compile component")?;
+    let function = if recording.instance.is_empty() {
+        recording.name.clone()
+    } else {
+        format!("{}#{}", recording.instance, recording.name)
+    };
+    let expected = recording.results.clone();
+    let imports = recording.imports.len();
+    let results = component
+        .replay(ReplayHandler, recording)
+        .await
+        .with_context(|| format!("failed to replay invocation of `{function}`"))?;
+    if results != expected {
+        bail!("results of the replayed invocation of `{function}` differ from the recording");
+    }
+
+    let mut map = HashMap::new();
+    map.insert("function".to_string(), json!(function));
+    map.insert("imports".to_string(), json!(imports));
+    Ok(CommandOutput::new(
+        format!(
+            "Replayed invocation of `{function}` with {imports} recorded import invocation(s), results match the recording"
+        ),
+        map,
+    ))
+}
@@ -7,6 +7,7 @@ use wash_lib::cli::{input_vec_to_hashmap, CliConnectionOpts, CommandOutput, Outp
 use wasmcloud_secrets_types::{SecretConfig, SECRET_PREFIX};
 
 use crate::cmd;
+use crate::cmd::config::ValidateOpts;
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum SecretsCliCommand {
@@ -83,7 +84,14 @@ pub async fn handle_command(
             trace!(?secret_config, "Putting secret config");
             let values: HashMap<String, String> = secret_config.try_into()?;
 
-            cmd::config::put::invoke(opts, &secret_configdata_key(&name), values, output_kind).await
+            cmd::config::put::invoke(
+                opts,
+                &secret_configdata_key(&name),
+                values,
+                ValidateOpts::default(),
+                output_kind,
+            )
+            .await
         }
         SecretsCliCommand::GetCommand { opts, name } => {
             cmd::config::get::invoke(opts, &secret_configdata_key(&name), output_kind).await
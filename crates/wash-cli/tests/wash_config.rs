@@ -4,7 +4,7 @@ use common::TestWashInstance;
 
 use std::collections::HashMap;
 
-use wash_cli::cmd::config::ConfigCliCommand;
+use wash_cli::cmd::config::{ConfigCliCommand, ValidateOpts};
 use wash_lib::cli::{CliConnectionOpts, OutputKind};
 
 #[tokio::test]
@@ -20,6 +20,7 @@ async fn test_config_put_and_get() -> anyhow::Result<()> {
         },
         name: "foobar".to_string(),
         config_values,
+        validate: ValidateOpts::default(),
     };
 
     // Put the config
@@ -56,6 +57,7 @@ async fn test_config_secret_name_error() -> anyhow::Result<()> {
         opts: CliConnectionOpts::default(),
         name: "SECRET_foo".to_string(),
         config_values,
+        validate: ValidateOpts::default(),
     };
 
     // Put the config and expect an error
@@ -66,3 +68,100 @@ async fn test_config_secret_name_error() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_config_history_and_rollback() -> anyhow::Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let opts = CliConnectionOpts {
+        ctl_port: Some(wash_instance.nats_port.to_string()),
+        ..Default::default()
+    };
+
+    for value in ["first", "second"] {
+        wash_cli::cmd::config::handle_command(
+            ConfigCliCommand::PutCommand {
+                opts: opts.clone(),
+                name: "history".to_string(),
+                config_values: vec![format!("key={value}")],
+                validate: ValidateOpts::default(),
+            },
+            OutputKind::Json,
+        )
+        .await?;
+    }
+
+    // Revisions are numbered by the config store, so they are looked up first
+    let history = wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::HistoryCommand {
+            opts: opts.clone(),
+            name: "history".to_string(),
+            diff: None,
+        },
+        OutputKind::Json,
+    )
+    .await?
+    .map;
+    let revisions: Vec<u64> = history
+        .get("revisions")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|revision| revision["revision"].as_u64().unwrap())
+        .collect();
+    let [first, second] = revisions[..] else {
+        panic!("expected two revisions, got {revisions:?}");
+    };
+
+    let diff = wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::HistoryCommand {
+            opts: opts.clone(),
+            name: "history".to_string(),
+            diff: Some(vec![first, second]),
+        },
+        OutputKind::Json,
+    )
+    .await?
+    .map;
+    assert_eq!(
+        diff.get("changes").unwrap(),
+        &serde_json::json!([{ "change": "changed", "key": "key", "from": "first", "to": "second" }])
+    );
+
+    wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::RollbackCommand {
+            opts: opts.clone(),
+            name: "history".to_string(),
+            revision: first,
+        },
+        OutputKind::Json,
+    )
+    .await?;
+
+    let retrieved_config = wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::GetCommand {
+            opts: opts.clone(),
+            name: "history".to_string(),
+        },
+        OutputKind::Json,
+    )
+    .await?
+    .map;
+    assert_eq!(retrieved_config.get("key").unwrap(), "first");
+
+    let history = wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::HistoryCommand {
+            opts,
+            name: "history".to_string(),
+            diff: None,
+        },
+        OutputKind::Json,
+    )
+    .await?
+    .map;
+    let revisions = history.get("revisions").unwrap().as_array().unwrap();
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[2]["rollback_of"], first);
+
+    Ok(())
+}
@@ -8,11 +8,9 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use normpath::PathExt;
 use tracing::{debug, info, warn};
-use wasi_preview1_component_adapter_provider::{
-    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
-};
 use wasm_encoder::{Encode, Section};
-use wit_component::{ComponentEncoder, StringEncoding};
+use wasmcloud_component_adapters::WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER;
+use wit_component::StringEncoding;
 
 use crate::{
     build::{convert_wit_dir_to_world, SignConfig, WASMCLOUD_WASM_TAG_EXPERIMENTAL},
@@ -168,6 +166,7 @@ pub fn sign_component_wasm(
             ver: Some(common_config.version.to_string()),
             rev: Some(common_config.revision),
             call_alias: None,
+            config_schema: None,
             issuer: signing_config.issuer.clone(),
             subject: signing_config.subject.clone(),
             common: GenerateCommon {
@@ -470,30 +469,13 @@ fn adapt_wasip1_component(
             wasm_path.as_ref().display()
         )
     })?;
-
-    // Build a component encoder
-    let mut encoder = ComponentEncoder::default()
-        .validate(true)
-        .module(&wasm_bytes)
+    wasmcloud_component_adapters::adapt_wasip1_component(wasm_bytes, adapter_wasm_bytes)
         .with_context(|| {
             format!(
                 "failed to encode wasm component @ [{}]",
                 wasm_path.as_ref().display()
             )
-        })?;
-
-    // Adapt the module
-    encoder = encoder
-        .adapter(
-            WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME,
-            adapter_wasm_bytes.as_ref(),
-        )
-        .context("failed to set adapter during encoding")?;
-
-    // Return the encoded module bytes
-    encoder
-        .encode()
-        .context("failed to serialize encoded component")
+        })
 }
 
 /// Retrieve bytes for WASIP2 adapter given a project configuration,
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use async_nats::jetstream::consumer::{pull::Config as ConsumerConfig, AckPolicy, DeliverPolicy};
+use clap::Parser;
+use futures::TryStreamExt;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use wasmcloud_core::{audit_stream_name, audit_subject, AuditRecord};
+
+use super::{CliConnectionOpts, CommandOutput, OutputKind};
+use crate::config::WashConnectionOptions;
+
+/// Maximum number of audit records fetched from JetStream at once
+const FETCH_BATCH_SIZE: usize = 256;
+
+#[derive(Parser, Debug, Clone)]
+pub struct AuditCommand {
+    /// Path to the audit log file written by a host using `--audit-log-file`. If not set, audit
+    /// records are read from the `AUDIT_{lattice}` JetStream stream
+    #[clap(long = "file")]
+    pub file: Option<PathBuf>,
+
+    /// Only show requests handled at or after this time, either an RFC 3339 timestamp (e.g.
+    /// `2024-10-01T12:00:00Z`) or a duration before now (e.g. `1h`)
+    #[clap(long = "since", value_parser = parse_time)]
+    pub since: Option<SystemTime>,
+
+    /// Only show requests handled before this time, either an RFC 3339 timestamp or a duration
+    /// before now
+    #[clap(long = "until", value_parser = parse_time)]
+    pub until: Option<SystemTime>,
+
+    /// Only show requests of this operation (e.g. `component.scale`) or group of operations
+    /// (e.g. `component`)
+    #[clap(long = "operation")]
+    pub operation: Option<String>,
+
+    /// Only show failed requests
+    #[clap(long = "failed")]
+    pub failed: bool,
+
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+}
+
+/// Filter of audit records
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    pub operation: Option<String>,
+    pub failed: bool,
+}
+
+impl AuditFilter {
+    /// Returns whether `record` passes the filter. Records with invalid timestamps never pass
+    /// time filters
+    #[must_use]
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        if self.failed && record.success {
+            return false;
+        }
+        if let Some(operation) = &self.operation {
+            if record.operation != *operation
+                && !record
+                    .operation
+                    .strip_prefix(operation.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+            {
+                return false;
+            }
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Ok(timestamp) = humantime::parse_rfc3339_weak(&record.timestamp) else {
+            return false;
+        };
+        self.since.is_none_or(|since| timestamp >= since)
+            && self.until.is_none_or(|until| timestamp < until)
+    }
+}
+
+/// Parses an RFC 3339 timestamp or a duration before now
+fn parse_time(s: &str) -> Result<SystemTime> {
+    if let Ok(time) = humantime::parse_rfc3339_weak(s) {
+        return Ok(time);
+    }
+    let duration = humantime::parse_duration(s).with_context(|| {
+        format!("`{s}` is neither an RFC 3339 timestamp nor a duration (e.g. `1h`)")
+    })?;
+    SystemTime::now()
+        .checked_sub(duration)
+        .context("duration is too long")
+}
+
+pub async fn handle_command(cmd: AuditCommand, output_kind: OutputKind) -> Result<CommandOutput> {
+    let filter = AuditFilter {
+        since: cmd.since,
+        until: cmd.until,
+        operation: cmd.operation,
+        failed: cmd.failed,
+    };
+    let records = if let Some(path) = &cmd.file {
+        read_audit_file(path, &filter).await?
+    } else {
+        let wco: WashConnectionOptions = cmd.opts.try_into()?;
+        let lattice = wco.get_lattice();
+        let nats_client = wco.clone().into_nats_client().await?;
+        let js_context = if let Some(domain) = wco.js_domain {
+            async_nats::jetstream::with_domain(nats_client, domain)
+        } else {
+            async_nats::jetstream::new(nats_client)
+        };
+        read_audit_stream(&js_context, &lattice, &filter).await?
+    };
+
+    let text = records
+        .iter()
+        .map(|record| {
+            format!(
+                "{} {} {} {} {}{}",
+                record.timestamp,
+                record.host_id,
+                record.user.as_deref().unwrap_or("-"),
+                record.operation,
+                if record.success {
+                    "succeeded"
+                } else {
+                    "failed"
+                },
+                if record.message.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", record.message)
+                }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut map = HashMap::new();
+    map.insert("records".to_string(), json!(records));
+    Ok(CommandOutput::new(
+        match output_kind {
+            OutputKind::Text if records.is_empty() => "No audit records found".to_string(),
+            OutputKind::Text => text,
+            OutputKind::Json => String::new(),
+        },
+        map,
+    ))
+}
+
+/// Reads the audit records passing `filter` from the JSON-lines audit log at `path`
+pub async fn read_audit_file(
+    path: impl AsRef<Path>,
+    filter: &AuditFilter,
+) -> Result<Vec<AuditRecord>> {
+    let path = path.as_ref();
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("failed to open `{}`", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut records = Vec::new();
+    let mut n = 0;
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .with_context(|| format!("failed to read `{}`", path.display()))?
+    {
+        n += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord = serde_json::from_str(&line)
+            .with_context(|| format!("failed to parse audit record on line {n}"))?;
+        if filter.matches(&record) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Reads the audit records passing `filter` from the audit stream of `lattice`
+pub async fn read_audit_stream(
+    ctx: &async_nats::jetstream::Context,
+    lattice: &str,
+    filter: &AuditFilter,
+) -> Result<Vec<AuditRecord>> {
+    let stream = ctx
+        .get_stream(audit_stream_name(lattice))
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Unable to find audit stream. Are hosts started with `--audit-log-jetstream`? Error: {e:?}"
+            )
+        })?;
+    let deliver_policy = match filter.since {
+        Some(since) => {
+            let since = since.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+            DeliverPolicy::ByStartTime {
+                start_time: time::OffsetDateTime::from_unix_timestamp_nanos(
+                    since
+                        .as_nanos()
+                        .try_into()
+                        .context("timestamp out of range")?,
+                )
+                .context("timestamp out of range")?,
+            }
+        }
+        None => DeliverPolicy::All,
+    };
+    let consumer = stream
+        .create_consumer(ConsumerConfig {
+            description: Some("wash audit consumer".to_string()),
+            deliver_policy,
+            ack_policy: AckPolicy::None,
+            filter_subject: audit_subject(lattice, ">"),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    let mut records = Vec::new();
+    loop {
+        let mut batch = consumer
+            .fetch()
+            .max_messages(FETCH_BATCH_SIZE)
+            .messages()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let mut fetched = 0;
+        while let Some(msg) = batch
+            .try_next()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?
+        {
+            fetched += 1;
+            let record: AuditRecord = serde_json::from_slice(&msg.payload)
+                .with_context(|| format!("failed to parse audit record on `{}`", msg.subject))?;
+            if filter.matches(&record) {
+                records.push(record);
+            }
+        }
+        if fetched == 0 {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(timestamp: &str, operation: &str, success: bool) -> AuditRecord {
+        AuditRecord {
+            timestamp: timestamp.into(),
+            host_id: "NHOST".into(),
+            subject: format!("wasmbus.ctl.v1.default.{operation}"),
+            operation: operation.into(),
+            success,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_audit_file() {
+        let tempdir = tempfile::tempdir().expect("Unable to create tempdir");
+        let path = tempdir.path().join("audit.jsonl");
+        let records = [
+            record("2024-10-01T12:00:00.000Z", "component.scale", true),
+            record("2024-10-01T13:00:00.000Z", "config.put", false),
+            record("2024-10-01T14:00:00.000Z", "component.update", true),
+            record("2024-10-01T15:00:00.000Z", "componentx.scale", true),
+        ];
+        let lines = records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap() + "\n")
+            .collect::<String>();
+        tokio::fs::write(&path, lines).await.unwrap();
+
+        let all = read_audit_file(&path, &AuditFilter::default())
+            .await
+            .expect("failed to read audit log");
+        assert_eq!(all, records);
+
+        let filter = AuditFilter {
+            operation: Some("component".into()),
+            ..Default::default()
+        };
+        let components = read_audit_file(&path, &filter).await.unwrap();
+        assert_eq!(components, [records[0].clone(), records[2].clone()]);
+
+        let filter = AuditFilter {
+            since: Some(parse_time("2024-10-01T12:30:00Z").unwrap()),
+            until: Some(parse_time("2024-10-01T14:00:00Z").unwrap()),
+            ..Default::default()
+        };
+        let window = read_audit_file(&path, &filter).await.unwrap();
+        assert_eq!(window, [records[1].clone()]);
+
+        let filter = AuditFilter {
+            failed: true,
+            ..Default::default()
+        };
+        let failed = read_audit_file(&path, &filter).await.unwrap();
+        assert_eq!(failed, [records[1].clone()]);
+
+        assert!(parse_time("1h").unwrap() < SystemTime::now());
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use async_compression::tokio::bufread::GzipDecoder;
+use clap::{Parser, Subcommand};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio_tar::Archive;
+use wasmcloud_core::{oci_cache_dir, OciCache};
+
+use super::{CommandOutput, OutputKind};
+
+/// Annotation of the image layout index holding the full reference of a manifest
+const IMAGE_NAME_ANNOTATION: &str = "io.containerd.image.name";
+/// Annotation of the image layout index holding the reference or tag of a manifest
+const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum CacheCommand {
+    /// Seed the OCI cache of wasmCloud hosts with artifacts from OCI image layout tarballs, such
+    /// that hosts can run them without contacting a registry
+    #[clap(name = "seed")]
+    Seed(CacheSeedCommand),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct CacheSeedCommand {
+    /// Paths to OCI image layout tarballs, optionally gzip-compressed
+    #[clap(name = "tarball", required = true)]
+    pub tarballs: Vec<PathBuf>,
+
+    /// OCI cache directory of the host, defaults to the default OCI cache directory of wasmCloud
+    #[clap(long = "cache-dir", env = "WASMCLOUD_OCI_CACHE_DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Repository (e.g. `ghcr.io/wasmcloud/components/http-hello-world-rust`) of artifacts, whose
+    /// name in the image layout is only a tag
+    #[clap(short = 'r', long = "repository")]
+    pub repository: Option<String>,
+}
+
+/// An artifact stored in an OCI cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeededArtifact {
+    /// Reference recorded for the artifact, if its name was known
+    pub reference: Option<String>,
+    /// Manifest digest of the artifact
+    pub digest: String,
+}
+
+#[derive(Deserialize)]
+struct Descriptor {
+    #[serde(rename = "mediaType", default)]
+    media_type: String,
+    digest: String,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    manifests: Vec<Descriptor>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    layers: Vec<Descriptor>,
+}
+
+pub async fn handle_command(
+    command: CacheCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    match command {
+        CacheCommand::Seed(cmd) => handle_seed(cmd, output_kind).await,
+    }
+}
+
+async fn handle_seed(cmd: CacheSeedCommand, output_kind: OutputKind) -> Result<CommandOutput> {
+    let cache = match cmd.cache_dir {
+        Some(dir) => OciCache::new(dir),
+        None => OciCache::new(oci_cache_dir().await?),
+    };
+    let mut seeded = Vec::new();
+    for tarball in &cmd.tarballs {
+        let artifacts = seed_cache(&cache, tarball, cmd.repository.as_deref())
+            .await
+            .with_context(|| format!("failed to seed cache from `{}`", tarball.display()))?;
+        seeded.extend(artifacts);
+    }
+    let text = seeded
+        .iter()
+        .map(|artifact| match &artifact.reference {
+            Some(reference) => format!("{reference} ({})", artifact.digest),
+            None => artifact.digest.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut map = HashMap::new();
+    map.insert("cache_dir".to_string(), json!(cache.dir()));
+    map.insert("seeded".to_string(), json!(seeded));
+    Ok(CommandOutput::new(
+        match output_kind {
+            OutputKind::Text => format!(
+                "Seeded OCI cache at {} with:\n{text}",
+                cache.dir().display()
+            ),
+            OutputKind::Json => String::new(),
+        },
+        map,
+    ))
+}
+
+/// Stores the artifacts of the OCI image layout tarball at `tarball` in `cache` and returns them.
+///
+/// Artifacts named only by a tag in the image layout are recorded under `repository`, if given.
+pub async fn seed_cache(
+    cache: &OciCache,
+    tarball: impl AsRef<Path>,
+    repository: Option<&str>,
+) -> Result<Vec<SeededArtifact>> {
+    let tarball = tarball.as_ref();
+    let file = tokio::fs::File::open(tarball)
+        .await
+        .with_context(|| format!("failed to open `{}`", tarball.display()))?;
+    let mut file = BufReader::new(file);
+    // Gzip-compressed tarballs start with the gzip magic bytes
+    let files = if file.fill_buf().await?.starts_with(&[0x1f, 0x8b]) {
+        read_tarball(GzipDecoder::new(file)).await?
+    } else {
+        read_tarball(file).await?
+    };
+    ensure!(
+        files.contains_key("oci-layout"),
+        "tarball is not an OCI image layout"
+    );
+    let index: Index = serde_json::from_slice(
+        files
+            .get("index.json")
+            .context("image layout is missing `index.json`")?,
+    )
+    .context("failed to parse `index.json`")?;
+
+    let mut seeded = Vec::with_capacity(index.manifests.len());
+    for descriptor in index.manifests {
+        if descriptor.media_type.ends_with("image.index.v1+json")
+            || descriptor.media_type.ends_with("manifest.list.v2+json")
+        {
+            bail!(
+                "nested image index `{}` is not supported",
+                descriptor.digest
+            );
+        }
+        let manifest: Manifest = serde_json::from_slice(blob(&files, &descriptor.digest)?)
+            .with_context(|| format!("failed to parse manifest `{}`", descriptor.digest))?;
+        let mut content = Vec::new();
+        for layer in &manifest.layers {
+            content.extend_from_slice(blob(&files, &layer.digest)?);
+        }
+        cache
+            .insert(&descriptor.digest, content)
+            .await
+            .with_context(|| format!("failed to store artifact `{}`", descriptor.digest))?;
+
+        let reference = match (
+            descriptor.annotations.get(IMAGE_NAME_ANNOTATION),
+            descriptor.annotations.get(REF_NAME_ANNOTATION),
+        ) {
+            (Some(name), _) => Some(name.clone()),
+            (None, Some(name)) if name.contains('/') => Some(name.clone()),
+            (None, Some(tag)) => repository.map(|repository| format!("{repository}:{tag}")),
+            (None, None) => None,
+        };
+        if let Some(reference) = &reference {
+            cache
+                .record(reference, &descriptor.digest)
+                .await
+                .with_context(|| format!("failed to record `{reference}`"))?;
+        }
+        seeded.push(SeededArtifact {
+            reference,
+            digest: descriptor.digest,
+        });
+    }
+    Ok(seeded)
+}
+
+/// Reads all regular files in a tarball into memory, keyed by their path
+async fn read_tarball(reader: impl AsyncRead + Unpin + Send) -> Result<HashMap<String, Vec<u8>>> {
+    let mut archive = Archive::new(reader);
+    let mut entries = archive.entries().context("failed to read tarball")?;
+    let mut files = HashMap::new();
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry.context("failed to read tarball entry")?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        let mut buf = Vec::new();
+        entry
+            .read_to_end(&mut buf)
+            .await
+            .with_context(|| format!("failed to read `{path}`"))?;
+        files.insert(path, buf);
+    }
+    Ok(files)
+}
+
+/// Returns the blob with digest `digest` of an image layout, verifying its digest
+fn blob<'a>(files: &'a HashMap<String, Vec<u8>>, digest: &str) -> Result<&'a [u8]> {
+    let hex = digest
+        .strip_prefix("sha256:")
+        .with_context(|| format!("unsupported digest `{digest}`"))?;
+    let blob = files
+        .get(&format!("blobs/sha256/{hex}"))
+        .with_context(|| format!("image layout is missing blob `{digest}`"))?;
+    ensure!(
+        format!("{:x}", Sha256::digest(blob)) == hex,
+        "blob `{digest}` does not match its digest"
+    );
+    Ok(blob)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tokio_tar::{Builder, Header};
+
+    fn sha256(data: &[u8]) -> String {
+        format!("sha256:{:x}", Sha256::digest(data))
+    }
+
+    async fn append(builder: &mut Builder<Vec<u8>>, path: &str, data: &[u8]) {
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, data)
+            .await
+            .expect("failed to append to tarball");
+    }
+
+    #[tokio::test]
+    async fn test_seed_cache() {
+        let tempdir = tempfile::tempdir().expect("Unable to create tempdir");
+
+        let layer = b"\0asm\x0d\0\x01\0";
+        let manifest = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": { "mediaType": "application/vnd.wasm.config.v0+json", "digest": sha256(b"{}"), "size": 2 },
+            "layers": [{ "mediaType": "application/wasm", "digest": sha256(layer), "size": layer.len() }],
+        }))
+        .unwrap();
+        let digest = sha256(&manifest);
+        let index = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "manifests": [{
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "digest": digest,
+                "size": manifest.len(),
+                "annotations": { REF_NAME_ANNOTATION: "0.1.0" },
+            }],
+        }))
+        .unwrap();
+
+        let mut builder = Builder::new(Vec::new());
+        append(
+            &mut builder,
+            "oci-layout",
+            br#"{"imageLayoutVersion":"1.0.0"}"#,
+        )
+        .await;
+        append(&mut builder, "index.json", &index).await;
+        for blob in [&manifest[..], layer, b"{}"] {
+            let path = format!("blobs/sha256/{}", &sha256(blob)["sha256:".len()..]);
+            append(&mut builder, &path, blob).await;
+        }
+        let tarball = tempdir.path().join("layout.tar");
+        tokio::fs::write(&tarball, builder.into_inner().await.unwrap())
+            .await
+            .unwrap();
+
+        let cache = OciCache::new(tempdir.path().join("cache"));
+        let seeded = seed_cache(&cache, &tarball, Some("ghcr.io/wasmcloud/components/hello"))
+            .await
+            .expect("failed to seed cache");
+        assert_eq!(
+            seeded,
+            vec![SeededArtifact {
+                reference: Some("ghcr.io/wasmcloud/components/hello:0.1.0".into()),
+                digest: digest.clone(),
+            }]
+        );
+        assert_eq!(
+            cache
+                .resolve("ghcr.io/wasmcloud/components/hello:0.1.0")
+                .await
+                .unwrap(),
+            Some(digest.clone())
+        );
+        let path = cache.get(&digest).await.unwrap().expect("artifact missing");
+        assert_eq!(tokio::fs::read(path).await.unwrap(), layer);
+
+        // Layouts with blobs not matching their digest are rejected
+        let mut builder = Builder::new(Vec::new());
+        append(
+            &mut builder,
+            "oci-layout",
+            br#"{"imageLayoutVersion":"1.0.0"}"#,
+        )
+        .await;
+        append(&mut builder, "index.json", &index).await;
+        let path = format!("blobs/sha256/{}", &digest["sha256:".len()..]);
+        append(&mut builder, &path, b"tampered").await;
+        tokio::fs::write(&tarball, builder.into_inner().await.unwrap())
+            .await
+            .unwrap();
+        assert!(seed_cache(&cache, &tarball, None).await.is_err());
+    }
+}
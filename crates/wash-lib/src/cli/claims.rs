@@ -12,7 +12,7 @@ use std::{
 use tracing::warn;
 use wascap::{
     jwt::{Account, CapabilityProvider, Claims, Component, Operator},
-    wasm::{days_from_now_to_jwt_time, sign_buffer_with_claims},
+    wasm::{days_from_now_to_jwt_time, embed_claims},
 };
 
 use super::{extract_keypair, get::GetClaimsCommand, CommandOutput, OutputKind};
@@ -271,6 +271,10 @@ pub struct ComponentMetadata {
     /// Developer or human friendly unique alias used for invoking an component, consisting of lowercase alphanumeric characters, underscores '_' and slashes '/'
     #[clap(short = 'a', long = "call-alias")]
     pub call_alias: Option<String>,
+    /// Optional path to a JSON schema describing the config expected by the component. Hosts
+    /// validate the config of the component against it
+    #[clap(long = "config-schema")]
+    pub config_schema: Option<PathBuf>,
 
     /// Path to issuer seed key (account). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
     #[clap(
@@ -618,14 +622,13 @@ pub fn sign_file(cmd: SignCommand, output_kind: OutputKind) -> Result<CommandOut
         output_kind,
     )?;
 
-    let signed = sign_buffer_with_claims(
+    let mut claims = Claims::<Component>::with_dates(
         cmd.metadata.name.context("component name is required")?,
-        &buf,
-        &subject,
-        &issuer,
-        cmd.metadata.common.expires_in_days,
-        cmd.metadata.common.not_before_days,
-        cmd.metadata.tags.clone(),
+        issuer.public_key(),
+        subject.public_key(),
+        Some(cmd.metadata.tags.clone()),
+        days_from_now_to_jwt_time(cmd.metadata.common.not_before_days),
+        days_from_now_to_jwt_time(cmd.metadata.common.expires_in_days),
         false,
         Some(
             cmd.metadata
@@ -634,7 +637,17 @@ pub fn sign_file(cmd: SignCommand, output_kind: OutputKind) -> Result<CommandOut
         ),
         Some(cmd.metadata.ver.context("component version is required")?),
         sanitize_alias(cmd.metadata.call_alias)?,
-    )?;
+    );
+    if let Some(path) = &cmd.metadata.config_schema {
+        let bytes = fs::read(path)
+            .with_context(|| format!("failed to read config schema '{}'", path.display()))?;
+        let schema = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Error parsing JSON schema from file '{path:?}'"))?;
+        if let Some(metadata) = claims.metadata.as_mut() {
+            metadata.config_schema = Some(schema);
+        }
+    }
+    let signed = embed_claims(&buf, &claims, &issuer)?;
 
     let destination = cmd.destination.unwrap_or_else(|| {
         let source = Path::new(&cmd.source);
@@ -169,6 +169,32 @@ async fn get_caps(
     Ok(wascap::wasm::extract_claims(artifact_bytes)?)
 }
 
+/// Returns the config schema embedded in the claims of the component or provider archive at
+/// `target`, which is either a path or an OCI reference
+pub async fn get_config_schema(
+    target: &str,
+    options: OciPullOptions,
+) -> Result<Option<serde_json::Value>> {
+    let buf = get_oci_artifact(target.to_string(), Some(cached_oci_file(target)), options).await?;
+    let parsed = wasmparser::Parser::new(0).parse_all(&buf).next();
+    match parsed {
+        Some(Ok(_)) => {
+            let token = wascap::wasm::extract_claims(&buf)?
+                .with_context(|| format!("No capabilities discovered in : {target}"))?;
+            Ok(token
+                .claims
+                .metadata
+                .and_then(|metadata| metadata.config_schema))
+        }
+        _ => {
+            let artifact = ProviderArchive::try_load(&buf)
+                .await
+                .map_err(|e| anyhow!("{}", e))?;
+            Ok(artifact.schema())
+        }
+    }
+}
+
 /// Renders component claims into provided output format
 #[must_use]
 pub fn render_component_claims(
@@ -207,6 +233,9 @@ pub fn render_component_claims(
     map.insert("revision".to_string(), json!(friendly_rev));
     map.insert("tags".to_string(), json!(tags));
     map.insert("name".to_string(), json!(name));
+    if let Some(schema) = &md.config_schema {
+        map.insert("schema".to_string(), json!(schema));
+    }
 
     let mut table = render_core(&claims, validation);
 
@@ -36,6 +36,8 @@ use crate::{
     },
 };
 
+pub mod audit;
+pub mod cache;
 pub mod capture;
 pub mod claims;
 pub mod dev;
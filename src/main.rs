@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::{Arc, LazyLock};
 use std::time::Duration;
@@ -14,11 +15,16 @@ use tracing::{warn, Level as TracingLogLevel};
 use tracing_subscriber::util::SubscriberInitExt as _;
 use wasmcloud_core::logging::Level as WasmcloudLogLevel;
 use wasmcloud_core::{OtelConfig, OtelProtocol};
-use wasmcloud_host::oci::Config as OciConfig;
+use wasmcloud_host::oci::{Config as OciConfig, SignaturePolicy};
 use wasmcloud_host::url::Url;
-use wasmcloud_host::wasmbus::host_config::PolicyService as PolicyServiceConfig;
+use wasmcloud_host::wasmbus::host_config::{
+    AuditLogSink, InvocationRecordingSink, PolicyService as PolicyServiceConfig, ProviderLimits,
+    ProviderRestartPolicy, DEFAULT_COMPONENT_CACHE_MAX_SIZE,
+};
 use wasmcloud_host::WasmbusHostConfig;
-use wasmcloud_tracing::configure_observability;
+use wasmcloud_tracing::{
+    configure_observability, configure_observability_with_prometheus, PrometheusRegistry,
+};
 
 #[derive(Debug, Parser)]
 #[allow(clippy::struct_excessive_bools)]
@@ -77,6 +83,9 @@ struct Args {
     /// Delay, in milliseconds, between requesting a provider shut down and forcibly terminating its process
     #[clap(long = "provider-shutdown-delay-ms", alias = "provider-shutdown-delay", default_value = "300", env = "WASMCLOUD_PROV_SHUTDOWN_DELAY_MS", value_parser = parse_duration_millis)]
     provider_shutdown_delay: Duration,
+    /// Time, in milliseconds, to wait for in-flight invocations to finish when draining the host on SIGTERM
+    #[clap(long = "drain-timeout-ms", default_value = "30000", env = "WASMCLOUD_DRAIN_TIMEOUT_MS", value_parser = parse_duration_millis)]
+    drain_timeout: Duration,
     /// Determines whether OCI images tagged latest are allowed to be pulled from OCI registries and started
     #[clap(long = "allow-latest", env = "WASMCLOUD_OCI_ALLOW_LATEST")]
     allow_latest: bool,
@@ -104,6 +113,13 @@ struct Args {
         env = "WASMCLOUD_ALLOW_FILE_LOAD"
     )]
     allow_file_load: bool,
+    /// Host directory, within which components are allowed to have directories preopened using the `wasmcloud.dev/preopens` annotation or config key, can be specified multiple times. By default, components cannot access the host filesystem
+    #[clap(
+        long = "allow-preopen-path",
+        env = "WASMCLOUD_ALLOWED_PREOPEN_PATHS",
+        value_delimiter = ','
+    )]
+    allowed_preopen_paths: Vec<PathBuf>,
     /// Enable JSON structured logging from the wasmCloud host
     #[clap(
         long = "enable-structured-logging",
@@ -190,6 +206,13 @@ struct Args {
     /// If provided, enables policy checks on start actions and component invocations
     #[clap(long = "policy-topic", env = "WASMCLOUD_POLICY_TOPIC")]
     policy_topic: Option<String>,
+    /// If provided, enables policy checks on start actions and component invocations, decided by the host using the rules in this file instead of a policy server. The file is reloaded whenever it changes
+    #[clap(
+        long = "policy-file",
+        env = "WASMCLOUD_POLICY_FILE",
+        conflicts_with = "policy_topic"
+    )]
+    policy_file: Option<PathBuf>,
     /// If provided, allows the host to subscribe to updates on past policy decisions. Requires `policy_topic` to be set.
     #[clap(
         long = "policy-changes-topic",
@@ -200,7 +223,7 @@ struct Args {
     /// If provided, allows to set a custom Max Execution time for the Host in ms.
     #[clap(long = "max-execution-time-ms", default_value = "600000", env = "WASMCLOUD_MAX_EXECUTION_TIME_MS", value_parser = parse_duration_millis)]
     max_execution_time: Duration,
-    /// The maximum amount of memory bytes that a component can allocate (default 256 MiB)
+    /// The maximum amount of memory bytes that a component can allocate (default 256 MiB). Lower per-component limits can be set using the `wasmcloud.dev/max-memory` annotation
     #[clap(long = "max-linear-memory-bytes", default_value_t = 256 * 1024 * 1024, env = "WASMCLOUD_MAX_LINEAR_MEMORY")]
     max_linear_memory: u64,
     /// The maximum byte size of a component binary that can be loaded (default 50 MiB)
@@ -213,6 +236,141 @@ struct Args {
         env = "WASMCLOUD_MAX_COMPONENTS"
     )]
     max_components: u32,
+    /// Enable fuel metering of component invocations, which allows per-component fuel budgets to be set using the `wasmcloud.dev/max-fuel` annotation
+    #[clap(long = "enable-fuel-metering", env = "WASMCLOUD_FUEL_METERING_ENABLED")]
+    fuel_metering: bool,
+    /// Reject WASI preview 1 core modules instead of adapting them to components when loaded
+    #[clap(long = "deny-core-modules", env = "WASMCLOUD_DENY_CORE_MODULES")]
+    deny_core_modules: bool,
+    /// Adapt WASI preview 1 core modules exporting `_start` using the command adapter instead of the reactor adapter
+    #[clap(
+        long = "adapt-command-modules",
+        env = "WASMCLOUD_ADAPT_COMMAND_MODULES",
+        conflicts_with = "deny_core_modules"
+    )]
+    adapt_command_modules: bool,
+    /// If provided, precompiled components are cached in this directory and reused across host restarts
+    #[clap(long = "component-cache-dir", env = "WASMCLOUD_COMPONENT_CACHE_DIR")]
+    component_cache_dir: Option<PathBuf>,
+    /// The maximum byte size of the precompiled component cache (default 1 GiB). Least recently used entries are evicted first
+    #[clap(
+        long = "component-cache-max-bytes",
+        default_value_t = DEFAULT_COMPONENT_CACHE_MAX_SIZE,
+        env = "WASMCLOUD_COMPONENT_CACHE_MAX_BYTES",
+        requires = "component_cache_dir"
+    )]
+    component_cache_max_size: u64,
+    /// The maximum number of lines written by each component to stdout and stderr, which are logged per second. Excess lines are dropped. Set to 0 to disable the limit
+    #[clap(
+        long = "max-component-output-lines",
+        default_value_t = 100,
+        env = "WASMCLOUD_MAX_COMPONENT_OUTPUT_LINES"
+    )]
+    max_component_output_lines: u32,
+    /// Disable direct dispatch of invocations between components running on this host. If set, all component-to-component invocations go through NATS
+    #[clap(
+        long = "disable-local-component-invocations",
+        env = "WASMCLOUD_DISABLE_LOCAL_COMPONENT_INVOCATIONS"
+    )]
+    disable_local_component_invocations: bool,
+    /// If provided, recordings of invocations of components annotated with `wasmcloud.dev/record-invocations: "true"` are written to this directory
+    #[clap(
+        long = "invocation-recording-dir",
+        env = "WASMCLOUD_INVOCATION_RECORDING_DIR",
+        conflicts_with = "invocation_recording_jetstream"
+    )]
+    invocation_recording_dir: Option<PathBuf>,
+    /// If set, recordings of invocations of components annotated with `wasmcloud.dev/record-invocations: "true"` are published to the `RECORDINGS_{lattice}` JetStream stream
+    #[clap(
+        long = "invocation-recording-jetstream",
+        env = "WASMCLOUD_INVOCATION_RECORDING_JETSTREAM"
+    )]
+    invocation_recording_jetstream: bool,
+    /// If provided, every control interface request handled by the host and its outcome is appended as a line of JSON to this file, with secrets redacted
+    #[clap(
+        long = "audit-log-file",
+        env = "WASMCLOUD_AUDIT_LOG_FILE",
+        conflicts_with = "audit_log_jetstream"
+    )]
+    audit_log_file: Option<PathBuf>,
+    /// If set, every control interface request handled by the host and its outcome is published to the `AUDIT_{lattice}` JetStream stream, with secrets redacted
+    #[clap(long = "audit-log-jetstream", env = "WASMCLOUD_AUDIT_LOG_JETSTREAM")]
+    audit_log_jetstream: bool,
+    /// Default restart policy of capability providers, one of `never` (default), `on-failure` or `always`. Can be overridden per provider using the `wasmcloud.dev/restart-policy` annotation
+    #[clap(
+        long = "provider-restart-policy",
+        default_value = "never",
+        env = "WASMCLOUD_PROVIDER_RESTART_POLICY"
+    )]
+    provider_restart_policy: ProviderRestartPolicy,
+    /// The maximum number of consecutive restarts of a capability provider before it is considered to be crash looping. Can be overridden per provider using the `wasmcloud.dev/max-restarts` annotation
+    #[clap(
+        long = "provider-max-restarts",
+        default_value_t = 5,
+        env = "WASMCLOUD_PROVIDER_MAX_RESTARTS"
+    )]
+    provider_max_restarts: u32,
+    /// Delay before the first restart of an exited capability provider, which is doubled on every consecutive restart
+    #[clap(long = "provider-restart-backoff-ms", default_value = "1000", env = "WASMCLOUD_PROVIDER_RESTART_BACKOFF_MS", value_parser = parse_duration_millis)]
+    provider_restart_backoff: Duration,
+    /// The maximum delay between consecutive restarts of a capability provider
+    #[clap(long = "provider-max-restart-backoff-ms", default_value = "60000", env = "WASMCLOUD_PROVIDER_MAX_RESTART_BACKOFF_MS", value_parser = parse_duration_millis)]
+    provider_max_restart_backoff: Duration,
+    /// The default maximum amount of memory in bytes of each capability provider process. Can be overridden per provider using the `wasmcloud.dev/max-memory` annotation. Only enforced on Linux. Without `provider_cgroup_root`, this limits the virtual address space of the process using `RLIMIT_AS`, which is usually much larger than its resident memory, so providers reserving large address ranges, e.g. Go runtimes, may fail to start with a limit close to their actual memory usage
+    #[clap(
+        long = "provider-max-memory-bytes",
+        env = "WASMCLOUD_PROVIDER_MAX_MEMORY"
+    )]
+    provider_max_memory: Option<u64>,
+    /// The default relative CPU weight (1-10000) of each capability provider process. Can be overridden per provider using the `wasmcloud.dev/cpu-weight` annotation. Requires `provider_cgroup_root` to be set
+    #[clap(
+        long = "provider-cpu-weight",
+        env = "WASMCLOUD_PROVIDER_CPU_WEIGHT",
+        requires = "provider_cgroup_root",
+        value_parser = clap::value_parser!(u64).range(1..=10_000)
+    )]
+    provider_cpu_weight: Option<u64>,
+    /// The default maximum number of files each capability provider process can open. Can be overridden per provider using the `wasmcloud.dev/max-open-files` annotation. Only enforced on Linux
+    #[clap(
+        long = "provider-max-open-files",
+        env = "WASMCLOUD_PROVIDER_MAX_OPEN_FILES"
+    )]
+    provider_max_open_files: Option<u64>,
+    /// The default maximum number of processes and threads of each capability provider. Can be overridden per provider using the `wasmcloud.dev/max-processes` annotation. Only enforced on Linux. Without `provider_cgroup_root`, this is enforced using `RLIMIT_NPROC`, which counts all processes and threads of the user running the host, including the host itself and all other providers, rather than those of the provider only
+    #[clap(
+        long = "provider-max-processes",
+        env = "WASMCLOUD_PROVIDER_MAX_PROCESSES"
+    )]
+    provider_max_processes: Option<u64>,
+    /// If provided, each capability provider process with resource limits is placed in a dedicated cgroup created in this cgroup v2 directory, which must be writable by the host
+    #[clap(long = "provider-cgroup-root", env = "WASMCLOUD_PROVIDER_CGROUP_ROOT")]
+    provider_cgroup_root: Option<PathBuf>,
+    /// If provided, the host serves `/livez`, `/readyz` and `/inventory` HTTP endpoints on this address, for example `0.0.0.0:8081`
+    #[clap(long = "http-admin", env = "WASMCLOUD_HTTP_ADMIN")]
+    http_admin: Option<SocketAddr>,
+    /// If set, host metrics are served in the Prometheus text format on the `/metrics` path of the HTTP admin endpoint, regardless of whether OTEL metrics are enabled
+    #[clap(
+        long = "enable-prometheus-metrics",
+        env = "WASMCLOUD_PROMETHEUS_METRICS_ENABLED",
+        requires = "http_admin"
+    )]
+    enable_prometheus_metrics: bool,
+    /// If provided, the host applies the components, providers, links and named config described by this manifest file, without requiring a control plane, and reconciles them whenever the file changes. NATS is only used for provider RPC, over which control interface events are also published. Data otherwise stored in the JetStream buckets of the lattice, such as links and named config, is kept in memory only and lost when the host stops
+    #[clap(long = "manifest", env = "WASMCLOUD_MANIFEST")]
+    manifest: Option<PathBuf>,
+    /// If enabled, the host records the components and providers it runs and brings them back after a restart. Requires `host_seed` to be set, such that the host keeps its identity across restarts
+    #[clap(
+        long = "rehydrate-workloads",
+        env = "WASMCLOUD_REHYDRATE_WORKLOADS",
+        requires = "host_seed"
+    )]
+    rehydrate_workloads: bool,
+    /// If provided, the host stops bidding in auctions once its utilization reaches this percentage. Utilization is the higher of the share of used component slots and the CPU load
+    #[clap(
+        long = "auction-refusal-threshold",
+        env = "WASMCLOUD_AUCTION_REFUSAL_THRESHOLD"
+    )]
+    auction_refusal_threshold: Option<u32>,
     /// If provided, allows setting a custom timeout for requesting policy decisions. Defaults to one second. Requires `policy_topic` to be set.
     #[clap(
         long = "policy-timeout-ms",
@@ -250,6 +408,40 @@ struct Args {
         requires = "oci_user"
     )]
     oci_password: Option<String>,
+    /// Path to a PEM file with public keys trusted to sign OCI artifacts, can be specified multiple times
+    #[clap(
+        long = "oci-signature-trust-root",
+        env = "WASMCLOUD_OCI_SIGNATURE_TRUST_ROOTS",
+        value_delimiter = ','
+    )]
+    oci_signature_trust_roots: Vec<PathBuf>,
+    /// Policy for verifying signatures of OCI artifacts against the trust roots before running them, one of `enforce`, `warn` or `off`
+    #[clap(
+        long = "oci-signature-policy",
+        default_value = "off",
+        env = "WASMCLOUD_OCI_SIGNATURE_POLICY"
+    )]
+    oci_signature_policy: SignaturePolicy,
+    /// Signature policy of a specific OCI registry in the form `registry=policy`, overriding `--oci-signature-policy`, can be specified multiple times
+    #[clap(
+        long = "oci-registry-signature-policy",
+        env = "WASMCLOUD_OCI_REGISTRY_SIGNATURE_POLICIES",
+        value_delimiter = ',',
+        value_parser = parse_registry_signature_policy
+    )]
+    oci_registry_signature_policies: Vec<(String, SignaturePolicy)>,
+    /// Directory, in which fetched OCI artifacts are cached. Defaults to a directory in the temporary directory of the system
+    #[clap(long = "oci-cache-dir", env = "WASMCLOUD_OCI_CACHE_DIR")]
+    oci_cache_dir: Option<PathBuf>,
+    /// Only run OCI artifacts already present in the OCI cache, without contacting registries
+    #[clap(long = "oci-offline", env = "WASMCLOUD_OCI_OFFLINE")]
+    oci_offline: bool,
+    /// Size of the OCI cache in bytes, above which cached artifacts not referenced by running components or providers are evicted. By default, the cache is not garbage collected
+    #[clap(
+        long = "oci-cache-max-size-bytes",
+        env = "WASMCLOUD_OCI_CACHE_MAX_SIZE"
+    )]
+    oci_cache_max_size: Option<u64>,
 
     /// Determines whether observability should be enabled.
     #[clap(
@@ -354,14 +546,28 @@ async fn main() -> anyhow::Result<()> {
     };
     let log_level = WasmcloudLogLevel::from(args.log_level);
 
-    let _guard = match configure_observability(
-        "wasmcloud-host",
-        &otel_config,
-        args.enable_structured_logging,
-        args.flame_graph,
-        Some(&log_level),
-        Some(&otel_config.trace_level),
-    ) {
+    let prometheus_registry = args.enable_prometheus_metrics.then(PrometheusRegistry::new);
+    let observability = if let Some(registry) = &prometheus_registry {
+        configure_observability_with_prometheus(
+            "wasmcloud-host",
+            &otel_config,
+            args.enable_structured_logging,
+            args.flame_graph,
+            Some(&log_level),
+            Some(&otel_config.trace_level),
+            registry,
+        )
+    } else {
+        configure_observability(
+            "wasmcloud-host",
+            &otel_config,
+            args.enable_structured_logging,
+            args.flame_graph,
+            Some(&log_level),
+            Some(&otel_config.trace_level),
+        )
+    };
+    let _guard = match observability {
         Ok((dispatch, guard)) => {
             dispatch
                 .try_init()
@@ -411,6 +617,12 @@ async fn main() -> anyhow::Result<()> {
         oci_registry: args.oci_registry,
         oci_user: args.oci_user,
         oci_password: args.oci_password,
+        signature_trust_roots: args.oci_signature_trust_roots,
+        signature_policy: args.oci_signature_policy,
+        registry_signature_policies: args.oci_registry_signature_policies.into_iter().collect(),
+        cache_dir: args.oci_cache_dir,
+        offline: args.oci_offline,
+        cache_max_size: args.oci_cache_max_size,
     };
     if let Some(policy_topic) = args.policy_topic.as_deref() {
         anyhow::ensure!(
@@ -422,6 +634,7 @@ async fn main() -> anyhow::Result<()> {
         policy_topic: args.policy_topic,
         policy_changes_topic: args.policy_changes_topic,
         policy_timeout_ms: args.policy_timeout_ms,
+        policy_file: args.policy_file,
     };
     let mut labels = args
         .label
@@ -471,6 +684,7 @@ async fn main() -> anyhow::Result<()> {
         rpc_key: rpc_key.or_else(|| nats_key.clone()),
         rpc_tls: args.rpc_tls,
         allow_file_load: args.allow_file_load,
+        allowed_preopen_paths: args.allowed_preopen_paths,
         log_level,
         enable_structured_logging: args.enable_structured_logging,
         otel_config,
@@ -481,7 +695,41 @@ async fn main() -> anyhow::Result<()> {
         max_linear_memory: args.max_linear_memory,
         max_component_size: args.max_component_size,
         max_components: args.max_components,
+        fuel_metering: args.fuel_metering,
+        allow_core_modules: !args.deny_core_modules,
+        adapt_command_modules: args.adapt_command_modules,
         heartbeat_interval: args.heartbeat_interval,
+        component_cache_dir: args.component_cache_dir,
+        component_cache_max_size: args.component_cache_max_size,
+        max_component_output_lines: args.max_component_output_lines,
+        local_component_invocations: !args.disable_local_component_invocations,
+        invocation_recordings: if args.invocation_recording_jetstream {
+            Some(InvocationRecordingSink::JetStream)
+        } else {
+            args.invocation_recording_dir
+                .map(InvocationRecordingSink::Directory)
+        },
+        provider_restart_policy: args.provider_restart_policy,
+        provider_max_restarts: args.provider_max_restarts,
+        provider_restart_backoff: args.provider_restart_backoff,
+        provider_max_restart_backoff: args.provider_max_restart_backoff,
+        provider_limits: ProviderLimits {
+            max_memory: args.provider_max_memory,
+            cpu_weight: args.provider_cpu_weight,
+            max_open_files: args.provider_max_open_files,
+            max_processes: args.provider_max_processes,
+        },
+        provider_cgroup_root: args.provider_cgroup_root,
+        http_admin: args.http_admin,
+        prometheus_registry,
+        manifest: args.manifest,
+        rehydrate_workloads: args.rehydrate_workloads,
+        auction_refusal_threshold: args.auction_refusal_threshold,
+        audit_log: if args.audit_log_jetstream {
+            Some(AuditLogSink::JetStream)
+        } else {
+            args.audit_log_file.map(AuditLogSink::File)
+        },
     }))
     .await
     .context("failed to initialize host")?;
@@ -493,7 +741,10 @@ async fn main() -> anyhow::Result<()> {
                 sig.context("failed to wait for Ctrl-C")?;
                 None
             },
-            _ = terminate.recv() => None,
+            _ = terminate.recv() => {
+                host.drain(Some(args.drain_timeout)).await;
+                None
+            },
             deadline = host.stopped() => deadline?,
         }
     };
@@ -550,6 +801,13 @@ fn parse_label(labelpair: &str) -> anyhow::Result<(String, String)> {
     }
 }
 
+fn parse_registry_signature_policy(arg: &str) -> anyhow::Result<(String, SignaturePolicy)> {
+    let (registry, policy) = arg.split_once('=').with_context(|| {
+        format!("invalid registry signature policy format `{arg}`. Expected `registry=policy`")
+    })?;
+    Ok((registry.to_string(), policy.parse()?))
+}
+
 static JWT_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"-----BEGIN NATS USER JWT-----\n(?<jwt>.*)\n------END NATS USER JWT------").unwrap()
 });